@@ -0,0 +1,219 @@
+//! A minimal CBOR decoder for certificates, borrowing byte and text strings from the input,
+//! and an encoder for the witnesses sent in HTTP headers.
+
+use crate::hashtree::HashTree;
+use std::borrow::Cow;
+
+const SELF_DESCRIBE_TAG: u64 = 55799;
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Value<'a> {
+    Uint(u64),
+    Bytes(&'a [u8]),
+    Text(&'a str),
+    Array(Vec<Value<'a>>),
+    Map(Vec<(Value<'a>, Value<'a>)>),
+}
+
+impl<'a> Value<'a> {
+    pub(crate) fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Field `key` of a map with text keys.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| *k == Value::Text(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes a single CBOR item spanning all of `bytes`, skipping the self-describe tag.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Value<'_>, String> {
+    let mut decoder = Decoder { bytes, pos: 0 };
+    let value = decoder.value(0)?;
+    if decoder.pos != bytes.len() {
+        return Err(format!("trailing bytes at offset {}", decoder.pos));
+    }
+    Ok(value)
+}
+
+/// Converts a decoded value to a [`HashTree`], as encoded by its `Serialize` implementation.
+pub(crate) fn hash_tree<'a>(value: &Value<'a>) -> Result<HashTree<'a>, String> {
+    let Value::Array(items) = value else {
+        return Err("a hash tree node must be an array".to_string());
+    };
+    let bytes = |i: usize| {
+        items
+            .get(i)
+            .and_then(Value::as_bytes)
+            .ok_or_else(|| "malformed hash tree node".to_string())
+    };
+    match (items.first(), items.len()) {
+        (Some(Value::Uint(0)), 1) => Ok(HashTree::Empty),
+        (Some(Value::Uint(1)), 3) => Ok(crate::fork(hash_tree(&items[1])?, hash_tree(&items[2])?)),
+        (Some(Value::Uint(2)), 3) => Ok(crate::labeled(bytes(1)?, hash_tree(&items[2])?)),
+        (Some(Value::Uint(3)), 2) => Ok(HashTree::Leaf(Cow::Borrowed(bytes(1)?))),
+        (Some(Value::Uint(4)), 2) => {
+            let hash = bytes(1)?
+                .try_into()
+                .map_err(|_| "a pruned hash must be 32 bytes long".to_string())?;
+            Ok(HashTree::Pruned(hash))
+        }
+        _ => Err("malformed hash tree node".to_string()),
+    }
+}
+
+/// Encodes a hash tree like its `Serialize` implementation, after the self-describe tag.
+pub(crate) fn encode_hash_tree(tree: &HashTree<'_>) -> Vec<u8> {
+    fn go(out: &mut Vec<u8>, tree: &HashTree<'_>) {
+        match tree {
+            HashTree::Empty => {
+                head(out, 4, 1);
+                head(out, 0, 0);
+            }
+            HashTree::Fork(p) => {
+                head(out, 4, 3);
+                head(out, 0, 1);
+                go(out, &p.0);
+                go(out, &p.1);
+            }
+            HashTree::Labeled(label, tree) => {
+                head(out, 4, 3);
+                head(out, 0, 2);
+                bytes(out, 2, label);
+                go(out, tree);
+            }
+            HashTree::Leaf(leaf) => {
+                head(out, 4, 2);
+                head(out, 0, 3);
+                bytes(out, 2, leaf);
+            }
+            HashTree::Pruned(digest) => {
+                head(out, 4, 2);
+                head(out, 0, 4);
+                bytes(out, 2, digest);
+            }
+        }
+    }
+    let mut out = vec![];
+    head(&mut out, 6, SELF_DESCRIBE_TAG);
+    go(&mut out, tree);
+    out
+}
+
+/// Encodes an array of text strings, after the self-describe tag.
+pub(crate) fn encode_text_array(items: &[&str]) -> Vec<u8> {
+    let mut out = vec![];
+    head(&mut out, 6, SELF_DESCRIBE_TAG);
+    head(&mut out, 4, items.len() as u64);
+    for item in items {
+        bytes(&mut out, 3, item.as_bytes());
+    }
+    out
+}
+
+fn head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match arg {
+        0..=23 => out.push(major | arg as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, arg as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+fn bytes(out: &mut Vec<u8>, major: u8, bytes: &[u8]) {
+    head(out, major, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "unexpected end of input".to_string())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    // Reads the major type and argument of an item header.
+    fn header(&mut self) -> Result<(u8, u64), String> {
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let arg = match initial & 0x1f {
+            n @ 0..=23 => u64::from(n),
+            24 => u64::from(self.take(1)?[0]),
+            25 => u64::from(u16::from_be_bytes(self.take(2)?.try_into().unwrap())),
+            26 => u64::from(u32::from_be_bytes(self.take(4)?.try_into().unwrap())),
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            _ => return Err(format!("unsupported CBOR item at offset {}", self.pos - 1)),
+        };
+        Ok((major, arg))
+    }
+
+    fn len(arg: u64) -> Result<usize, String> {
+        usize::try_from(arg).map_err(|_| "CBOR length too large".to_string())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value<'a>, String> {
+        if depth > MAX_DEPTH {
+            return Err("CBOR nesting too deep".to_string());
+        }
+        let start = self.pos;
+        let (major, arg) = self.header()?;
+        match major {
+            0 => Ok(Value::Uint(arg)),
+            2 => Ok(Value::Bytes(self.take(Self::len(arg)?)?)),
+            3 => std::str::from_utf8(self.take(Self::len(arg)?)?)
+                .map(Value::Text)
+                .map_err(|e| e.to_string()),
+            4 => {
+                let len = Self::len(arg)?;
+                // Every item takes at least one byte.
+                let mut items = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+                for _ in 0..len {
+                    items.push(self.value(depth + 1)?);
+                }
+                Ok(Value::Array(items))
+            }
+            5 => {
+                let len = Self::len(arg)?;
+                let mut entries = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+                for _ in 0..len {
+                    let key = self.value(depth + 1)?;
+                    entries.push((key, self.value(depth + 1)?));
+                }
+                Ok(Value::Map(entries))
+            }
+            6 if arg == SELF_DESCRIBE_TAG => self.value(depth + 1),
+            _ => Err(format!("unsupported CBOR item at offset {start}")),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! Verification of certificates and the witnesses of certified data.
+
+#[cfg(test)]
+mod test;
+
+use crate::cbor::{self, Value};
+use crate::hashtree::{HashTree, LookupResult};
+use candid::Principal;
+use std::fmt::{self, Write};
+
+/// DER prefix of a BLS12-381 public key, as found in the root key and subnet public keys.
+const DER_PREFIX: [u8; 37] = [
+    0x30, 0x81, 0x82, 0x30, 0x1d, 0x06, 0x0d, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0xdc, 0x7c, 0x05,
+    0x03, 0x01, 0x02, 0x01, 0x06, 0x0c, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0xdc, 0x7c, 0x05, 0x03,
+    0x02, 0x01, 0x03, 0x61, 0x00,
+];
+const KEY_LENGTH: usize = 96;
+
+/// The DER-encoded public key of the IC mainnet, which signs the certificates of all subnets.
+pub const IC_ROOT_KEY: &[u8; 133] = b"\x30\x81\x82\x30\x1d\x06\x0d\x2b\x06\x01\x04\x01\x82\xdc\x7c\x05\x03\x01\x02\x01\x06\x0c\x2b\x06\x01\x04\x01\x82\xdc\x7c\x05\x03\x02\x01\x03\x61\x00\x81\x4c\x0e\x6e\xc7\x1f\xab\x58\x3b\x08\xbd\x81\x37\x3c\x25\x5c\x3c\x37\x1b\x2e\x84\x86\x3c\x98\xa4\xf1\xe0\x8b\x74\x23\x5d\x14\xfb\x5d\x9c\x0c\xd5\x46\xd9\x68\x5f\x91\x3a\x0c\x0b\x2c\xc5\x34\x15\x83\xbf\x4b\x43\x92\xe4\x67\xdb\x96\xd6\x5b\x9b\xb4\xcb\x71\x71\x12\xf8\x47\x2e\x0d\x5a\x4d\x14\x50\x5f\xfd\x74\x84\xb0\x12\x91\x09\x1c\x5f\x87\xb9\x88\x83\x46\x3f\x98\x09\x1a\x0b\xaa\xae";
+
+// Checks a BLS signature of a message with a public key.
+type VerifyBls<'f> = dyn Fn(&[u8], &[u8], &[u8]) -> bool + 'f;
+
+/// Domain separator of the message signed by the IC: the root hash of the certificate tree.
+const STATE_ROOT_DOMAIN: &[u8] = b"\x0dic-state-root";
+
+/// A certificate, as defined in the [interfaces spec](https://internetcomputer.org/docs/current/references/ic-interface-spec#certificate).
+#[derive(Debug, Clone)]
+pub struct Certificate<'a> {
+    /// The certified state tree.
+    pub tree: HashTree<'a>,
+    /// BLS signature of the root hash of `tree`.
+    pub signature: &'a [u8],
+    /// Delegation from the root key to the key of the subnet signing the certificate.
+    pub delegation: Option<Delegation<'a>>,
+}
+
+/// Delegation of the signing authority to a subnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delegation<'a> {
+    /// Id of the subnet the authority is delegated to.
+    pub subnet_id: &'a [u8],
+    /// CBOR-encoded certificate, signed with the root key, of the subnet public key and canister ranges.
+    pub certificate: &'a [u8],
+}
+
+/// Reasons for a certificate to fail the verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertificateError {
+    /// The certificate doesn't decode, or doesn't have the structure of a certificate.
+    Malformed(String),
+    /// A path the verification needs is absent or pruned from the certificate tree.
+    MissingPath(String),
+    /// A public key is not a DER-encoded BLS12-381 key.
+    InvalidPublicKey,
+    /// The signature doesn't match the root hash of the certificate tree.
+    InvalidSignature,
+    /// The certificate of a delegation itself has a delegation.
+    NestedDelegation,
+    /// The delegated subnet isn't allowed to certify the data of the canister.
+    CanisterNotInRange,
+    /// The certified data differs from the root hash of the witness.
+    CertifiedDataMismatch,
+}
+
+impl fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(e) => write!(f, "malformed certificate: {e}"),
+            Self::MissingPath(path) => write!(f, "the certificate has no value at {path}"),
+            Self::InvalidPublicKey => f.write_str("invalid BLS public key"),
+            Self::InvalidSignature => f.write_str("invalid certificate signature"),
+            Self::NestedDelegation => f.write_str("the delegation certificate has a delegation"),
+            Self::CanisterNotInRange => {
+                f.write_str("the canister is not in the canister ranges of the delegated subnet")
+            }
+            Self::CertifiedDataMismatch => {
+                f.write_str("the certified data doesn't match the root hash of the witness")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CertificateError {}
+
+impl<'a> Certificate<'a> {
+    /// Decodes a CBOR-encoded certificate, borrowing from `bytes`.
+    pub fn from_cbor(bytes: &'a [u8]) -> Result<Self, CertificateError> {
+        let value = cbor::decode(bytes).map_err(CertificateError::Malformed)?;
+        let tree = cbor::hash_tree(field(&value, "tree")?).map_err(CertificateError::Malformed)?;
+        let signature = bytes_field(&value, "signature")?;
+        let delegation = match value.get("delegation") {
+            Some(delegation) => Some(Delegation {
+                subnet_id: bytes_field(delegation, "subnet_id")?,
+                certificate: bytes_field(delegation, "certificate")?,
+            }),
+            None => None,
+        };
+        Ok(Self {
+            tree,
+            signature,
+            delegation,
+        })
+    }
+
+    /// Checks that the certificate is signed by the IC, whose public key is `root_key`, e.g.
+    /// [`IC_ROOT_KEY`], and may certify the state of `canister_id`.
+    #[cfg(feature = "verify-bls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "verify-bls")))]
+    pub fn verify(&self, canister_id: &Principal, root_key: &[u8]) -> Result<(), CertificateError> {
+        self.verify_inner(canister_id, root_key, &verify_bls_signature)
+    }
+
+    /// Like `verify`, available with the `verify-bls` feature, with the BLS signatures checked by
+    /// `verify_bls`, e.g. with another pairing library.
+    ///
+    /// `verify_bls` checks a BLS signature of a message with a 96-byte public key. The
+    /// certificate is only as trustworthy as this check.
+    pub fn verify_with_bls(
+        &self,
+        canister_id: &Principal,
+        root_key: &[u8],
+        verify_bls: impl Fn(&[u8], &[u8], &[u8]) -> bool,
+    ) -> Result<(), CertificateError> {
+        self.verify_inner(canister_id, root_key, &verify_bls)
+    }
+
+    fn verify_inner(
+        &self,
+        canister_id: &Principal,
+        root_key: &[u8],
+        verify_bls: &VerifyBls<'_>,
+    ) -> Result<(), CertificateError> {
+        match &self.delegation {
+            None => self.verify_signature(extract_der(root_key)?, verify_bls),
+            Some(delegation) => {
+                let certificate = Certificate::from_cbor(delegation.certificate)?;
+                if certificate.delegation.is_some() {
+                    return Err(CertificateError::NestedDelegation);
+                }
+                certificate.verify_inner(canister_id, root_key, verify_bls)?;
+                let subnet_id = delegation.subnet_id;
+                let ranges = certificate.lookup(&[b"subnet", subnet_id, b"canister_ranges"])?;
+                if !canister_in_ranges(canister_id, ranges)? {
+                    return Err(CertificateError::CanisterNotInRange);
+                }
+                let key = certificate.lookup(&[b"subnet", subnet_id, b"public_key"])?;
+                self.verify_signature(extract_der(key)?, verify_bls)
+            }
+        }
+    }
+
+    fn verify_signature(
+        &self,
+        key: &[u8],
+        verify_bls: &VerifyBls<'_>,
+    ) -> Result<(), CertificateError> {
+        let mut message = STATE_ROOT_DOMAIN.to_vec();
+        message.extend_from_slice(&self.tree.reconstruct());
+        if verify_bls(self.signature, &message, key) {
+            Ok(())
+        } else {
+            Err(CertificateError::InvalidSignature)
+        }
+    }
+
+    /// The certified data of `canister_id`, set with `set_certified_data`.
+    ///
+    /// This doesn't verify the certificate.
+    pub fn certified_data(&self, canister_id: &Principal) -> Result<&[u8], CertificateError> {
+        self.lookup(&[b"canister", canister_id.as_slice(), b"certified_data"])
+    }
+
+    /// The time of the certificate, in nanoseconds since 1970-01-01.
+    ///
+    /// This doesn't verify the certificate. Compare it to the current time to reject stale
+    /// certificates, e.g. older than 5 minutes.
+    pub fn time(&self) -> Result<u64, CertificateError> {
+        let bytes = self.lookup(&[b"time"])?;
+        let mut time = 0u64;
+        for (i, byte) in bytes.iter().enumerate() {
+            if i >= 10 {
+                break;
+            }
+            time |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(time);
+            }
+        }
+        Err(CertificateError::Malformed(
+            "`/time` is not a LEB128 integer".to_string(),
+        ))
+    }
+
+    fn lookup(&self, path: &[&[u8]]) -> Result<&'_ [u8], CertificateError> {
+        match self.tree.lookup_path(path) {
+            LookupResult::Found(value) => Ok(value),
+            LookupResult::Absent | LookupResult::Unknown | LookupResult::Error => {
+                Err(CertificateError::MissingPath(display_path(path)))
+            }
+        }
+    }
+}
+
+/// Verifies a certificate and the witness of the certified data of `canister_id`.
+///
+/// `certificate` is the one returned by [`data_certificate`] in the query serving `witness`,
+/// e.g. a [`witness`](crate::RbTree::witness) of the certified map. The certificate must be
+/// signed by the IC, whose public key is `root_key`, possibly through a subnet delegation, and
+/// certify the root hash of `witness`. Returns the decoded certificate, e.g. to check its
+/// [`time`](Certificate::time).
+///
+/// ```ignore
+/// let certificate = verify_certified_data(
+///     &response.certificate,
+///     &witness,
+///     &canister_id,
+///     IC_ROOT_KEY,
+/// )?;
+/// ```
+///
+/// The BLS signatures are checked with the [`ic-verify-bls-signature`] crate. Without the
+/// `verify-bls` feature, which keeps the pairing library out of the canisters that don't need
+/// it, use [`verify_certified_data_with_bls`].
+///
+/// [`data_certificate`]: https://docs.rs/ic-cdk/latest/ic_cdk/api/fn.data_certificate.html
+/// [`ic-verify-bls-signature`]: https://docs.rs/ic-verify-bls-signature
+#[cfg(feature = "verify-bls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "verify-bls")))]
+pub fn verify_certified_data<'a>(
+    certificate: &'a [u8],
+    witness: &HashTree<'_>,
+    canister_id: &Principal,
+    root_key: &[u8],
+) -> Result<Certificate<'a>, CertificateError> {
+    verify_certified_data_with_bls(
+        certificate,
+        witness,
+        canister_id,
+        root_key,
+        verify_bls_signature,
+    )
+}
+
+/// Like `verify_certified_data`, available with the `verify-bls` feature, with the BLS signatures
+/// checked by `verify_bls`.
+///
+/// `verify_bls` checks a BLS signature of a message with a 96-byte public key. The
+/// certificate is only as trustworthy as this check.
+pub fn verify_certified_data_with_bls<'a>(
+    certificate: &'a [u8],
+    witness: &HashTree<'_>,
+    canister_id: &Principal,
+    root_key: &[u8],
+    verify_bls: impl Fn(&[u8], &[u8], &[u8]) -> bool,
+) -> Result<Certificate<'a>, CertificateError> {
+    let certificate = Certificate::from_cbor(certificate)?;
+    certificate.verify_with_bls(canister_id, root_key, verify_bls)?;
+    if certificate.certified_data(canister_id)? != witness.reconstruct() {
+        return Err(CertificateError::CertifiedDataMismatch);
+    }
+    Ok(certificate)
+}
+
+#[cfg(feature = "verify-bls")]
+fn verify_bls_signature(signature: &[u8], message: &[u8], key: &[u8]) -> bool {
+    ic_verify_bls_signature::verify_bls_signature(signature, message, key).is_ok()
+}
+
+fn field<'v, 'a>(value: &'v Value<'a>, name: &str) -> Result<&'v Value<'a>, CertificateError> {
+    value
+        .get(name)
+        .ok_or_else(|| CertificateError::Malformed(format!("missing field `{name}`")))
+}
+
+fn bytes_field<'a>(value: &Value<'a>, name: &str) -> Result<&'a [u8], CertificateError> {
+    field(value, name)?
+        .as_bytes()
+        .ok_or_else(|| CertificateError::Malformed(format!("`{name}` is not a byte string")))
+}
+
+fn extract_der(key: &[u8]) -> Result<&[u8], CertificateError> {
+    match key.strip_prefix(&DER_PREFIX[..]) {
+        Some(key) if key.len() == KEY_LENGTH => Ok(key),
+        _ => Err(CertificateError::InvalidPublicKey),
+    }
+}
+
+// The ranges are a CBOR array of inclusive `[first, last]` pairs of canister ids.
+fn canister_in_ranges(canister_id: &Principal, ranges: &[u8]) -> Result<bool, CertificateError> {
+    let malformed = || CertificateError::Malformed("invalid canister ranges".to_string());
+    let Value::Array(ranges) = cbor::decode(ranges).map_err(CertificateError::Malformed)? else {
+        return Err(malformed());
+    };
+    let id = canister_id.as_slice();
+    for range in &ranges {
+        match range {
+            Value::Array(bounds) if bounds.len() == 2 => {
+                let first = bounds[0].as_bytes().ok_or_else(malformed)?;
+                let last = bounds[1].as_bytes().ok_or_else(malformed)?;
+                if first <= id && id <= last {
+                    return Ok(true);
+                }
+            }
+            _ => return Err(malformed()),
+        }
+    }
+    Ok(false)
+}
+
+fn display_path(path: &[&[u8]]) -> String {
+    path.iter()
+        .map(|label| match std::str::from_utf8(label) {
+            Ok(s) if s.chars().all(|c| c.is_ascii_graphic()) => format!("/{s}"),
+            _ => label.iter().fold("/0x".to_string(), |mut s, b| {
+                let _ = write!(s, "{b:02x}");
+                s
+            }),
+        })
+        .collect()
+}
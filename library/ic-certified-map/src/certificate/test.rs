@@ -0,0 +1,377 @@
+use super::*;
+use crate::{fork, labeled, AsHashTree, RbTree};
+use serde_cbor::Value as Cbor;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+
+const CANISTER_ID: Principal = Principal::from_slice(&[0, 0, 0, 0, 0, 0, 0, 7, 1, 1]);
+const SUBNET_ID: &[u8] = &[42; 29];
+
+fn der(key: &[u8; 96]) -> Vec<u8> {
+    [&DER_PREFIX[..], &key[..]].concat()
+}
+
+// Stands in for BLS: the signature is the hash of the key and the message.
+fn sign(key: &[u8], message: &[u8]) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(key)
+        .chain_update(message)
+        .finalize()
+        .to_vec()
+}
+
+fn verify_bls(signature: &[u8], message: &[u8], key: &[u8]) -> bool {
+    signature == sign(key, message)
+}
+
+fn leaf(data: &[u8]) -> HashTree<'_> {
+    HashTree::Leaf(Cow::Borrowed(data))
+}
+
+fn certificate(tree: &HashTree<'_>, key: &[u8; 96], delegation: Option<Cbor>) -> Vec<u8> {
+    let mut message = STATE_ROOT_DOMAIN.to_vec();
+    message.extend_from_slice(&tree.reconstruct());
+    let mut fields = vec![
+        (
+            Cbor::Text("tree".to_string()),
+            serde_cbor::value::to_value(tree).unwrap(),
+        ),
+        (
+            Cbor::Text("signature".to_string()),
+            Cbor::Bytes(sign(key, &message)),
+        ),
+    ];
+    fields.extend(delegation.map(|d| (Cbor::Text("delegation".to_string()), d)));
+    let value = Cbor::Tag(55799, Box::new(Cbor::Map(fields.into_iter().collect())));
+    serde_cbor::to_vec(&value).unwrap()
+}
+
+fn delegation(root_key: &[u8; 96], subnet_key: &[u8; 96], ranges: &[(&[u8], &[u8])]) -> Cbor {
+    let ranges = Cbor::Array(
+        ranges
+            .iter()
+            .map(|(first, last)| {
+                Cbor::Array(vec![
+                    Cbor::Bytes(first.to_vec()),
+                    Cbor::Bytes(last.to_vec()),
+                ])
+            })
+            .collect(),
+    );
+    let ranges = serde_cbor::to_vec(&ranges).unwrap();
+    let public_key = der(subnet_key);
+    let tree = labeled(
+        b"subnet",
+        labeled(
+            SUBNET_ID,
+            fork(
+                labeled(b"canister_ranges", leaf(&ranges)),
+                labeled(b"public_key", leaf(&public_key)),
+            ),
+        ),
+    );
+    Cbor::Map(
+        [
+            (
+                Cbor::Text("subnet_id".to_string()),
+                Cbor::Bytes(SUBNET_ID.to_vec()),
+            ),
+            (
+                Cbor::Text("certificate".to_string()),
+                Cbor::Bytes(certificate(&tree, root_key, None)),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    )
+}
+
+// A state tree certifying `data` for `CANISTER_ID` at time 1_000_000_000 (LEB128-encoded).
+fn state_tree(data: &[u8]) -> HashTree<'_> {
+    fork(
+        labeled(
+            b"canister",
+            labeled(
+                CANISTER_ID.as_slice(),
+                labeled(b"certified_data", leaf(data)),
+            ),
+        ),
+        labeled(b"time", leaf(&[0x80, 0x94, 0xeb, 0xdc, 0x03])),
+    )
+}
+
+fn map() -> RbTree<&'static str, Vec<u8>> {
+    let mut map = RbTree::new();
+    map.insert("a", b"alpha".to_vec());
+    map.insert("b", b"beta".to_vec());
+    map.insert("c", b"gamma".to_vec());
+    map
+}
+
+#[test]
+fn test_verify_root_certificate() {
+    let root_key = [1; 96];
+    let map = map();
+    let root_hash = map.root_hash();
+    let bytes = certificate(&state_tree(&root_hash), &root_key, None);
+
+    let certificate = verify_certified_data_with_bls(
+        &bytes,
+        &map.witness(b"b"),
+        &CANISTER_ID,
+        &der(&root_key),
+        verify_bls,
+    )
+    .unwrap();
+    assert_eq!(certificate.time(), Ok(1_000_000_000));
+    assert_eq!(certificate.certified_data(&CANISTER_ID), Ok(&root_hash[..]));
+    assert!(certificate.delegation.is_none());
+
+    assert_eq!(
+        verify_certified_data_with_bls(
+            &bytes,
+            &map.witness(b"b"),
+            &CANISTER_ID,
+            &der(&[2; 96]),
+            verify_bls
+        )
+        .unwrap_err(),
+        CertificateError::InvalidSignature
+    );
+    assert_eq!(
+        verify_certified_data_with_bls(
+            &bytes,
+            &map.witness(b"b"),
+            &CANISTER_ID,
+            &[1; 96],
+            verify_bls
+        )
+        .unwrap_err(),
+        CertificateError::InvalidPublicKey
+    );
+    let other = Principal::from_slice(&[0, 0, 0, 0, 0, 0, 0, 8, 1, 1]);
+    assert_eq!(
+        verify_certified_data_with_bls(
+            &bytes,
+            &map.witness(b"b"),
+            &other,
+            &der(&root_key),
+            verify_bls
+        )
+        .unwrap_err(),
+        CertificateError::MissingPath(
+            "/canister/0x00000000000000080101/certified_data".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_witness_mismatch() {
+    let root_key = [1; 96];
+    let map = map();
+    let bytes = certificate(&state_tree(&map.root_hash()), &root_key, None);
+    let mut other = RbTree::new();
+    other.insert("b", b"beta".to_vec());
+    assert_eq!(
+        verify_certified_data_with_bls(
+            &bytes,
+            &other.witness(b"b"),
+            &CANISTER_ID,
+            &der(&root_key),
+            verify_bls
+        )
+        .unwrap_err(),
+        CertificateError::CertifiedDataMismatch
+    );
+}
+
+#[test]
+fn test_verify_delegation() {
+    let (root_key, subnet_key) = ([1; 96], [2; 96]);
+    let root_hash = map().root_hash();
+    let tree = state_tree(&root_hash);
+    let ranges: &[(&[u8], &[u8])] = &[
+        (
+            &[0, 0, 0, 0, 0, 0, 0, 0, 1, 1],
+            &[0, 0, 0, 0, 0, 0, 0, 5, 1, 1],
+        ),
+        (
+            &[0, 0, 0, 0, 0, 0, 0, 6, 1, 1],
+            &[0, 0, 0, 0, 0, 0, 0, 9, 1, 1],
+        ),
+    ];
+
+    let bytes = certificate(
+        &tree,
+        &subnet_key,
+        Some(delegation(&root_key, &subnet_key, ranges)),
+    );
+    let certificate = verify_certified_data_with_bls(
+        &bytes,
+        &map().witness(b"a"),
+        &CANISTER_ID,
+        &der(&root_key),
+        verify_bls,
+    )
+    .unwrap();
+    assert_eq!(certificate.delegation.unwrap().subnet_id, SUBNET_ID);
+
+    // The subnet key must be certified by the root key.
+    let bytes = self::certificate(
+        &tree,
+        &subnet_key,
+        Some(delegation(&[3; 96], &subnet_key, ranges)),
+    );
+    assert_eq!(
+        Certificate::from_cbor(&bytes).unwrap().verify_with_bls(
+            &CANISTER_ID,
+            &der(&root_key),
+            verify_bls
+        ),
+        Err(CertificateError::InvalidSignature)
+    );
+
+    // The certificate must be signed by the subnet key.
+    let bytes = self::certificate(
+        &tree,
+        &root_key,
+        Some(delegation(&root_key, &subnet_key, ranges)),
+    );
+    assert_eq!(
+        Certificate::from_cbor(&bytes).unwrap().verify_with_bls(
+            &CANISTER_ID,
+            &der(&root_key),
+            verify_bls
+        ),
+        Err(CertificateError::InvalidSignature)
+    );
+
+    let bytes = self::certificate(
+        &tree,
+        &subnet_key,
+        Some(delegation(&root_key, &subnet_key, &ranges[..1])),
+    );
+    assert_eq!(
+        Certificate::from_cbor(&bytes).unwrap().verify_with_bls(
+            &CANISTER_ID,
+            &der(&root_key),
+            verify_bls
+        ),
+        Err(CertificateError::CanisterNotInRange)
+    );
+}
+
+#[test]
+fn test_nested_delegation() {
+    let root_key = [1; 96];
+    let tree = state_tree(&[0; 32]);
+    let inner = certificate(
+        &tree,
+        &root_key,
+        Some(delegation(&root_key, &root_key, &[])),
+    );
+    let outer = Cbor::Map(
+        [
+            (
+                Cbor::Text("subnet_id".to_string()),
+                Cbor::Bytes(SUBNET_ID.to_vec()),
+            ),
+            (Cbor::Text("certificate".to_string()), Cbor::Bytes(inner)),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    let bytes = certificate(&tree, &root_key, Some(outer));
+    assert_eq!(
+        Certificate::from_cbor(&bytes).unwrap().verify_with_bls(
+            &CANISTER_ID,
+            &der(&root_key),
+            verify_bls
+        ),
+        Err(CertificateError::NestedDelegation)
+    );
+}
+
+#[test]
+fn test_malformed_certificate() {
+    for bytes in [
+        &b""[..],
+        // The self-describe tag alone.
+        &[0xd9, 0xd9, 0xf7],
+        // An empty map.
+        &[0xa0],
+        // A truncated byte string.
+        &[0x45, 1, 2],
+    ] {
+        assert!(matches!(
+            Certificate::from_cbor(bytes),
+            Err(CertificateError::Malformed(_))
+        ));
+    }
+
+    let root_key = [1; 96];
+    let mut bytes = certificate(&state_tree(&[0; 32]), &root_key, None);
+    bytes.push(0);
+    assert!(matches!(
+        Certificate::from_cbor(&bytes),
+        Err(CertificateError::Malformed(_))
+    ));
+}
+
+#[test]
+fn test_ic_root_key() {
+    assert_eq!(&IC_ROOT_KEY[..DER_PREFIX.len()], DER_PREFIX);
+    assert_eq!(
+        hex::encode(extract_der(IC_ROOT_KEY).unwrap()),
+        concat!(
+            "814c0e6ec71fab583b08bd81373c255c3c371b2e84863c98a4f1e08b74235d14fb5d9c0cd546d9685f",
+            "913a0c0b2cc5341583bf4b4392e467db96d65b9bb4cb717112f8472e0d5a4d14505ffd7484b0129109",
+            "1c5f87b98883463f98091a0baaae"
+        )
+    );
+}
+
+// The root hash and the signature of a mainnet certificate.
+#[cfg(feature = "verify-bls")]
+const MAINNET_ROOT_HASH: &str = "e6c01e909b4923345ce5970962bcfe3004bfd8474a21dae28f50692502f46d90";
+#[cfg(feature = "verify-bls")]
+const MAINNET_SIGNATURE: &str = "ace9fcdd9bc977e05d6328f889dc4e7c99114c737a494653cb27a1f55c06f4555e0f160980af5ead098acc195010b2f7";
+
+// The certificate with its tree pruned to its root hash, which is all the signature covers.
+#[cfg(feature = "verify-bls")]
+fn mainnet_certificate<'a>(root_hash: &str, signature: &'a [u8]) -> Certificate<'a> {
+    Certificate {
+        tree: HashTree::Pruned(hex::decode(root_hash).unwrap().try_into().unwrap()),
+        signature,
+        delegation: None,
+    }
+}
+
+#[cfg(feature = "verify-bls")]
+#[test]
+fn test_verify_mainnet_signature() {
+    let signature = hex::decode(MAINNET_SIGNATURE).unwrap();
+    let certificate = mainnet_certificate(MAINNET_ROOT_HASH, &signature);
+    assert_eq!(certificate.verify(&CANISTER_ID, IC_ROOT_KEY), Ok(()));
+
+    let mut other = MAINNET_ROOT_HASH.to_string();
+    other.replace_range(63.., "1");
+    assert_eq!(
+        mainnet_certificate(&other, &signature).verify(&CANISTER_ID, IC_ROOT_KEY),
+        Err(CertificateError::InvalidSignature)
+    );
+    assert_eq!(
+        certificate.verify(&CANISTER_ID, &der(&[1; 96])),
+        Err(CertificateError::InvalidSignature)
+    );
+
+    // The signatures of the other tests are not BLS signatures.
+    let root_key = [1; 96];
+    let map = map();
+    let bytes = self::certificate(&state_tree(&map.root_hash()), &root_key, None);
+    assert_eq!(
+        verify_certified_data(&bytes, &map.witness(b"b"), &CANISTER_ID, &der(&root_key))
+            .unwrap_err(),
+        CertificateError::InvalidSignature
+    );
+}
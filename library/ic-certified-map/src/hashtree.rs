@@ -1,8 +1,12 @@
 #[cfg(test)]
 mod test;
 
-use serde::{ser::SerializeSeq, Serialize, Serializer};
-use serde_bytes::Bytes;
+use serde::{
+    de::{self, Deserialize, Deserializer, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Serialize, Serializer,
+};
+use serde_bytes::{ByteBuf, Bytes};
 use sha2::{Digest, Sha256};
 use std::borrow::Cow;
 
@@ -18,7 +22,7 @@ pub enum HashTree<'a> {
     /// Left and right child branches.
     Fork(Box<(HashTree<'a>, HashTree<'a>)>),
     /// A labeled child node.
-    Labeled(&'a [u8], Box<HashTree<'a>>),
+    Labeled(Cow<'a, [u8]>, Box<HashTree<'a>>),
     /// A leaf node containing a value or hash.
     Leaf(Cow<'a, [u8]>),
     /// A branch that has been removed from this view of the tree, but is not necessarily absent.
@@ -32,7 +36,7 @@ pub fn fork<'a>(l: HashTree<'a>, r: HashTree<'a>) -> HashTree<'a> {
 
 /// Shorthand for [`HashTree::Labeled`].
 pub fn labeled<'a>(l: &'a [u8], t: HashTree<'a>) -> HashTree<'a> {
-    HashTree::Labeled(l, Box::new(t))
+    HashTree::Labeled(Cow::Borrowed(l), Box::new(t))
 }
 
 /// Identifiably hashes a fork in the branch. Used for hashing [`HashTree::Fork`].
@@ -58,6 +62,133 @@ pub fn labeled_hash(label: &[u8], content_hash: &Hash) -> Hash {
     h.finalize().into()
 }
 
+/// Merges two witnesses of the same tree into one, which proves everything either of them proves.
+///
+/// For example, the [witnesses](crate::RbTree::witness) of two keys of a map merge into a
+/// single witness of both keys, smaller than the two together.
+///
+/// # Panics
+///
+/// If the trees have different root hashes.
+pub fn merge_hash_trees<'a>(lhs: HashTree<'a>, rhs: HashTree<'a>) -> HashTree<'a> {
+    assert_eq!(
+        lhs.reconstruct(),
+        rhs.reconstruct(),
+        "cannot merge hash trees with different root hashes"
+    );
+    merge(lhs, rhs)
+}
+
+// Merges two trees with the same root hash: a pruned branch is replaced with the same branch of
+// the other tree, any other node has the same shape in both trees.
+pub(crate) fn merge<'a>(lhs: HashTree<'a>, rhs: HashTree<'a>) -> HashTree<'a> {
+    match (lhs, rhs) {
+        (HashTree::Pruned(_), t) | (t, HashTree::Pruned(_)) => t,
+        (HashTree::Fork(l), HashTree::Fork(r)) => {
+            let ((l1, l2), (r1, r2)) = (*l, *r);
+            fork(merge(l1, r1), merge(l2, r2))
+        }
+        (HashTree::Labeled(label, l), HashTree::Labeled(_, r)) => {
+            HashTree::Labeled(label, Box::new(merge(*l, *r)))
+        }
+        (t, _) => t,
+    }
+}
+
+/// Result of looking up a path in a [`HashTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupResult<'t> {
+    /// The path leads to a leaf with this value.
+    Found(&'t [u8]),
+    /// The tree proves that the path is absent.
+    Absent,
+    /// The path leads to a pruned branch, so the tree cannot prove its presence or absence.
+    Unknown,
+    /// The path leads to a subtree rather than a value.
+    Error,
+}
+
+// Result of looking up a label among the children of a node.
+enum LabelResult<'t, 'a> {
+    Found(&'t HashTree<'a>),
+    Absent,
+    Unknown,
+    Error,
+}
+
+impl<'a> HashTree<'a> {
+    /// Looks up the value at `path`, with the semantics of `lookup_path` in the
+    /// [interfaces spec](https://internetcomputer.org/docs/current/references/ic-interface-spec#lookup).
+    ///
+    /// A witness proves the absence of a key as well as its presence: the lookup of a key
+    /// the witness doesn't cover is [`Unknown`](LookupResult::Unknown), not
+    /// [`Absent`](LookupResult::Absent).
+    ///
+    /// ```
+    /// # use ic_certified_map::*;
+    /// let mut map: RbTree<&str, Vec<u8>> = RbTree::new();
+    /// map.insert("a", b"alpha".to_vec());
+    /// map.insert("c", b"gamma".to_vec());
+    /// let witness = map.witness(b"b");
+    /// assert_eq!(witness.lookup_path(&[b"b"]), LookupResult::Absent);
+    /// assert_eq!(witness.lookup_path(&[b"a"]), LookupResult::Unknown);
+    /// assert_eq!(map.witness(b"a").lookup_path(&[b"a"]), LookupResult::Found(b"alpha"));
+    /// ```
+    pub fn lookup_path(&self, path: &[&[u8]]) -> LookupResult<'_> {
+        match path.split_first() {
+            None => match self {
+                Self::Empty => LookupResult::Absent,
+                Self::Leaf(v) => LookupResult::Found(v),
+                Self::Pruned(_) => LookupResult::Unknown,
+                Self::Fork(_) | Self::Labeled(..) => LookupResult::Error,
+            },
+            Some((label, rest)) => match self.find_label(label) {
+                LabelResult::Found(t) => t.lookup_path(rest),
+                LabelResult::Absent => LookupResult::Absent,
+                LabelResult::Unknown => LookupResult::Unknown,
+                LabelResult::Error => LookupResult::Error,
+            },
+        }
+    }
+
+    // The labels of a node are sorted: a label is absent if it falls between two labels, before
+    // the first or after the last one, with no pruned branch in the way. A leaf has no labels.
+    fn find_label(&self, label: &[u8]) -> LabelResult<'_, 'a> {
+        let mut children = vec![];
+        self.flatten_forks(&mut children);
+        if let [Self::Leaf(_)] = children[..] {
+            return LabelResult::Error;
+        }
+        let mut proven = true;
+        for child in children {
+            match child {
+                Self::Labeled(l, t) => match label.cmp(l.as_ref()) {
+                    std::cmp::Ordering::Equal => return LabelResult::Found(t),
+                    std::cmp::Ordering::Less => break,
+                    std::cmp::Ordering::Greater => proven = true,
+                },
+                _ => proven = false,
+            }
+        }
+        if proven {
+            LabelResult::Absent
+        } else {
+            LabelResult::Unknown
+        }
+    }
+
+    fn flatten_forks<'t>(&'t self, out: &mut Vec<&'t HashTree<'a>>) {
+        match self {
+            Self::Empty => {}
+            Self::Fork(f) => {
+                f.0.flatten_forks(out);
+                f.1.flatten_forks(out);
+            }
+            t => out.push(t),
+        }
+    }
+}
+
 impl HashTree<'_> {
     /// Produces the root hash of the tree.
     pub fn reconstruct(&self) -> Hash {
@@ -95,7 +226,7 @@ impl Serialize for HashTree<'_> {
             HashTree::Labeled(label, tree) => {
                 let mut seq = serializer.serialize_seq(Some(3))?;
                 seq.serialize_element(&2u8)?;
-                seq.serialize_element(Bytes::new(label))?;
+                seq.serialize_element(Bytes::new(label.as_ref()))?;
                 seq.serialize_element(&tree)?;
                 seq.end()
             }
@@ -115,6 +246,69 @@ impl Serialize for HashTree<'_> {
     }
 }
 
+/// Deserializes a tree serialized with [`Serialize`], e.g. a witness received from a canister.
+///
+/// The tree owns its labels and values.
+impl<'de> Deserialize<'de> for HashTree<'static> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(HashTreeVisitor)
+    }
+}
+
+struct HashTreeVisitor;
+
+impl<'de> Visitor<'de> for HashTreeVisitor {
+    type Value = HashTree<'static>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("a hash tree")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        fn next<'de, A: SeqAccess<'de>, T: Deserialize<'de>>(
+            seq: &mut A,
+            index: usize,
+        ) -> Result<T, A::Error> {
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(index, &"a hash tree node"))
+        }
+
+        let tree = match next::<_, u8>(&mut seq, 0)? {
+            0 => HashTree::Empty,
+            1 => HashTree::Fork(Box::new((next(&mut seq, 1)?, next(&mut seq, 2)?))),
+            2 => {
+                let label: ByteBuf = next(&mut seq, 1)?;
+                HashTree::Labeled(Cow::Owned(label.into_vec()), Box::new(next(&mut seq, 2)?))
+            }
+            3 => HashTree::Leaf(Cow::Owned(next::<_, ByteBuf>(&mut seq, 1)?.into_vec())),
+            4 => {
+                let digest: ByteBuf = next(&mut seq, 1)?;
+                let hash = digest
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| de::Error::invalid_length(digest.len(), &"a 32-byte hash"))?;
+                HashTree::Pruned(hash)
+            }
+            tag => {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Unsigned(tag.into()),
+                    &"a hash tree tag between 0 and 4",
+                ))
+            }
+        };
+        if seq.next_element::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom("too many elements in a hash tree node"));
+        }
+        Ok(tree)
+    }
+}
+
 fn domain_sep(s: &str) -> sha2::Sha256 {
     let buf: [u8; 1] = [s.len() as u8];
     let mut h = Sha256::new();
@@ -1,6 +1,7 @@
 use super::{
-    fork, labeled,
-    HashTree::{Empty, Leaf},
+    fork, labeled, HashTree,
+    HashTree::{Empty, Leaf, Pruned},
+    LookupResult,
 };
 use std::borrow::Cow;
 
@@ -38,3 +39,85 @@ fn test_public_spec_example() {
         hex::encode(serde_cbor::to_vec(&t).unwrap()),
         "8301830183024161830183018302417882034568656c6c6f810083024179820345776f726c6483024162820344676f6f648301830241638100830241648203476d6f726e696e67".to_string());
 }
+
+#[test]
+fn test_lookup_path() {
+    let t = fork(
+        fork(
+            labeled(
+                b"a",
+                fork(
+                    fork(labeled(b"x", Leaf(Cow::Borrowed(b"hello"))), Empty),
+                    labeled(b"y", Leaf(Cow::Borrowed(b"world"))),
+                ),
+            ),
+            labeled(b"b", Pruned([0; 32])),
+        ),
+        fork(
+            Pruned([1; 32]),
+            labeled(b"d", Leaf(Cow::Borrowed(b"morning"))),
+        ),
+    );
+
+    assert_eq!(t.lookup_path(&[b"a", b"x"]), LookupResult::Found(b"hello"));
+    assert_eq!(t.lookup_path(&[b"a", b"y"]), LookupResult::Found(b"world"));
+    assert_eq!(t.lookup_path(&[b"a", b"z"]), LookupResult::Absent);
+    assert_eq!(t.lookup_path(&[b"a", b"a"]), LookupResult::Absent);
+    assert_eq!(t.lookup_path(&[b"aa"]), LookupResult::Absent);
+    assert_eq!(t.lookup_path(&[b"b"]), LookupResult::Unknown);
+    // Between "b" and "d", a pruned branch may hold "c".
+    assert_eq!(t.lookup_path(&[b"c"]), LookupResult::Unknown);
+    assert_eq!(t.lookup_path(&[b"d"]), LookupResult::Found(b"morning"));
+    assert_eq!(t.lookup_path(&[b"e"]), LookupResult::Absent);
+    assert_eq!(t.lookup_path(&[b"a"]), LookupResult::Error);
+    assert_eq!(t.lookup_path(&[b"d", b"x"]), LookupResult::Error);
+    // Only a lone leaf is an error, any other node without the label is unknown.
+    let t = fork(Pruned([1; 32]), Leaf(Cow::Borrowed(b"morning")));
+    assert_eq!(t.lookup_path(&[b"d"]), LookupResult::Unknown);
+}
+
+#[test]
+fn test_deserialize() {
+    // The public spec example, with a pruned branch.
+    let t = fork(
+        fork(
+            labeled(b"a", Pruned([7; 32])),
+            labeled(b"b", Leaf(Cow::Borrowed(b"good"))),
+        ),
+        fork(labeled(b"c", Empty), Empty),
+    );
+    let bytes = serde_cbor::to_vec(&t).unwrap();
+    let owned: HashTree<'static> = serde_cbor::from_slice(&bytes).unwrap();
+    assert_eq!(owned.reconstruct(), t.reconstruct());
+    assert_eq!(serde_cbor::to_vec(&owned).unwrap(), bytes);
+    assert_eq!(owned.lookup_path(&[b"b"]), LookupResult::Found(b"good"));
+
+    // With the self-describe tag, as served by canisters.
+    let mut tagged = vec![];
+    let mut serializer = serde_cbor::Serializer::new(&mut tagged);
+    serializer.self_describe().unwrap();
+    serde::Serialize::serialize(&t, &mut serializer).unwrap();
+    let owned: HashTree<'static> = serde_cbor::from_slice(&tagged).unwrap();
+    assert_eq!(owned.reconstruct(), t.reconstruct());
+
+    let spec = hex::decode("8301830183024161830183018302417882034568656c6c6f810083024179820345776f726c6483024162820344676f6f648301830241638100830241648203476d6f726e696e67").unwrap();
+    let owned: HashTree<'static> = serde_cbor::from_slice(&spec).unwrap();
+    assert_eq!(
+        hex::encode(owned.reconstruct()),
+        "eb5c5b2195e62d996b84c9bcc8259d19a83786a2f59e0878cec84c811f669aa0"
+    );
+
+    for invalid in [
+        // Unknown tag.
+        "8105",
+        // Missing the right branch.
+        "820100",
+        // Extra element.
+        "820000",
+        // Pruned hash of 2 bytes.
+        "8204420102",
+    ] {
+        let bytes = hex::decode(invalid).unwrap();
+        assert!(serde_cbor::from_slice::<HashTree<'static>>(&bytes).is_err());
+    }
+}
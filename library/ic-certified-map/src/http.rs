@@ -0,0 +1,386 @@
+//! Certification of the responses of a canister serving HTTP, through the `IC-Certificate`
+//! header checked by the [HTTP gateways](https://internetcomputer.org/docs/current/references/http-gateway-protocol-spec).
+
+#[cfg(test)]
+mod test;
+
+use crate::cbor;
+use crate::hashtree::{labeled, labeled_hash, merge, Hash, HashTree, HashTree::Pruned};
+use crate::rbtree::{AsHashTree, RbTree};
+
+/// Name of the header carrying the certificate and the witness of a response.
+pub const CERTIFICATE_HEADER: &str = "IC-Certificate";
+
+const HTTP_ASSETS_LABEL: &[u8] = b"http_assets";
+const HTTP_EXPR_LABEL: &str = "http_expr";
+const EXACT_LABEL: &str = "<$>";
+const WILDCARD_LABEL: &str = "<*>";
+// Served by the gateways, in the version 1 of the response verification, in place of a missing asset.
+const FALLBACK_ASSET: &str = "/index.html";
+
+/// Root hash of the tree of the certified assets, to set as the certified data of the canister,
+/// in the version 1 of the response verification.
+///
+/// `assets` maps the path of each asset, e.g. `/index.html`, to the SHA-256 hash of its body.
+pub fn http_assets_root_hash<K: AsRef<[u8]>>(assets: &RbTree<K, Hash>) -> Hash {
+    labeled_hash(HTTP_ASSETS_LABEL, &assets.root_hash())
+}
+
+/// The `IC-Certificate` header of the response to a request for `path`, in the version 1 of
+/// the response verification.
+///
+/// `certificate` is the one returned by [`data_certificate`], certifying
+/// [`http_assets_root_hash`]. If there is no asset at `path`, the witness also proves the asset
+/// at `/index.html`, which the gateways accept in its place, e.g. as a 404 page.
+///
+/// [`data_certificate`]: https://docs.rs/ic-cdk/latest/ic_cdk/api/fn.data_certificate.html
+pub fn http_assets_certificate_header<K: AsRef<[u8]>>(
+    certificate: &[u8],
+    assets: &RbTree<K, Hash>,
+    path: &str,
+) -> (String, String) {
+    let mut witness = assets.witness(path.as_bytes());
+    if assets.get(path.as_bytes()).is_none() {
+        witness = merge(witness, assets.witness(FALLBACK_ASSET.as_bytes()));
+    }
+    let tree = labeled(HTTP_ASSETS_LABEL, witness);
+    (
+        CERTIFICATE_HEADER.to_string(),
+        format!(
+            "certificate=:{}:, tree=:{}:",
+            base64(certificate),
+            base64(&cbor::encode_hash_tree(&tree))
+        ),
+    )
+}
+
+/// Path of certified responses, in the version 2 of the response verification.
+///
+/// A path is split in segments at each `/`: `/` has a single empty segment, and `/assets/` ends
+/// with one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HttpCertificationPath {
+    /// Responses to the requests for exactly this path, e.g. `/index.html`.
+    Exact(String),
+    /// Responses to the requests for any path starting with these segments, unless a more
+    /// specific path is certified, e.g. `/app` for a single-page app, or `""` for a 404 page.
+    Wildcard(String),
+}
+
+impl HttpCertificationPath {
+    /// The `expr_path` of the responses: `http_expr`, the segments of the path, then `<$>` for an
+    /// exact path or `<*>` for a wildcard.
+    pub fn expr_path(&self) -> Vec<&str> {
+        let (path, last) = match self {
+            Self::Exact(path) => (path, EXACT_LABEL),
+            Self::Wildcard(path) => (path, WILDCARD_LABEL),
+        };
+        expr_path(&segments(path), last)
+    }
+}
+
+fn expr_path<'a>(segments: &[&'a str], last: &'a str) -> Vec<&'a str> {
+    let mut expr_path = vec![HTTP_EXPR_LABEL];
+    expr_path.extend_from_slice(segments);
+    expr_path.push(last);
+    expr_path
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    if path.is_empty() {
+        return vec![];
+    }
+    path.strip_prefix('/').unwrap_or(path).split('/').collect()
+}
+
+// The `expr_path`s the gateways look up for a request, from the most specific one.
+fn potential_expr_paths(request_path: &str) -> Vec<Vec<&str>> {
+    let segments = segments(request_path);
+    let mut expr_paths = vec![expr_path(&segments, EXACT_LABEL)];
+    for i in (0..=segments.len()).rev() {
+        expr_paths.push(expr_path(&segments[..i], WILDCARD_LABEL));
+    }
+    expr_paths
+}
+
+/// A certified response, in the version 2 of the response verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpCertificationEntry {
+    /// Path of the requests the response is served for.
+    pub path: HttpCertificationPath,
+    /// SHA-256 hash of the `IC-CertificateExpression` header of the response.
+    pub expr_hash: Hash,
+    /// Hash of the certified parts of the request, or `None` if the expression doesn't certify
+    /// the request.
+    pub request_hash: Option<Hash>,
+    /// Hash of the certified headers and of the body of the response.
+    pub response_hash: Hash,
+}
+
+impl HttpCertificationEntry {
+    fn request_label(&self) -> &[u8] {
+        self.request_hash.as_ref().map_or(&[], |hash| &hash[..])
+    }
+}
+
+type Responses = RbTree<Hash, RbTree<Vec<u8>, RbTree<Hash, Vec<u8>>>>;
+
+#[derive(Debug, Clone)]
+enum PathNode {
+    Segments(RbTree<Vec<u8>, PathNode>),
+    Responses(Responses),
+}
+
+impl AsHashTree for PathNode {
+    fn root_hash(&self) -> Hash {
+        match self {
+            PathNode::Segments(segments) => segments.root_hash(),
+            PathNode::Responses(responses) => responses.root_hash(),
+        }
+    }
+
+    fn as_hash_tree(&self) -> HashTree<'_> {
+        match self {
+            PathNode::Segments(segments) => segments.as_hash_tree(),
+            PathNode::Responses(responses) => responses.as_hash_tree(),
+        }
+    }
+}
+
+/// The certified responses of a canister serving HTTP, in the version 2 of the response
+/// verification.
+///
+/// The tree maps the `expr_path` of each response, followed by its expression, request and
+/// response hashes, to an empty leaf. Its [`root_hash`](AsHashTree::root_hash) is the certified
+/// data of the canister.
+#[derive(Debug, Clone)]
+pub struct HttpCertificationTree {
+    root: RbTree<Vec<u8>, PathNode>,
+}
+
+impl AsHashTree for HttpCertificationTree {
+    fn root_hash(&self) -> Hash {
+        labeled_hash(HTTP_EXPR_LABEL.as_bytes(), &self.root.root_hash())
+    }
+
+    fn as_hash_tree(&self) -> HashTree<'_> {
+        labeled(HTTP_EXPR_LABEL.as_bytes(), self.root.as_hash_tree())
+    }
+}
+
+impl Default for HttpCertificationTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpCertificationTree {
+    /// Constructs a new empty tree.
+    pub const fn new() -> Self {
+        Self {
+            root: RbTree::new(),
+        }
+    }
+
+    /// Returns true if the tree certifies no response.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty()
+    }
+
+    /// Certifies a response.
+    pub fn insert(&mut self, entry: &HttpCertificationEntry) {
+        insert_entry(&mut self.root, &entry.path.expr_path()[1..], entry);
+    }
+
+    /// Removes the certification of a response.
+    pub fn delete(&mut self, entry: &HttpCertificationEntry) {
+        delete_entry(&mut self.root, &entry.path.expr_path()[1..], entry);
+    }
+
+    /// Constructs the witness of the response `entry` to a request for `request_path`: the path
+    /// to the response, and the absence of the more specific paths the gateways look up first.
+    ///
+    /// `request_path` is the path of the request URL, without the query string. Returns `None`
+    /// if the path of `entry` doesn't match `request_path`.
+    pub fn witness(
+        &self,
+        entry: &HttpCertificationEntry,
+        request_path: &str,
+    ) -> Option<HashTree<'_>> {
+        let expr_path = entry.path.expr_path();
+        let potential_expr_paths = potential_expr_paths(request_path);
+        let position = potential_expr_paths.iter().position(|p| *p == expr_path)?;
+        let mut witness = response_witness(&self.root, &expr_path[1..], entry);
+        for more_specific in &potential_expr_paths[..position] {
+            witness = merge(witness, absence_witness(&self.root, &more_specific[1..]));
+        }
+        Some(labeled(HTTP_EXPR_LABEL.as_bytes(), witness))
+    }
+
+    /// The `IC-Certificate` header of the response `entry` to a request for `request_path`.
+    ///
+    /// `certificate` is the one returned by [`data_certificate`], certifying the
+    /// [`root_hash`](AsHashTree::root_hash) of the tree. The response must also carry its
+    /// `IC-CertificateExpression` header. Returns `None` if the path of `entry` doesn't match
+    /// `request_path`.
+    ///
+    /// [`data_certificate`]: https://docs.rs/ic-cdk/latest/ic_cdk/api/fn.data_certificate.html
+    pub fn certificate_header(
+        &self,
+        certificate: &[u8],
+        entry: &HttpCertificationEntry,
+        request_path: &str,
+    ) -> Option<(String, String)> {
+        let tree = self.witness(entry, request_path)?;
+        Some((
+            CERTIFICATE_HEADER.to_string(),
+            format!(
+                "certificate=:{}:, tree=:{}:, expr_path=:{}:, version=2",
+                base64(certificate),
+                base64(&cbor::encode_hash_tree(&tree)),
+                base64(&cbor::encode_text_array(&entry.path.expr_path())),
+            ),
+        ))
+    }
+}
+
+fn insert_entry(
+    level: &mut RbTree<Vec<u8>, PathNode>,
+    labels: &[&str],
+    entry: &HttpCertificationEntry,
+) {
+    let (label, rest) = labels
+        .split_first()
+        .expect("an expr_path ends with <$> or <*>");
+    let label = label.as_bytes();
+    if rest.is_empty() {
+        if !matches!(level.get(label), Some(PathNode::Responses(_))) {
+            level.insert(label.to_vec(), PathNode::Responses(RbTree::new()));
+        }
+        level.modify(label, |node| {
+            if let PathNode::Responses(responses) = node {
+                insert_response(responses, entry);
+            }
+        });
+    } else {
+        if !matches!(level.get(label), Some(PathNode::Segments(_))) {
+            level.insert(label.to_vec(), PathNode::Segments(RbTree::new()));
+        }
+        level.modify(label, |node| {
+            if let PathNode::Segments(segments) = node {
+                insert_entry(segments, rest, entry);
+            }
+        });
+    }
+}
+
+fn insert_response(responses: &mut Responses, entry: &HttpCertificationEntry) {
+    let request_label = entry.request_label();
+    if responses.get(&entry.expr_hash).is_none() {
+        responses.insert(entry.expr_hash, RbTree::new());
+    }
+    responses.modify(&entry.expr_hash, |requests| {
+        if requests.get(request_label).is_none() {
+            requests.insert(request_label.to_vec(), RbTree::new());
+        }
+        requests.modify(request_label, |hashes| {
+            hashes.insert(entry.response_hash, vec![]);
+        });
+    });
+}
+
+// Removes the empty subtrees on the way back, so that the hash doesn't depend on the history.
+fn delete_entry(
+    level: &mut RbTree<Vec<u8>, PathNode>,
+    labels: &[&str],
+    entry: &HttpCertificationEntry,
+) {
+    let Some((label, rest)) = labels.split_first() else {
+        return;
+    };
+    let label = label.as_bytes();
+    let mut empty = false;
+    level.modify(label, |node| match node {
+        PathNode::Responses(responses) if rest.is_empty() => {
+            delete_response(responses, entry);
+            empty = responses.is_empty();
+        }
+        PathNode::Segments(segments) if !rest.is_empty() => {
+            delete_entry(segments, rest, entry);
+            empty = segments.is_empty();
+        }
+        _ => {}
+    });
+    if empty {
+        level.delete(label);
+    }
+}
+
+fn delete_response(responses: &mut Responses, entry: &HttpCertificationEntry) {
+    let request_label = entry.request_label();
+    let mut empty = false;
+    responses.modify(&entry.expr_hash, |requests| {
+        let mut no_hashes = false;
+        requests.modify(request_label, |hashes| {
+            hashes.delete(&entry.response_hash);
+            no_hashes = hashes.is_empty();
+        });
+        if no_hashes {
+            requests.delete(request_label);
+        }
+        empty = requests.is_empty();
+    });
+    if empty {
+        responses.delete(&entry.expr_hash);
+    }
+}
+
+fn response_witness<'a>(
+    level: &'a RbTree<Vec<u8>, PathNode>,
+    labels: &[&str],
+    entry: &HttpCertificationEntry,
+) -> HashTree<'a> {
+    let (label, rest) = labels
+        .split_first()
+        .expect("an expr_path ends with <$> or <*>");
+    level.nested_witness(label.as_bytes(), |node| match node {
+        PathNode::Segments(segments) if !rest.is_empty() => response_witness(segments, rest, entry),
+        PathNode::Responses(responses) if rest.is_empty() => {
+            responses.nested_witness(&entry.expr_hash, |requests| {
+                requests.nested_witness(entry.request_label(), |hashes| {
+                    hashes.witness(&entry.response_hash)
+                })
+            })
+        }
+        node => Pruned(node.root_hash()),
+    })
+}
+
+fn absence_witness<'a>(level: &'a RbTree<Vec<u8>, PathNode>, labels: &[&str]) -> HashTree<'a> {
+    let (label, rest) = labels
+        .split_first()
+        .expect("an expr_path ends with <$> or <*>");
+    level.nested_witness(label.as_bytes(), |node| match node {
+        PathNode::Segments(segments) if !rest.is_empty() => absence_witness(segments, rest),
+        node => Pruned(node.root_hash()),
+    })
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,198 @@
+use super::*;
+use crate::LookupResult;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+fn sha256(data: &[u8]) -> Hash {
+    Sha256::digest(data).into()
+}
+
+fn decode_base64(s: &str) -> Vec<u8> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let digits: Vec<u32> = s
+        .bytes()
+        .filter(|c| *c != b'=')
+        .map(|c| ALPHABET.iter().position(|a| *a == c).unwrap() as u32)
+        .collect();
+    let mut out = vec![];
+    for chunk in digits.chunks(4) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, d)| n | d << (18 - 6 * i));
+        out.extend_from_slice(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    out
+}
+
+// Splits an `IC-Certificate` header value into its fields.
+fn fields(value: &str) -> Vec<(&str, &str)> {
+    value
+        .split(", ")
+        .map(|field| {
+            let (name, value) = field.split_once('=').unwrap();
+            (name, value.trim_matches(':'))
+        })
+        .collect()
+}
+
+fn decode_tree(value: &str) -> HashTree<'static> {
+    serde_cbor::from_slice(&decode_base64(value)).unwrap()
+}
+
+#[test]
+fn test_base64() {
+    for (bytes, encoded) in [
+        (&b""[..], ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"fooba", "Zm9vYmE="),
+        (b"foobar", "Zm9vYmFy"),
+        (&[0xfb, 0xff], "+/8="),
+    ] {
+        assert_eq!(base64(bytes), encoded);
+        assert_eq!(decode_base64(encoded), bytes);
+    }
+}
+
+#[test]
+fn test_encode_hash_tree() {
+    let mut map = RbTree::new();
+    for i in 0..300u32 {
+        map.insert(i.to_be_bytes(), vec![1; i as usize]);
+    }
+    for tree in [
+        map.witness(&7u32.to_be_bytes()),
+        map.as_hash_tree(),
+        HashTree::Empty,
+    ] {
+        let mut expected = vec![];
+        let mut serializer = serde_cbor::Serializer::new(&mut expected);
+        serializer.self_describe().unwrap();
+        tree.serialize(&mut serializer).unwrap();
+        assert_eq!(cbor::encode_hash_tree(&tree), expected);
+    }
+}
+
+#[test]
+fn test_http_assets_certificate_header() {
+    let mut assets = RbTree::new();
+    assets.insert("/index.html", sha256(b"<html></html>"));
+    assets.insert("/app.js", sha256(b"main()"));
+    let root_hash = http_assets_root_hash(&assets);
+
+    let (name, value) = http_assets_certificate_header(b"cert", &assets, "/app.js");
+    assert_eq!(name, "IC-Certificate");
+    let header = fields(&value);
+    assert_eq!(header[0], ("certificate", "Y2VydA=="));
+    assert_eq!(header[1].0, "tree");
+    let tree = decode_tree(header[1].1);
+    assert_eq!(tree.reconstruct(), root_hash);
+    assert_eq!(
+        tree.lookup_path(&[b"http_assets", b"/app.js"]),
+        LookupResult::Found(&sha256(b"main()"))
+    );
+
+    // A missing asset is replaced with /index.html.
+    let (_, value) = http_assets_certificate_header(b"cert", &assets, "/missing");
+    let tree = decode_tree(fields(&value)[1].1);
+    assert_eq!(tree.reconstruct(), root_hash);
+    assert_eq!(
+        tree.lookup_path(&[b"http_assets", b"/missing"]),
+        LookupResult::Absent
+    );
+    assert_eq!(
+        tree.lookup_path(&[b"http_assets", b"/index.html"]),
+        LookupResult::Found(&sha256(b"<html></html>"))
+    );
+}
+
+fn entry(path: HttpCertificationPath, body: &[u8]) -> HttpCertificationEntry {
+    HttpCertificationEntry {
+        path,
+        expr_hash: sha256(b"default_certification(ValidationArgs{no_certification:Empty{}})"),
+        request_hash: None,
+        response_hash: sha256(body),
+    }
+}
+
+#[test]
+fn test_expr_path() {
+    use HttpCertificationPath::*;
+    assert_eq!(Exact("/".to_string()).expr_path(), ["http_expr", "", "<$>"]);
+    assert_eq!(
+        Exact("/assets/app.js".to_string()).expr_path(),
+        ["http_expr", "assets", "app.js", "<$>"]
+    );
+    assert_eq!(
+        Wildcard("/assets/".to_string()).expr_path(),
+        ["http_expr", "assets", "", "<*>"]
+    );
+    assert_eq!(Wildcard(String::new()).expr_path(), ["http_expr", "<*>"]);
+    assert_eq!(
+        potential_expr_paths("/a/b"),
+        [
+            vec!["http_expr", "a", "b", "<$>"],
+            vec!["http_expr", "a", "b", "<*>"],
+            vec!["http_expr", "a", "<*>"],
+            vec!["http_expr", "<*>"],
+        ]
+    );
+}
+
+#[test]
+fn test_http_certification_tree() {
+    use HttpCertificationPath::*;
+    let index = entry(Exact("/".to_string()), b"index");
+    let app = entry(Exact("/assets/app.js".to_string()), b"app");
+    let not_found = entry(Wildcard(String::new()), b"not found");
+    let mut tree = HttpCertificationTree::new();
+    for entry in [&index, &app, &not_found] {
+        tree.insert(entry);
+    }
+    let root_hash = tree.root_hash();
+    assert_eq!(tree.as_hash_tree().reconstruct(), root_hash);
+
+    let (name, value) = tree
+        .certificate_header(b"cert", &app, "/assets/app.js")
+        .unwrap();
+    assert_eq!(name, "IC-Certificate");
+    let header = fields(&value);
+    assert_eq!(header[0], ("certificate", "Y2VydA=="));
+    assert_eq!(header[3], ("version", "2"));
+    let expr_path: Vec<String> = serde_cbor::from_slice(&decode_base64(header[2].1)).unwrap();
+    assert_eq!(expr_path, ["http_expr", "assets", "app.js", "<$>"]);
+    let witness = decode_tree(header[1].1);
+    assert_eq!(witness.reconstruct(), root_hash);
+    let mut path: Vec<&[u8]> = vec![b"http_expr", b"assets", b"app.js", b"<$>"];
+    path.extend([&app.expr_hash[..], b"", &app.response_hash[..]]);
+    assert_eq!(witness.lookup_path(&path), LookupResult::Found(b""));
+
+    // The 404 page, with the proof that no more specific response exists.
+    let witness = tree.witness(&not_found, "/assets/missing.js").unwrap();
+    assert_eq!(witness.reconstruct(), root_hash);
+    for absent in [
+        &[&b"http_expr"[..], b"assets", b"missing.js"][..],
+        &[b"http_expr", b"assets", b"<*>"],
+    ] {
+        assert_eq!(witness.lookup_path(absent), LookupResult::Absent);
+    }
+    let mut path: Vec<&[u8]> = vec![b"http_expr", b"<*>"];
+    path.extend([&not_found.expr_hash[..], b"", &not_found.response_hash[..]]);
+    assert_eq!(witness.lookup_path(&path), LookupResult::Found(b""));
+
+    assert!(tree.witness(&app, "/index.html").is_none());
+    assert!(tree.witness(&index, "/assets/app.js").is_none());
+
+    // Deleting the responses gives back the hash of the tree without them.
+    let mut other = HttpCertificationTree::new();
+    other.insert(&index);
+    tree.delete(&app);
+    tree.delete(&not_found);
+    assert_eq!(tree.root_hash(), other.root_hash());
+    tree.delete(&index);
+    assert!(tree.is_empty());
+    assert_eq!(tree.root_hash(), HttpCertificationTree::new().root_hash());
+}
@@ -0,0 +1,275 @@
+//! Typed keys, encoded to bytes in the same order as their values.
+
+#[cfg(test)]
+mod test;
+
+use crate::{AsHashTree, HashTree, RbTree};
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Types encoded to the bytes of the keys of a certified map.
+///
+/// The encoding preserves the order: for values `a` and `b`, `a < b` if and only if the
+/// encoding of `a` is lexicographically smaller than the encoding of `b`. The key ranges of a
+/// map, e.g. [`key_range`](crate::RbTree::key_range), are then the ranges of the values.
+///
+/// Integers are encoded in big-endian, with the sign bit flipped for signed integers. Strings
+/// and byte strings are encoded as is, unless they are followed by other fields in a tuple.
+pub trait KeyEncoding {
+    /// Appends the encoding of the key to `buf`.
+    fn encode_key(&self, buf: &mut Vec<u8>);
+
+    /// Appends the encoding of the key to `buf`, when it is followed by other fields, e.g. in a
+    /// tuple. The encoding must not be a prefix of the encoding of another key.
+    ///
+    /// Defaults to [`encode_key`](Self::encode_key), for fixed-length encodings.
+    fn encode_key_field(&self, buf: &mut Vec<u8>) {
+        self.encode_key(buf)
+    }
+
+    /// Returns the encoding of the key, e.g. to look it up in a map.
+    fn to_key_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.encode_key(&mut buf);
+        buf
+    }
+}
+
+impl<T: KeyEncoding + ?Sized> KeyEncoding for &T {
+    fn encode_key(&self, buf: &mut Vec<u8>) {
+        (**self).encode_key(buf)
+    }
+
+    fn encode_key_field(&self, buf: &mut Vec<u8>) {
+        (**self).encode_key_field(buf)
+    }
+}
+
+macro_rules! unsigned_key {
+    ($($t:ty),*) => {$(
+        impl KeyEncoding for $t {
+            fn encode_key(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_be_bytes());
+            }
+        }
+    )*};
+}
+
+macro_rules! signed_key {
+    ($($t:ty),*) => {$(
+        impl KeyEncoding for $t {
+            fn encode_key(&self, buf: &mut Vec<u8>) {
+                let mut bytes = self.to_be_bytes();
+                bytes[0] ^= 0x80;
+                buf.extend_from_slice(&bytes);
+            }
+        }
+    )*};
+}
+
+unsigned_key!(u8, u16, u32, u64, u128);
+signed_key!(i8, i16, i32, i64, i128);
+
+impl KeyEncoding for bool {
+    fn encode_key(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+}
+
+// A byte string in a tuple is terminated with `00 00`, and its zero bytes are escaped as `00 ff`,
+// so that it is never a prefix of another, and the order is preserved.
+fn encode_bytes_field(bytes: &[u8], buf: &mut Vec<u8>) {
+    for byte in bytes {
+        buf.push(*byte);
+        if *byte == 0 {
+            buf.push(0xff);
+        }
+    }
+    buf.extend_from_slice(&[0, 0]);
+}
+
+impl KeyEncoding for [u8] {
+    fn encode_key(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self);
+    }
+
+    fn encode_key_field(&self, buf: &mut Vec<u8>) {
+        encode_bytes_field(self, buf)
+    }
+}
+
+impl KeyEncoding for Vec<u8> {
+    fn encode_key(&self, buf: &mut Vec<u8>) {
+        self.as_slice().encode_key(buf)
+    }
+
+    fn encode_key_field(&self, buf: &mut Vec<u8>) {
+        self.as_slice().encode_key_field(buf)
+    }
+}
+
+impl KeyEncoding for str {
+    fn encode_key(&self, buf: &mut Vec<u8>) {
+        self.as_bytes().encode_key(buf)
+    }
+
+    fn encode_key_field(&self, buf: &mut Vec<u8>) {
+        self.as_bytes().encode_key_field(buf)
+    }
+}
+
+impl KeyEncoding for String {
+    fn encode_key(&self, buf: &mut Vec<u8>) {
+        self.as_str().encode_key(buf)
+    }
+
+    fn encode_key_field(&self, buf: &mut Vec<u8>) {
+        self.as_str().encode_key_field(buf)
+    }
+}
+
+/// Principals are ordered by length first, so the encoding is the length, then the bytes.
+impl KeyEncoding for Principal {
+    fn encode_key(&self, buf: &mut Vec<u8>) {
+        let bytes = self.as_slice();
+        buf.push(bytes.len() as u8);
+        buf.extend_from_slice(bytes);
+    }
+}
+
+macro_rules! tuple_key {
+    ($($name:ident)*; $last:ident) => {
+        #[allow(non_snake_case)]
+        impl<$($name: KeyEncoding,)* $last: KeyEncoding> KeyEncoding for ($($name,)* $last,) {
+            fn encode_key(&self, buf: &mut Vec<u8>) {
+                let ($($name,)* $last,) = self;
+                $($name.encode_key_field(buf);)*
+                $last.encode_key(buf);
+            }
+
+            fn encode_key_field(&self, buf: &mut Vec<u8>) {
+                let ($($name,)* $last,) = self;
+                $($name.encode_key_field(buf);)*
+                $last.encode_key_field(buf);
+            }
+        }
+    };
+}
+
+tuple_key!(A; B);
+tuple_key!(A B; C);
+tuple_key!(A B C; D);
+
+/// A key of a certified map, stored with its [encoding](KeyEncoding).
+///
+/// ```
+/// # use ic_certified_map::*;
+/// let mut balances: RbTree<Key<(u64, String)>, String> = RbTree::new();
+/// balances.insert(Key::new((2, "bob".to_string())), "20".to_string());
+/// balances.insert(Key::new((10, "alice".to_string())), "5".to_string());
+///
+/// assert_eq!(
+///     balances.get_typed(&(10, "alice".to_string())),
+///     Some(&"5".to_string())
+/// );
+/// let keys: Vec<_> = balances.iter().map(|(k, _)| k.value().0).collect();
+/// assert_eq!(keys, [2, 10]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key<T> {
+    value: T,
+    bytes: Vec<u8>,
+}
+
+impl<T: KeyEncoding> Key<T> {
+    /// Encodes a key.
+    pub fn new(value: T) -> Self {
+        let bytes = value.to_key_bytes();
+        Self { value, bytes }
+    }
+}
+
+impl<T> Key<T> {
+    /// The value of the key.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value of the key.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+impl<T: KeyEncoding> From<T> for Key<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Accessors taking the value of a key, instead of its encoding.
+///
+/// The key is encoded as its type `T`, unlike the bytes passed to [`get`](RbTree::get), which
+/// may be the encoding of another type, e.g. `(10u32, "alice")` for a map of `(u64, String)`.
+impl<'t, T: KeyEncoding + 't, V: AsHashTree + 't> RbTree<Key<T>, V> {
+    /// Like [`get`](Self::get), with the value of the key.
+    pub fn get_typed(&self, key: &T) -> Option<&V> {
+        self.get(&key.to_key_bytes())
+    }
+
+    /// Like [`witness`](Self::witness), with the value of the key.
+    pub fn witness_typed(&self, key: &T) -> HashTree<'_> {
+        self.witness(&key.to_key_bytes())
+    }
+
+    /// Like [`multi_witness`](Self::multi_witness), with the values of the keys.
+    pub fn multi_witness_typed<'k>(&self, keys: impl IntoIterator<Item = &'k T>) -> HashTree<'_>
+    where
+        T: 'k,
+    {
+        let keys: Vec<_> = keys.into_iter().map(T::to_key_bytes).collect();
+        self.multi_witness(keys.iter().map(Vec::as_slice))
+    }
+
+    /// Like [`key_range`](Self::key_range), with the values of the bounds.
+    pub fn key_range_typed(&self, first: &T, last: &T) -> HashTree<'_> {
+        self.key_range(&first.to_key_bytes(), &last.to_key_bytes())
+    }
+
+    /// Like [`value_range`](Self::value_range), with the values of the bounds.
+    pub fn value_range_typed(&self, first: &T, last: &T) -> HashTree<'_> {
+        self.value_range(&first.to_key_bytes(), &last.to_key_bytes())
+    }
+
+    /// Like [`delete`](Self::delete), with the value of the key.
+    pub fn delete_typed(&mut self, key: &T) {
+        self.delete(&key.to_key_bytes())
+    }
+}
+
+impl<T> AsRef<[u8]> for Key<T> {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<T: CandidType> CandidType for Key<T> {
+    fn _ty() -> candid::types::internal::Type {
+        T::_ty()
+    }
+    fn idl_serialize<S: candid::types::Serializer>(&self, serializer: S) -> Result<(), S::Error> {
+        self.value.idl_serialize(serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for Key<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + KeyEncoding> Deserialize<'de> for Key<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
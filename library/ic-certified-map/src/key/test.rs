@@ -0,0 +1,145 @@
+use super::*;
+use crate::{AsHashTree, RbTree};
+use std::fmt::Debug;
+
+// Checks that the encoding of every pair of values is in the same order as the values.
+fn assert_order_preserved<T: KeyEncoding + Ord + Debug>(values: &[T]) {
+    for a in values {
+        for b in values {
+            assert_eq!(
+                a.to_key_bytes().cmp(&b.to_key_bytes()),
+                a.cmp(b),
+                "{a:?} and {b:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_integers() {
+    assert_order_preserved(&[0u8, 1, 0x7f, 0x80, 0xff]);
+    assert_order_preserved(&[0u64, 1, 255, 256, u64::MAX - 1, u64::MAX]);
+    assert_order_preserved(&[i32::MIN, -256, -1, 0, 1, 255, i32::MAX]);
+    assert_order_preserved(&[i128::MIN, -1, 0, i128::MAX]);
+    assert_order_preserved(&[false, true]);
+    assert_eq!(258u16.to_key_bytes(), [1, 2]);
+    assert_eq!((-1i16).to_key_bytes(), [0x7f, 0xff]);
+}
+
+#[test]
+fn test_strings() {
+    let strings = [
+        "", "\0", "\0\0", "\0a", "a", "a\0", "a\0\0", "ab", "b", "\u{ff}",
+    ];
+    assert_order_preserved(&strings);
+    assert_eq!("a\0b".to_key_bytes(), b"a\0b");
+    let bytes: Vec<Vec<u8>> = strings.iter().map(|s| s.as_bytes().to_vec()).collect();
+    assert_order_preserved(&bytes);
+}
+
+#[test]
+fn test_principals() {
+    let principals: Vec<Principal> = [
+        &[][..],
+        &[0],
+        &[0xff],
+        &[0, 0],
+        &[1, 0],
+        &[0, 0, 0, 0, 0, 0, 0, 7, 1, 1],
+        &[0xff; 29],
+    ]
+    .iter()
+    .map(|bytes| Principal::from_slice(bytes))
+    .collect();
+    assert_order_preserved(&principals);
+}
+
+#[test]
+fn test_tuples() {
+    let strings = ["", "\0", "a", "a\0", "ab"];
+    let mut pairs = vec![];
+    let mut triples = vec![];
+    for s in strings {
+        for n in [0u64, 1, u64::MAX] {
+            pairs.push((s.to_string(), n));
+            for t in strings {
+                triples.push((n, s.as_bytes().to_vec(), t.to_string()));
+            }
+        }
+    }
+    assert_order_preserved(&pairs);
+    assert_order_preserved(&triples);
+    assert_order_preserved(&[
+        (1u8, (2u8, "a")),
+        (1, (2, "a\0")),
+        (1, (3, "")),
+        (2, (0, "")),
+    ]);
+    // The last field is encoded as is.
+    assert_eq!((1u8, "ab").to_key_bytes(), [1, b'a', b'b']);
+    assert_eq!(("a\0", 1u8).to_key_bytes(), [b'a', 0, 0xff, 0, 0, 1]);
+}
+
+#[test]
+fn test_key_in_map() {
+    let mut map = RbTree::new();
+    for (i, name) in ["carol", "alice", "bob"].iter().enumerate() {
+        map.insert(Key::new((i as i64 - 1, name.to_string())), vec![i as u8]);
+    }
+    let keys: Vec<_> = map.iter().map(|(k, _)| k.value().clone()).collect();
+    assert_eq!(
+        keys,
+        [
+            (-1, "carol".to_string()),
+            (0, "alice".to_string()),
+            (1, "bob".to_string())
+        ]
+    );
+    assert_eq!(map.get(&(0i64, "alice").to_key_bytes()), Some(&vec![1]));
+    assert_eq!(map.get_typed(&(0, "alice".to_string())), Some(&vec![1]));
+
+    let bytes = serde_cbor::to_vec(&map).unwrap();
+    let decoded: RbTree<Key<(i64, String)>, Vec<u8>> = serde_cbor::from_slice(&bytes).unwrap();
+    assert_eq!(decoded.root_hash(), map.root_hash());
+}
+
+#[test]
+fn test_typed_accessors() {
+    let mut map: RbTree<Key<(u64, String)>, Vec<u8>> = RbTree::new();
+    for (i, name) in ["alice", "bob", "carol"].iter().enumerate() {
+        map.insert(Key::new((10 * i as u64, name.to_string())), vec![i as u8]);
+    }
+    let alice = (0, "alice".to_string());
+    let bob = (10, "bob".to_string());
+    let carol = (20, "carol".to_string());
+
+    // The encoding of another type misses the key.
+    assert_eq!(map.get(&(10u32, "bob").to_key_bytes()), None);
+    assert_eq!(map.get_typed(&bob), Some(&vec![1]));
+
+    let witness = |k: &(u64, String)| map.witness(&k.to_key_bytes());
+    assert_eq!(map.witness_typed(&bob).reconstruct(), map.root_hash());
+    assert_eq!(
+        serde_cbor::to_vec(&map.witness_typed(&bob)).unwrap(),
+        serde_cbor::to_vec(&witness(&bob)).unwrap()
+    );
+    assert_eq!(
+        serde_cbor::to_vec(&map.multi_witness_typed([&alice, &carol])).unwrap(),
+        serde_cbor::to_vec(
+            &map.multi_witness([&alice.to_key_bytes()[..], &carol.to_key_bytes()[..]])
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        serde_cbor::to_vec(&map.key_range_typed(&alice, &bob)).unwrap(),
+        serde_cbor::to_vec(&map.key_range(&alice.to_key_bytes(), &bob.to_key_bytes())).unwrap()
+    );
+    assert_eq!(
+        serde_cbor::to_vec(&map.value_range_typed(&bob, &carol)).unwrap(),
+        serde_cbor::to_vec(&map.value_range(&bob.to_key_bytes(), &carol.to_key_bytes())).unwrap()
+    );
+
+    map.delete_typed(&bob);
+    assert_eq!(map.get_typed(&bob), None);
+    assert_eq!(map.get_typed(&carol), Some(&vec![2]));
+}
@@ -8,7 +8,19 @@
 //! the hash, under the [path] `/canister/<canister id>/certified_data`.
 //! By providing this certificate, as well as a [`witness`](RbTree::witness)
 //! that the value exists in the hash, you can then prove to the caller that
-//! the IC certified the data.
+//! the IC certified the data. The caller checks both with
+//! `verify_certified_data`, behind the `verify-bls` feature, or with
+//! [`verify_certified_data_with_bls`] and its own BLS signature check.
+//!
+//! Canisters serving HTTP certify their responses with the `IC-Certificate`
+//! header of [`HttpCertificationTree::certificate_header`].
+//!
+//! Typed keys are wrapped in [`Key`], which stores their order-preserving
+//! [encoding](KeyEncoding), and records in [`Candid`], certified as a leaf of
+//! their Candid encoding.
+//!
+//! With the `stable-memory` feature, `StableRbTree` is a certified map
+//! stored in stable memory, which survives upgrades without being rebuilt.
 //!
 //! [certified data]: https://docs.rs/ic-cdk/latest/ic_cdk/api/fn.set_certified_data.html
 //! [data certificate]: https://docs.rs/ic-cdk/latest/ic_cdk/api/fn.data_certificate.html
@@ -69,6 +81,7 @@
 //! }
 //! ```
 
+#![cfg_attr(docsrs, feature(doc_cfg))]
 #![warn(
     elided_lifetimes_in_paths,
     missing_debug_implementations,
@@ -78,8 +91,21 @@
     clippy::missing_safety_doc
 )]
 
+mod cbor;
+mod certificate;
 mod hashtree;
+mod http;
+mod key;
 mod rbtree;
+#[cfg(feature = "stable-memory")]
+mod stable;
+mod value;
 
+pub use crate::certificate::*;
 pub use crate::hashtree::*;
+pub use crate::http::*;
+pub use crate::key::*;
 pub use crate::rbtree::*;
+#[cfg(feature = "stable-memory")]
+pub use crate::stable::*;
+pub use crate::value::*;
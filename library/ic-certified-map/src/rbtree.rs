@@ -1,5 +1,5 @@
 use crate::hashtree::{
-    fork, fork_hash, labeled, labeled_hash, leaf_hash, Hash,
+    fork, fork_hash, labeled, labeled_hash, leaf_hash, merge, Hash,
     HashTree::{self, Empty, Leaf, Pruned},
 };
 use std::borrow::Cow;
@@ -441,6 +441,22 @@ impl<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't> RbTree<K, V> {
         )
     }
 
+    /// Constructs a hash tree that acts as a proof of presence or absence
+    /// of each of the specified keys, with the values of the present ones.
+    ///
+    /// The keys don't need to be sorted or contiguous: the witness is the
+    /// [merge](crate::merge_hash_trees) of the witnesses of each key, where
+    /// the paths to the keys share their nodes.
+    pub fn multi_witness<'a, 'k>(
+        &'a self,
+        keys: impl IntoIterator<Item = &'k [u8]>,
+    ) -> HashTree<'a> {
+        keys.into_iter()
+            .map(|key| self.witness(key))
+            .reduce(merge)
+            .unwrap_or_else(|| Pruned(self.root_hash()))
+    }
+
     /// Returns a witness enumerating all the keys in this map.  The
     /// resulting tree doesn't include values, they are replaced with
     /// "Pruned" nodes.
@@ -778,6 +794,11 @@ impl<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't> RbTree<K, V> {
 
     /// Removes the specified key from the map.
     pub fn delete(&mut self, key: &[u8]) {
+        self.remove(key);
+    }
+
+    /// Removes the specified key from the map, in O(log n) time, and returns its entry.
+    fn remove(&mut self, key: &[u8]) -> Option<(K, V)> {
         fn move_red_left<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't>(
             mut h: Box<Node<K, V>>,
         ) -> Box<Node<K, V>> {
@@ -813,16 +834,17 @@ impl<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't> RbTree<K, V> {
 
         fn delete_min<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't>(
             mut h: Box<Node<K, V>>,
+            removed: &mut Option<(K, V)>,
         ) -> NodeRef<K, V> {
             if h.left.is_none() {
                 debug_assert!(h.right.is_none());
-                drop(h);
+                *removed = Some((h.key, h.value));
                 return None;
             }
             if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
                 h = move_red_left(h);
             }
-            h.left = delete_min(h.left.unwrap());
+            h.left = delete_min(h.left.unwrap(), removed);
             h.update_subtree_hash();
             Some(balance(h))
         }
@@ -830,20 +852,21 @@ impl<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't> RbTree<K, V> {
         fn go<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't>(
             mut h: Box<Node<K, V>>,
             key: &[u8],
+            removed: &mut Option<(K, V)>,
         ) -> NodeRef<K, V> {
             if key < h.key.as_ref() {
                 debug_assert!(h.left.is_some(), "the key must be present in the tree");
                 if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
                     h = move_red_left(h);
                 }
-                h.left = go(h.left.take().unwrap(), key);
+                h.left = go(h.left.take().unwrap(), key, removed);
             } else {
                 if is_red(&h.left) {
                     h = rotate_right(h);
                 }
                 if key == h.key.as_ref() && h.right.is_none() {
                     debug_assert!(h.left.is_none());
-                    drop(h);
+                    *removed = Some((h.key, h.value));
                     return None;
                 }
 
@@ -855,24 +878,23 @@ impl<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't> RbTree<K, V> {
                     let m = min(h.right.as_mut().unwrap());
                     std::mem::swap(&mut h.key, &mut m.key);
                     std::mem::swap(&mut h.value, &mut m.value);
-                    h.right = delete_min(h.right.take().unwrap());
+                    h.right = delete_min(h.right.take().unwrap(), removed);
                 } else {
-                    h.right = go(h.right.take().unwrap(), key);
+                    h.right = go(h.right.take().unwrap(), key, removed);
                 }
             }
             h.update_subtree_hash();
             Some(balance(h))
         }
 
-        if self.get(key).is_none() {
-            return;
-        }
+        self.get(key)?;
 
         if !is_red(&self.root.as_ref().unwrap().left) && !is_red(&self.root.as_ref().unwrap().right)
         {
             self.root.as_mut().unwrap().color = Color::Red;
         }
-        self.root = go(self.root.take().unwrap(), key);
+        let mut removed = None;
+        self.root = go(self.root.take().unwrap(), key, &mut removed);
         if let Some(n) = self.root.as_mut() {
             n.color = Color::Black;
         }
@@ -885,6 +907,91 @@ impl<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't> RbTree<K, V> {
         );
 
         debug_assert!(self.get(key).is_none());
+        removed
+    }
+
+    /// Constructs a map from entries sorted by key, in O(n) time.
+    ///
+    /// The map has the same entries, but not the same shape, as a map built by inserting the
+    /// entries one by one: its root hash differs, and must be set again as the certified data.
+    ///
+    /// # Panics
+    ///
+    /// If the keys are not strictly increasing.
+    pub fn from_sorted_iter(iter: impl IntoIterator<Item = (K, V)>) -> Self {
+        let entries: Vec<(K, V)> = iter.into_iter().collect();
+        assert!(
+            entries
+                .windows(2)
+                .all(|w| w[0].0.as_ref() < w[1].0.as_ref()),
+            "the keys must be sorted and unique"
+        );
+        Self::from_sorted_vec(entries)
+    }
+
+    fn from_sorted_vec(entries: Vec<(K, V)>) -> Self {
+        let len = entries.len();
+        let black_height = (len + 1).ilog2();
+        let root = build(&mut entries.into_iter(), len, black_height);
+
+        #[cfg(test)]
+        debug_assert!(
+            is_balanced(&root),
+            "the tree is not balanced:\n{:?}",
+            DebugView(&root)
+        );
+
+        Self { root }
+    }
+
+    fn take_entries(&mut self) -> Vec<(K, V)> {
+        let mut entries = vec![];
+        into_entries(self.root.take(), &mut entries);
+        entries
+    }
+
+    /// Retains only the entries for which `f` returns true, in O(n) time.
+    ///
+    /// The map is rebuilt like with [`from_sorted_iter`](Self::from_sorted_iter).
+    pub fn retain(&mut self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        let mut entries = self.take_entries();
+        entries.retain_mut(|(k, v)| f(k, v));
+        *self = Self::from_sorted_vec(entries);
+    }
+
+    /// Removes the keys in the specified range, bounds included, in O(k log n) time, where k
+    /// is the number of removed keys.
+    ///
+    /// Each key is deleted like with [`delete`](Self::delete): removing most of a large map is
+    /// slower than [`retain`](Self::retain), which rebuilds it in O(n) time.
+    pub fn remove_range(&mut self, first: &[u8], last: &[u8]) {
+        for key in keys_in_range(&self.root, first, Some(last)) {
+            self.remove(&key);
+        }
+    }
+
+    /// Splits the map in two at `key`: the entries from `key` on are moved to the returned map.
+    ///
+    /// This takes O(k log n) time, where k is the number of moved entries, which are deleted
+    /// one by one from the map and built into the returned map like with
+    /// [`from_sorted_iter`](Self::from_sorted_iter).
+    pub fn split_off(&mut self, key: &[u8]) -> Self {
+        let tail = keys_in_range(&self.root, key, None)
+            .iter()
+            .map(|k| self.remove(k).unwrap())
+            .collect();
+        Self::from_sorted_vec(tail)
+    }
+
+    /// Moves all the entries of `other` into the map, leaving `other` empty. The values of
+    /// `other` replace those of the map for the keys in both maps.
+    ///
+    /// This takes O(m log (n + m)) time, where m is the length of `other`, whose entries are
+    /// inserted one by one.
+    pub fn append(&mut self, other: &mut Self) {
+        for (key, value) in other.take_entries() {
+            self.insert(key, value);
+        }
     }
 }
 
@@ -976,7 +1083,11 @@ where
     }
 }
 
-fn three_way_fork<'a>(l: HashTree<'a>, m: HashTree<'a>, r: HashTree<'a>) -> HashTree<'a> {
+pub(crate) fn three_way_fork<'a>(
+    l: HashTree<'a>,
+    m: HashTree<'a>,
+    r: HashTree<'a>,
+) -> HashTree<'a> {
     match (l, m, r) {
         (Empty, m, Empty) => m,
         (l, m, Empty) => fork(l, m),
@@ -1007,6 +1118,104 @@ fn balance<'t, K: AsRef<[u8]> + 't, V: AsHashTree + 't>(mut h: Box<Node<K, V>>)
     h
 }
 
+/// Collects the keys from `first` to `last`, bounds included, or to the end if `last` is
+/// `None`, in O(log n + k) time.
+fn keys_in_range<K: AsRef<[u8]>, V>(
+    n: &NodeRef<K, V>,
+    first: &[u8],
+    last: Option<&[u8]>,
+) -> Vec<Vec<u8>> {
+    fn go<K: AsRef<[u8]>, V>(
+        n: &NodeRef<K, V>,
+        first: &[u8],
+        last: Option<&[u8]>,
+        keys: &mut Vec<Vec<u8>>,
+    ) {
+        if let Some(n) = n {
+            let key = n.key.as_ref();
+            let after_first = first <= key;
+            let before_last = !matches!(last, Some(last) if key > last);
+            if after_first {
+                go(&n.left, first, last, keys);
+            }
+            if after_first && before_last {
+                keys.push(key.to_vec());
+            }
+            if before_last {
+                go(&n.right, first, last, keys);
+            }
+        }
+    }
+
+    let mut keys = vec![];
+    go(n, first, last, &mut keys);
+    keys
+}
+
+fn into_entries<K, V>(n: NodeRef<K, V>, entries: &mut Vec<(K, V)>) {
+    if let Some(n) = n {
+        let Node {
+            key,
+            value,
+            left,
+            right,
+            ..
+        } = *n;
+        into_entries(left, entries);
+        entries.push((key, value));
+        into_entries(right, entries);
+    }
+}
+
+/// Builds a tree of `len` entries, whose paths all have `black_height` black nodes.
+///
+/// A tree of black height `b` has between `2^b - 1` entries, when all its nodes are 2-nodes,
+/// and `3^b - 1` entries, when all its nodes are 3-nodes, i.e. a black node with a red left
+/// child. The root is a 2-node if the subtrees can hold the entries, a 3-node otherwise, and
+/// the entries are spread evenly across the subtrees.
+fn build<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't>(
+    entries: &mut impl Iterator<Item = (K, V)>,
+    len: usize,
+    black_height: u32,
+) -> NodeRef<K, V> {
+    fn node<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't>(
+        left: NodeRef<K, V>,
+        (key, value): (K, V),
+        right: NodeRef<K, V>,
+        color: Color,
+    ) -> Box<Node<K, V>> {
+        let mut n = Node::new(key, value);
+        n.left = left;
+        n.right = right;
+        n.color = color;
+        n.update_subtree_hash();
+        n
+    }
+
+    if len == 0 {
+        return None;
+    }
+    let b = black_height - 1;
+    let max_subtree_len = 3usize.saturating_pow(b) - 1;
+    if len - 1 <= max_subtree_len.saturating_mul(2) {
+        let left_len = (len - 1).div_ceil(2);
+        let left = build(entries, left_len, b);
+        let entry = entries.next().unwrap();
+        let right = build(entries, len - 1 - left_len, b);
+        Some(node(left, entry, right, Color::Black))
+    } else {
+        let m = len - 2;
+        let lens = [m.div_ceil(3), (m + 1) / 3, m / 3];
+        let first = build(entries, lens[0], b);
+        let red_entry = entries.next().unwrap();
+        let second = build(entries, lens[1], b);
+        let red = node(first, red_entry, second, Color::Red);
+        let black_entry = entries.next().unwrap();
+        let third = build(entries, lens[2], b);
+        Some(node(Some(red), black_entry, third, Color::Black))
+    }
+}
+
 /// Make a left-leaning link lean to the right.
 fn rotate_right<'t, K: 't + AsRef<[u8]>, V: AsHashTree + 't>(
     mut h: Box<Node<K, V>>,
@@ -7,8 +7,8 @@ fn insert(t: &mut TreeOfBytes, k: impl AsRef<[u8]>, v: impl AsRef<[u8]>) {
     t.insert(k.as_ref().to_vec(), v.as_ref().to_vec())
 }
 
-fn get_labels<'a>(ht: &HashTree<'a>) -> Vec<&'a [u8]> {
-    fn go<'a>(t: &HashTree<'a>, keys: &mut Vec<&'a [u8]>) {
+fn get_labels<'a>(ht: &'a HashTree<'_>) -> Vec<&'a [u8]> {
+    fn go<'a>(t: &'a HashTree<'_>, keys: &mut Vec<&'a [u8]>) {
         match t {
             HashTree::Labeled(key, _) => {
                 keys.push(key);
@@ -251,10 +251,10 @@ fn test_nested_witness() {
     assert_eq!(ht.reconstruct(), rb.root_hash());
     match ht {
         HashTree::Labeled(lt, tt) => {
-            assert_eq!(lt, b"top");
+            assert_eq!(&lt[..], b"top");
             match &(*tt) {
                 HashTree::Labeled(lb, _) => {
-                    assert_eq!(lb, b"bottom");
+                    assert_eq!(&lb[..], b"bottom");
                 }
                 other => panic!("unexpected nested tree: {:?}", other),
             }
@@ -322,6 +322,199 @@ fn test_witness_value_range() {
     );
 }
 
+#[test]
+fn test_multi_witness() {
+    use crate::{merge_hash_trees, LookupResult};
+
+    let mut t = TreeOfBytes::new();
+    for i in 0u64..100 {
+        insert(&mut t, (2 * i).to_be_bytes(), i.to_le_bytes());
+    }
+    let keys: Vec<[u8; 8]> = [42u64, 7, 150, 3, 199, 0]
+        .iter()
+        .map(|i| i.to_be_bytes())
+        .collect();
+
+    let ht = t.multi_witness(keys.iter().map(|k| &k[..]));
+    assert_eq!(ht.reconstruct(), t.root_hash());
+    for key in &keys {
+        let i = u64::from_be_bytes(*key);
+        let value = (i / 2).to_le_bytes();
+        let expected = if i % 2 == 0 {
+            LookupResult::Found(&value[..])
+        } else {
+            LookupResult::Absent
+        };
+        assert_eq!(
+            ht.lookup_path(&[&key[..]]),
+            expected,
+            "key: {}",
+            hex::encode(key)
+        );
+    }
+    // Keys out of the witness are neither proven present nor absent.
+    assert_eq!(
+        ht.lookup_path(&[&100u64.to_be_bytes()[..]]),
+        LookupResult::Unknown
+    );
+
+    let merged = keys
+        .iter()
+        .map(|k| t.witness(&k[..]))
+        .reduce(merge_hash_trees)
+        .unwrap();
+    let size = |ht: &HashTree<'_>| serde_cbor::to_vec(ht).unwrap().len();
+    assert_eq!(size(&merged), size(&ht));
+    let separate: usize = keys.iter().map(|k| size(&t.witness(&k[..]))).sum();
+    assert!(size(&ht) < separate, "{} >= {}", size(&ht), separate);
+
+    assert_eq!(
+        t.multi_witness(std::iter::empty()).reconstruct(),
+        t.root_hash()
+    );
+    assert_eq!(
+        TreeOfBytes::new()
+            .multi_witness(std::iter::empty())
+            .reconstruct(),
+        TreeOfBytes::new().root_hash()
+    );
+}
+
+#[test]
+#[should_panic(expected = "different root hashes")]
+fn test_merge_different_trees() {
+    let mut t1 = TreeOfBytes::new();
+    insert(&mut t1, b"a", b"x");
+    let mut t2 = TreeOfBytes::new();
+    insert(&mut t2, b"a", b"y");
+    crate::merge_hash_trees(t1.witness(b"a"), t2.witness(b"a"));
+}
+
+// Checks the invariants of the tree, including the hashes, and returns its entries.
+fn check_tree(t: &TreeOfBytes) -> Vec<(Vec<u8>, Vec<u8>)> {
+    fn go(n: &NodeRef<Vec<u8>, Vec<u8>>) {
+        if let Some(n) = n {
+            assert!(!is_red(&n.right), "a red link leans right");
+            assert_eq!(n.subtree_hash, n.compute_subtree_hash());
+            go(&n.left);
+            go(&n.right);
+        }
+    }
+    assert!(is_balanced(&t.root));
+    assert!(!is_red(&t.root));
+    go(&t.root);
+    assert_eq!(t.root_hash(), t.as_hash_tree().reconstruct());
+    t.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+fn entries(keys: impl IntoIterator<Item = u32>) -> Vec<(Vec<u8>, Vec<u8>)> {
+    keys.into_iter()
+        .map(|i| (i.to_be_bytes().to_vec(), i.to_le_bytes().to_vec()))
+        .collect()
+}
+
+#[test]
+fn test_from_sorted_iter() {
+    for len in 0..200 {
+        let t = TreeOfBytes::from_sorted_iter(entries(0..len));
+        assert_eq!(check_tree(&t), entries(0..len));
+    }
+    let mut t = TreeOfBytes::from_sorted_iter(entries((0..1000).map(|i| i * 2)));
+    for i in 0..500u32 {
+        insert(&mut t, (i * 4 + 1).to_be_bytes(), b"odd");
+        t.delete(&(i * 4).to_be_bytes());
+    }
+    check_tree(&t);
+    let ht = t.witness(&5u32.to_be_bytes());
+    assert_eq!(ht.reconstruct(), t.root_hash());
+    assert_eq!(get_leaf_values(&ht), vec![b"odd"]);
+}
+
+#[test]
+#[should_panic(expected = "the keys must be sorted and unique")]
+fn test_from_unsorted_iter() {
+    TreeOfBytes::from_sorted_iter(entries([1, 3, 2]));
+}
+
+#[test]
+fn test_retain_and_remove_range() {
+    let mut t = TreeOfBytes::from_sorted_iter(entries(0..100));
+    t.retain(|k, v| {
+        v.push(0);
+        k[3] % 3 != 0
+    });
+    let expected: Vec<_> = entries((0..100).filter(|i| i % 3 != 0))
+        .into_iter()
+        .map(|(k, mut v)| {
+            v.push(0);
+            (k, v)
+        })
+        .collect();
+    assert_eq!(check_tree(&t), expected);
+
+    t.remove_range(&10u32.to_be_bytes(), &89u32.to_be_bytes());
+    let expected: Vec<_> = expected
+        .into_iter()
+        .filter(|(k, _)| !(10..90).contains(&k[3]))
+        .collect();
+    assert_eq!(check_tree(&t), expected);
+
+    t.remove_range(&[], &[0xff; 4]);
+    assert!(t.is_empty());
+}
+
+#[test]
+fn test_remove_range_model() {
+    for first in 0..20u32 {
+        for last in first..21 {
+            let mut t = TreeOfBytes::from_sorted_iter(entries(0..20));
+            t.remove_range(&first.to_be_bytes(), &last.to_be_bytes());
+            let expected = entries((0..20).filter(|i| !(first..=last).contains(i)));
+            assert_eq!(check_tree(&t), expected);
+        }
+    }
+}
+
+#[test]
+fn test_split_off_model() {
+    for at in 0..=50u32 {
+        let mut t = TreeOfBytes::new();
+        for (k, v) in entries(0..50) {
+            insert(&mut t, k, v);
+        }
+        let mut tail = t.split_off(&at.to_be_bytes());
+        assert_eq!(check_tree(&t), entries(0..at));
+        assert_eq!(check_tree(&tail), entries(at..50));
+        t.append(&mut tail);
+        assert!(tail.is_empty());
+        assert_eq!(check_tree(&t), entries(0..50));
+    }
+}
+
+#[test]
+fn test_split_off_and_append() {
+    let mut t = TreeOfBytes::from_sorted_iter(entries(0..100));
+    let mut tail = t.split_off(&40u32.to_be_bytes());
+    assert_eq!(check_tree(&t), entries(0..40));
+    assert_eq!(check_tree(&tail), entries(40..100));
+
+    let empty = t.split_off(&[0xff; 5]);
+    assert!(empty.is_empty());
+
+    let mut overlap = TreeOfBytes::new();
+    insert(&mut overlap, 30u32.to_be_bytes(), b"new");
+    insert(&mut overlap, 200u32.to_be_bytes(), b"new");
+    tail.append(&mut overlap);
+    assert!(overlap.is_empty());
+    t.append(&mut tail);
+    assert!(tail.is_empty());
+
+    let mut expected = entries(0..100);
+    expected[30].1 = b"new".to_vec();
+    expected.push((200u32.to_be_bytes().to_vec(), b"new".to_vec()));
+    assert_eq!(check_tree(&t), expected);
+}
+
 #[test]
 #[allow(clippy::map_identity)]
 fn test_iter() {
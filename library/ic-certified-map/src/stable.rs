@@ -0,0 +1,933 @@
+#[cfg(test)]
+mod test;
+
+use crate::hashtree::{
+    fork_hash, labeled_hash, leaf_hash, merge, Hash,
+    HashTree::{self, Empty, Leaf, Pruned},
+};
+use crate::rbtree::{three_way_fork, AsHashTree};
+use ic_cdk::api::stable::{StableMemory, StableMemoryError, WASM_PAGE_SIZE_IN_BYTES};
+use std::borrow::Cow;
+use std::cmp::Ordering::{Equal, Greater, Less};
+use std::fmt;
+
+// Layout of the stable memory, all integers are little-endian:
+//
+// [0, 64): header
+//   magic "CMAP", layout version, 3 reserved bytes, max key length (u32), max value length (u32),
+//   root node, head of the list of free slots, end of the allocated slots, number of entries (u64)
+// [64, ..): node slots of NODE_HEADER_SIZE + max key length + max value length bytes
+//   left child, right child (u64), color (u8), key length, value length (u32),
+//   value hash, subtree hash (32 bytes), key, value
+//
+// Address 0 is the header, so it stands for "no node". A free slot starts with the address of the
+// next free slot.
+const MAGIC: &[u8; 4] = b"CMAP";
+const LAYOUT_VERSION: u8 = 1;
+const HEADER_SIZE: u64 = 64;
+
+const LEFT: u64 = 0;
+const RIGHT: u64 = 8;
+const COLOR: u64 = 16;
+const KEY_LEN: u64 = 17;
+const VALUE_LEN: u64 = 21;
+const VALUE_HASH: u64 = 25;
+const SUBTREE_HASH: u64 = 57;
+const NODE_HEADER_SIZE: u64 = 89;
+
+type Addr = u64;
+const NULL: Addr = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Red,
+    Black,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeyBound {
+    Exact(Vec<u8>),
+    Neighbor(Vec<u8>),
+}
+
+impl KeyBound {
+    fn key(&self) -> &[u8] {
+        match self {
+            KeyBound::Exact(key) => key,
+            KeyBound::Neighbor(key) => key,
+        }
+    }
+}
+
+fn owned_labeled(label: Vec<u8>, t: HashTree<'static>) -> HashTree<'static> {
+    HashTree::Labeled(Cow::Owned(label), Box::new(t))
+}
+
+/// A certified map like [`RbTree`](crate::RbTree), whose nodes live in stable memory.
+///
+/// The map survives upgrades as is: [`load`](Self::load) it in `post_upgrade`, without
+/// rebuilding it nor re-hashing its entries. Keys and values are byte strings, a value is
+/// certified as a [`Leaf`](HashTree::Leaf), like a `Vec<u8>` in an `RbTree`.
+///
+/// Each entry takes a slot of the size of the longest key and value the map accepts, set with
+/// [`init`](Self::init). The map uses the whole memory: give it a [`StableMemory`] of its own.
+///
+/// ```no_run
+/// # use ic_cdk::api::stable::CanisterStableMemory;
+/// # use ic_certified_map::*;
+/// # use std::cell::RefCell;
+/// thread_local! {
+///     static MAP: RefCell<Option<StableRbTree<CanisterStableMemory>>> = RefCell::new(None);
+/// }
+///
+/// #[ic_cdk::init]
+/// fn init() {
+///     let map = StableRbTree::init(CanisterStableMemory::default(), 64, 1024).unwrap();
+///     MAP.with(|m| *m.borrow_mut() = Some(map));
+/// }
+///
+/// #[ic_cdk::post_upgrade]
+/// fn post_upgrade() {
+///     let map = StableRbTree::load(CanisterStableMemory::default()).expect("no certified map");
+///     MAP.with(|m| *m.borrow_mut() = Some(map));
+/// }
+///
+/// #[ic_cdk::update]
+/// fn put(key: String, value: Vec<u8>) {
+///     MAP.with(|m| {
+///         let mut m = m.borrow_mut();
+///         let map = m.as_mut().unwrap();
+///         map.insert(key.as_bytes(), &value).unwrap();
+///         ic_cdk::api::set_certified_data(&map.root_hash());
+///     });
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "stable-memory")))]
+pub struct StableRbTree<M: StableMemory> {
+    memory: M,
+    max_key_len: u32,
+    max_value_len: u32,
+    root: Addr,
+    free: Addr,
+    next: Addr,
+    len: u64,
+}
+
+impl<M: StableMemory> fmt::Debug for StableRbTree<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StableRbTree")
+            .field("max_key_len", &self.max_key_len)
+            .field("max_value_len", &self.max_value_len)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<M: StableMemory> AsHashTree for StableRbTree<M> {
+    fn root_hash(&self) -> Hash {
+        if self.root == NULL {
+            Empty.reconstruct()
+        } else {
+            self.subtree_hash(self.root)
+        }
+    }
+
+    fn as_hash_tree(&self) -> HashTree<'_> {
+        self.full_witness_tree(self.root, Self::data_tree)
+    }
+}
+
+impl<M: StableMemory> StableRbTree<M> {
+    /// Creates an empty map in `memory`, overwriting any map it holds, for keys of at most
+    /// `max_key_len` bytes and values of at most `max_value_len` bytes.
+    pub fn init(
+        memory: M,
+        max_key_len: u32,
+        max_value_len: u32,
+    ) -> Result<Self, StableMemoryError> {
+        let tree = Self {
+            memory,
+            max_key_len,
+            max_value_len,
+            root: NULL,
+            free: NULL,
+            next: HEADER_SIZE,
+            len: 0,
+        };
+        tree.ensure_capacity(HEADER_SIZE)?;
+        tree.save_header();
+        Ok(tree)
+    }
+
+    /// Loads the map stored in `memory` by a previous [`init`](Self::init), e.g. before an upgrade.
+    ///
+    /// Returns `None` if `memory` doesn't hold a map.
+    pub fn load(memory: M) -> Option<Self> {
+        if memory.stable_size() == 0 {
+            return None;
+        }
+        let mut header = [0; HEADER_SIZE as usize];
+        memory.stable_read(0, &mut header);
+        if &header[0..4] != MAGIC || header[4] != LAYOUT_VERSION {
+            return None;
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_le_bytes(header[i..i + 8].try_into().unwrap());
+        Some(Self {
+            max_key_len: u32_at(8),
+            max_value_len: u32_at(12),
+            root: u64_at(16),
+            free: u64_at(24),
+            next: u64_at(32),
+            len: u64_at(40),
+            memory,
+        })
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.root == NULL
+    }
+
+    /// Looks up the key in the map and returns the associated value, if there is one.
+    pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let n = self.find(key);
+        (n != NULL).then(|| self.value(n))
+    }
+
+    /// Inserts a key-value entry into the map.
+    ///
+    /// Fails, leaving the map untouched, if the stable memory cannot grow to store a new entry.
+    ///
+    /// # Panics
+    ///
+    /// If the key or the value is longer than the map accepts.
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) -> Result<(), StableMemoryError> {
+        assert!(
+            key.len() <= self.max_key_len as usize,
+            "the key is {} bytes long, the map accepts at most {}",
+            key.len(),
+            self.max_key_len
+        );
+        assert!(
+            value.len() <= self.max_value_len as usize,
+            "the value is {} bytes long, the map accepts at most {}",
+            value.len(),
+            self.max_value_len
+        );
+        let slot = if self.find(key) == NULL {
+            Some(self.allocate()?)
+        } else {
+            None
+        };
+        let root = self.insert_at(self.root, key, value, slot);
+        self.set_color(root, Color::Black);
+        self.root = root;
+        if slot.is_some() {
+            self.len += 1;
+        }
+        self.save_header();
+        Ok(())
+    }
+
+    /// Removes the specified key from the map. Its slot is reused by the next insertion.
+    pub fn delete(&mut self, key: &[u8]) {
+        if self.find(key) == NULL {
+            return;
+        }
+        let root = self.root;
+        if !self.is_red(self.left(root)) && !self.is_red(self.right(root)) {
+            self.set_color(root, Color::Red);
+        }
+        self.root = self.delete_at(root, key);
+        if self.root != NULL {
+            self.set_color(self.root, Color::Black);
+        }
+        self.len -= 1;
+        self.save_header();
+    }
+
+    /// Constructs a hash tree that acts as a proof that there is a
+    /// entry with the specified key in this map.  The proof also
+    /// contains the value in question.
+    ///
+    /// If the key is not in the map, returns a proof of absence.
+    pub fn witness(&self, key: &[u8]) -> HashTree<'static> {
+        if let Some(t) = self.lookup_and_build_witness(self.root, key) {
+            return t;
+        }
+        self.range_witness(
+            self.lower_bound(key),
+            self.upper_bound(key),
+            Self::witness_tree,
+        )
+    }
+
+    /// Constructs a hash tree that acts as a proof of presence or absence
+    /// of each of the specified keys, see [`RbTree::multi_witness`](crate::RbTree::multi_witness).
+    pub fn multi_witness<'k>(&self, keys: impl IntoIterator<Item = &'k [u8]>) -> HashTree<'static> {
+        keys.into_iter()
+            .map(|key| self.witness(key))
+            .reduce(merge)
+            .unwrap_or_else(|| Pruned(self.root_hash()))
+    }
+
+    /// Returns a witness enumerating all the keys in this map.  The
+    /// resulting tree doesn't include values, they are replaced with
+    /// "Pruned" nodes.
+    pub fn keys(&self) -> HashTree<'static> {
+        self.full_witness_tree(self.root, Self::witness_tree)
+    }
+
+    /// Returns a witness for the keys in the specified range.  The
+    /// resulting tree doesn't include values, they are replaced with
+    /// "Pruned" nodes.
+    pub fn key_range(&self, first: &[u8], last: &[u8]) -> HashTree<'static> {
+        self.range_witness(
+            self.lower_bound(first),
+            self.upper_bound(last),
+            Self::witness_tree,
+        )
+    }
+
+    /// Returns a witness for the key-value pairs in the specified range.
+    /// The resulting tree contains both keys and values.
+    pub fn value_range(&self, first: &[u8], last: &[u8]) -> HashTree<'static> {
+        self.range_witness(
+            self.lower_bound(first),
+            self.upper_bound(last),
+            Self::data_tree,
+        )
+    }
+
+    /// Returns a witness that enumerates all the keys starting with
+    /// the specified prefix.
+    pub fn keys_with_prefix(&self, prefix: &[u8]) -> HashTree<'static> {
+        self.range_witness(
+            self.lower_bound(prefix),
+            self.right_prefix_neighbor(prefix),
+            Self::witness_tree,
+        )
+    }
+
+    /// Creates an iterator over the map's keys and values, in key order.
+    pub fn iter(&self) -> StableIter<'_, M> {
+        let mut iter = StableIter {
+            tree: self,
+            stack: vec![],
+        };
+        iter.push_left_spine(self.root);
+        iter
+    }
+
+    // Memory access.
+
+    fn read_u64(&self, offset: u64) -> u64 {
+        let mut buf = [0; 8];
+        self.memory.stable_read(offset, &mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn read_u32(&self, offset: u64) -> u32 {
+        let mut buf = [0; 4];
+        self.memory.stable_read(offset, &mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn read_hash(&self, offset: u64) -> Hash {
+        let mut hash = [0; 32];
+        self.memory.stable_read(offset, &mut hash);
+        hash
+    }
+
+    fn read_bytes(&self, offset: u64, len: u32) -> Vec<u8> {
+        let mut buf = vec![0; len as usize];
+        self.memory.stable_read(offset, &mut buf);
+        buf
+    }
+
+    fn write_u64(&self, offset: u64, value: u64) {
+        self.memory.stable_write(offset, &value.to_le_bytes());
+    }
+
+    fn ensure_capacity(&self, end: u64) -> Result<(), StableMemoryError> {
+        let pages = end.div_ceil(WASM_PAGE_SIZE_IN_BYTES);
+        let size = self.memory.stable_size();
+        if pages > size {
+            self.memory.stable_grow(pages - size)?;
+        }
+        Ok(())
+    }
+
+    fn save_header(&self) {
+        let mut header = [0; HEADER_SIZE as usize];
+        header[0..4].copy_from_slice(MAGIC);
+        header[4] = LAYOUT_VERSION;
+        header[8..12].copy_from_slice(&self.max_key_len.to_le_bytes());
+        header[12..16].copy_from_slice(&self.max_value_len.to_le_bytes());
+        header[16..24].copy_from_slice(&self.root.to_le_bytes());
+        header[24..32].copy_from_slice(&self.free.to_le_bytes());
+        header[32..40].copy_from_slice(&self.next.to_le_bytes());
+        header[40..48].copy_from_slice(&self.len.to_le_bytes());
+        self.memory.stable_write(0, &header);
+    }
+
+    fn slot_size(&self) -> u64 {
+        NODE_HEADER_SIZE + u64::from(self.max_key_len) + u64::from(self.max_value_len)
+    }
+
+    fn allocate(&mut self) -> Result<Addr, StableMemoryError> {
+        if self.free != NULL {
+            let n = self.free;
+            self.free = self.read_u64(n);
+            return Ok(n);
+        }
+        let n = self.next;
+        self.ensure_capacity(n + self.slot_size())?;
+        self.next += self.slot_size();
+        Ok(n)
+    }
+
+    fn release(&mut self, n: Addr) {
+        self.write_u64(n, self.free);
+        self.free = n;
+    }
+
+    // Node fields.
+
+    fn left(&self, n: Addr) -> Addr {
+        if n == NULL {
+            NULL
+        } else {
+            self.read_u64(n + LEFT)
+        }
+    }
+
+    fn right(&self, n: Addr) -> Addr {
+        if n == NULL {
+            NULL
+        } else {
+            self.read_u64(n + RIGHT)
+        }
+    }
+
+    fn set_left(&self, n: Addr, left: Addr) {
+        self.write_u64(n + LEFT, left);
+    }
+
+    fn set_right(&self, n: Addr, right: Addr) {
+        self.write_u64(n + RIGHT, right);
+    }
+
+    fn color(&self, n: Addr) -> Color {
+        let mut buf = [0];
+        self.memory.stable_read(n + COLOR, &mut buf);
+        if buf[0] == 0 {
+            Color::Red
+        } else {
+            Color::Black
+        }
+    }
+
+    fn set_color(&self, n: Addr, color: Color) {
+        self.memory
+            .stable_write(n + COLOR, &[(color == Color::Black) as u8]);
+    }
+
+    fn flip_color(&self, n: Addr) {
+        match self.color(n) {
+            Color::Red => self.set_color(n, Color::Black),
+            Color::Black => self.set_color(n, Color::Red),
+        }
+    }
+
+    fn is_red(&self, n: Addr) -> bool {
+        n != NULL && self.color(n) == Color::Red
+    }
+
+    fn key(&self, n: Addr) -> Vec<u8> {
+        self.read_bytes(n + NODE_HEADER_SIZE, self.read_u32(n + KEY_LEN))
+    }
+
+    fn value(&self, n: Addr) -> Vec<u8> {
+        let offset = n + NODE_HEADER_SIZE + u64::from(self.max_key_len);
+        self.read_bytes(offset, self.read_u32(n + VALUE_LEN))
+    }
+
+    fn value_hash(&self, n: Addr) -> Hash {
+        self.read_hash(n + VALUE_HASH)
+    }
+
+    fn subtree_hash(&self, n: Addr) -> Hash {
+        self.read_hash(n + SUBTREE_HASH)
+    }
+
+    fn write_key(&self, n: Addr, key: &[u8]) {
+        self.memory
+            .stable_write(n + KEY_LEN, &(key.len() as u32).to_le_bytes());
+        self.memory.stable_write(n + NODE_HEADER_SIZE, key);
+    }
+
+    fn write_value(&self, n: Addr, value: &[u8]) {
+        let offset = n + NODE_HEADER_SIZE + u64::from(self.max_key_len);
+        self.memory
+            .stable_write(n + VALUE_LEN, &(value.len() as u32).to_le_bytes());
+        self.memory.stable_write(offset, value);
+        self.memory.stable_write(n + VALUE_HASH, &leaf_hash(value));
+    }
+
+    fn data_hash(&self, n: Addr) -> Hash {
+        labeled_hash(&self.key(n), &self.value_hash(n))
+    }
+
+    fn update_subtree_hash(&self, n: Addr) {
+        let h = self.data_hash(n);
+        let (l, r) = (self.left(n), self.right(n));
+        let hash = match (l != NULL, r != NULL) {
+            (false, false) => h,
+            (true, false) => fork_hash(&self.subtree_hash(l), &h),
+            (false, true) => fork_hash(&h, &self.subtree_hash(r)),
+            (true, true) => fork_hash(&self.subtree_hash(l), &fork_hash(&h, &self.subtree_hash(r))),
+        };
+        self.memory.stable_write(n + SUBTREE_HASH, &hash);
+    }
+
+    fn find(&self, key: &[u8]) -> Addr {
+        let mut n = self.root;
+        while n != NULL {
+            match key.cmp(&self.key(n)) {
+                Equal => return n,
+                Less => n = self.left(n),
+                Greater => n = self.right(n),
+            }
+        }
+        NULL
+    }
+
+    // Balancing, following `RbTree`, so that both maps have the same shape, and the same
+    // hashes, after the same operations.
+
+    fn insert_at(&self, h: Addr, key: &[u8], value: &[u8], slot: Option<Addr>) -> Addr {
+        if h == NULL {
+            let n = slot.expect("a slot is allocated for a new key");
+            self.set_left(n, NULL);
+            self.set_right(n, NULL);
+            self.set_color(n, Color::Red);
+            self.write_key(n, key);
+            self.write_value(n, value);
+            self.update_subtree_hash(n);
+            return n;
+        }
+        match key.cmp(&self.key(h)) {
+            Equal => self.write_value(h, value),
+            Less => self.set_left(h, self.insert_at(self.left(h), key, value, slot)),
+            Greater => self.set_right(h, self.insert_at(self.right(h), key, value, slot)),
+        }
+        self.update_subtree_hash(h);
+        self.balance(h)
+    }
+
+    fn delete_at(&mut self, mut h: Addr, key: &[u8]) -> Addr {
+        if key < &self.key(h)[..] {
+            if !self.is_red(self.left(h)) && !self.is_red(self.left(self.left(h))) {
+                h = self.move_red_left(h);
+            }
+            let left = self.delete_at(self.left(h), key);
+            self.set_left(h, left);
+        } else {
+            if self.is_red(self.left(h)) {
+                h = self.rotate_right(h);
+            }
+            if key == self.key(h) && self.right(h) == NULL {
+                debug_assert_eq!(self.left(h), NULL);
+                self.release(h);
+                return NULL;
+            }
+            if !self.is_red(self.right(h)) && !self.is_red(self.left(self.right(h))) {
+                h = self.move_red_right(h);
+            }
+            if key == self.key(h) {
+                let mut m = self.right(h);
+                while self.left(m) != NULL {
+                    m = self.left(m);
+                }
+                let (key, value) = (self.key(h), self.value(h));
+                self.write_key(h, &self.key(m));
+                self.write_value(h, &self.value(m));
+                self.write_key(m, &key);
+                self.write_value(m, &value);
+                let right = self.delete_min(self.right(h));
+                self.set_right(h, right);
+            } else {
+                let right = self.delete_at(self.right(h), key);
+                self.set_right(h, right);
+            }
+        }
+        self.update_subtree_hash(h);
+        self.balance(h)
+    }
+
+    fn delete_min(&mut self, mut h: Addr) -> Addr {
+        if self.left(h) == NULL {
+            debug_assert_eq!(self.right(h), NULL);
+            self.release(h);
+            return NULL;
+        }
+        if !self.is_red(self.left(h)) && !self.is_red(self.left(self.left(h))) {
+            h = self.move_red_left(h);
+        }
+        let left = self.delete_min(self.left(h));
+        self.set_left(h, left);
+        self.update_subtree_hash(h);
+        self.balance(h)
+    }
+
+    fn move_red_left(&self, mut h: Addr) -> Addr {
+        self.flip_colors(h);
+        if self.is_red(self.left(self.right(h))) {
+            self.set_right(h, self.rotate_right(self.right(h)));
+            h = self.rotate_left(h);
+            self.flip_colors(h);
+        }
+        h
+    }
+
+    fn move_red_right(&self, mut h: Addr) -> Addr {
+        self.flip_colors(h);
+        if self.is_red(self.left(self.left(h))) {
+            h = self.rotate_right(h);
+            self.flip_colors(h);
+        }
+        h
+    }
+
+    fn balance(&self, mut h: Addr) -> Addr {
+        if self.is_red(self.right(h)) && !self.is_red(self.left(h)) {
+            h = self.rotate_left(h);
+        }
+        if self.is_red(self.left(h)) && self.is_red(self.left(self.left(h))) {
+            h = self.rotate_right(h);
+        }
+        if self.is_red(self.left(h)) && self.is_red(self.right(h)) {
+            self.flip_colors(h);
+        }
+        h
+    }
+
+    fn rotate_right(&self, h: Addr) -> Addr {
+        debug_assert!(self.is_red(self.left(h)));
+        let x = self.left(h);
+        self.set_left(h, self.right(x));
+        self.update_subtree_hash(h);
+        self.set_right(x, h);
+        self.set_color(x, self.color(h));
+        self.set_color(h, Color::Red);
+        self.update_subtree_hash(x);
+        x
+    }
+
+    fn rotate_left(&self, h: Addr) -> Addr {
+        debug_assert!(self.is_red(self.right(h)));
+        let x = self.right(h);
+        self.set_right(h, self.left(x));
+        self.update_subtree_hash(h);
+        self.set_left(x, h);
+        self.set_color(x, self.color(h));
+        self.set_color(h, Color::Red);
+        self.update_subtree_hash(x);
+        x
+    }
+
+    fn flip_colors(&self, h: Addr) {
+        self.flip_color(h);
+        self.flip_color(self.left(h));
+        self.flip_color(self.right(h));
+    }
+
+    // Witnesses, following `RbTree`.
+
+    fn left_hash_tree(&self, n: Addr) -> HashTree<'static> {
+        match self.left(n) {
+            NULL => Empty,
+            l => Pruned(self.subtree_hash(l)),
+        }
+    }
+
+    fn right_hash_tree(&self, n: Addr) -> HashTree<'static> {
+        match self.right(n) {
+            NULL => Empty,
+            r => Pruned(self.subtree_hash(r)),
+        }
+    }
+
+    fn data_tree(&self, n: Addr) -> HashTree<'static> {
+        owned_labeled(self.key(n), Leaf(Cow::Owned(self.value(n))))
+    }
+
+    fn witness_tree(&self, n: Addr) -> HashTree<'static> {
+        owned_labeled(self.key(n), Pruned(self.value_hash(n)))
+    }
+
+    fn bound_tree(
+        &self,
+        n: Addr,
+        bound: &KeyBound,
+        f: fn(&Self, Addr) -> HashTree<'static>,
+    ) -> HashTree<'static> {
+        match bound {
+            KeyBound::Exact(_) => f(self, n),
+            KeyBound::Neighbor(_) => self.witness_tree(n),
+        }
+    }
+
+    fn full_witness_tree(
+        &self,
+        n: Addr,
+        f: fn(&Self, Addr) -> HashTree<'static>,
+    ) -> HashTree<'static> {
+        if n == NULL {
+            return Empty;
+        }
+        three_way_fork(
+            self.full_witness_tree(self.left(n), f),
+            f(self, n),
+            self.full_witness_tree(self.right(n), f),
+        )
+    }
+
+    fn lookup_and_build_witness(&self, n: Addr, key: &[u8]) -> Option<HashTree<'static>> {
+        if n == NULL {
+            return None;
+        }
+        match key.cmp(&self.key(n)) {
+            Equal => Some(three_way_fork(
+                self.left_hash_tree(n),
+                self.data_tree(n),
+                self.right_hash_tree(n),
+            )),
+            Less => {
+                let subtree = self.lookup_and_build_witness(self.left(n), key)?;
+                Some(three_way_fork(
+                    subtree,
+                    Pruned(self.data_hash(n)),
+                    self.right_hash_tree(n),
+                ))
+            }
+            Greater => {
+                let subtree = self.lookup_and_build_witness(self.right(n), key)?;
+                Some(three_way_fork(
+                    self.left_hash_tree(n),
+                    Pruned(self.data_hash(n)),
+                    subtree,
+                ))
+            }
+        }
+    }
+
+    fn range_witness(
+        &self,
+        left: Option<KeyBound>,
+        right: Option<KeyBound>,
+        f: fn(&Self, Addr) -> HashTree<'static>,
+    ) -> HashTree<'static> {
+        match (left, right) {
+            (None, None) => self.full_witness_tree(self.root, f),
+            (Some(l), None) => self.witness_range_above(self.root, &l, f),
+            (None, Some(r)) => self.witness_range_below(self.root, &r, f),
+            (Some(l), Some(r)) => self.witness_range_between(self.root, &l, &r, f),
+        }
+    }
+
+    fn witness_range_above(
+        &self,
+        n: Addr,
+        lo: &KeyBound,
+        f: fn(&Self, Addr) -> HashTree<'static>,
+    ) -> HashTree<'static> {
+        if n == NULL {
+            return Empty;
+        }
+        match self.key(n)[..].cmp(lo.key()) {
+            Equal => three_way_fork(
+                self.left_hash_tree(n),
+                self.bound_tree(n, lo, f),
+                self.full_witness_tree(self.right(n), f),
+            ),
+            Less => three_way_fork(
+                self.left_hash_tree(n),
+                Pruned(self.data_hash(n)),
+                self.witness_range_above(self.right(n), lo, f),
+            ),
+            Greater => three_way_fork(
+                self.witness_range_above(self.left(n), lo, f),
+                f(self, n),
+                self.full_witness_tree(self.right(n), f),
+            ),
+        }
+    }
+
+    fn witness_range_below(
+        &self,
+        n: Addr,
+        hi: &KeyBound,
+        f: fn(&Self, Addr) -> HashTree<'static>,
+    ) -> HashTree<'static> {
+        if n == NULL {
+            return Empty;
+        }
+        match self.key(n)[..].cmp(hi.key()) {
+            Equal => three_way_fork(
+                self.full_witness_tree(self.left(n), f),
+                self.bound_tree(n, hi, f),
+                self.right_hash_tree(n),
+            ),
+            Greater => three_way_fork(
+                self.witness_range_below(self.left(n), hi, f),
+                Pruned(self.data_hash(n)),
+                self.right_hash_tree(n),
+            ),
+            Less => three_way_fork(
+                self.full_witness_tree(self.left(n), f),
+                f(self, n),
+                self.witness_range_below(self.right(n), hi, f),
+            ),
+        }
+    }
+
+    fn witness_range_between(
+        &self,
+        n: Addr,
+        lo: &KeyBound,
+        hi: &KeyBound,
+        f: fn(&Self, Addr) -> HashTree<'static>,
+    ) -> HashTree<'static> {
+        if n == NULL {
+            return Empty;
+        }
+        let k = self.key(n);
+        match (lo.key().cmp(&k), k[..].cmp(hi.key())) {
+            (Less, Less) => three_way_fork(
+                self.witness_range_between(self.left(n), lo, hi, f),
+                f(self, n),
+                self.witness_range_between(self.right(n), lo, hi, f),
+            ),
+            (Equal, Equal) => three_way_fork(
+                self.left_hash_tree(n),
+                match (lo, hi) {
+                    (KeyBound::Exact(_), _) | (_, KeyBound::Exact(_)) => f(self, n),
+                    _ => self.witness_tree(n),
+                },
+                self.right_hash_tree(n),
+            ),
+            (_, Equal) => three_way_fork(
+                self.witness_range_between(self.left(n), lo, hi, f),
+                self.bound_tree(n, hi, f),
+                self.right_hash_tree(n),
+            ),
+            (Equal, _) => three_way_fork(
+                self.left_hash_tree(n),
+                self.bound_tree(n, lo, f),
+                self.witness_range_between(self.right(n), lo, hi, f),
+            ),
+            (Less, Greater) => three_way_fork(
+                self.witness_range_between(self.left(n), lo, hi, f),
+                Pruned(self.data_hash(n)),
+                self.right_hash_tree(n),
+            ),
+            (Greater, Less) => three_way_fork(
+                self.left_hash_tree(n),
+                Pruned(self.data_hash(n)),
+                self.witness_range_between(self.right(n), lo, hi, f),
+            ),
+            _ => Pruned(self.subtree_hash(n)),
+        }
+    }
+
+    fn lower_bound(&self, key: &[u8]) -> Option<KeyBound> {
+        let mut n = self.root;
+        let mut bound = None;
+        while n != NULL {
+            let node_key = self.key(n);
+            match node_key[..].cmp(key) {
+                Less => {
+                    n = self.right(n);
+                    bound = Some(KeyBound::Neighbor(node_key));
+                }
+                Equal => return Some(KeyBound::Exact(node_key)),
+                Greater => n = self.left(n),
+            }
+        }
+        bound
+    }
+
+    fn upper_bound(&self, key: &[u8]) -> Option<KeyBound> {
+        let mut n = self.root;
+        let mut bound = None;
+        while n != NULL {
+            let node_key = self.key(n);
+            match node_key[..].cmp(key) {
+                Less => n = self.right(n),
+                Equal => return Some(KeyBound::Exact(node_key)),
+                Greater => {
+                    n = self.left(n);
+                    bound = Some(KeyBound::Neighbor(node_key));
+                }
+            }
+        }
+        bound
+    }
+
+    fn right_prefix_neighbor(&self, prefix: &[u8]) -> Option<KeyBound> {
+        let mut n = self.root;
+        let mut bound = None;
+        while n != NULL {
+            let node_key = self.key(n);
+            match node_key[..].cmp(prefix) {
+                Greater if node_key.starts_with(prefix) => n = self.right(n),
+                Greater => {
+                    n = self.left(n);
+                    bound = Some(KeyBound::Neighbor(node_key));
+                }
+                Less | Equal => n = self.right(n),
+            }
+        }
+        bound
+    }
+}
+
+/// Iterator over a [`StableRbTree`], reading each entry from stable memory.
+#[cfg_attr(docsrs, doc(cfg(feature = "stable-memory")))]
+pub struct StableIter<'a, M: StableMemory> {
+    tree: &'a StableRbTree<M>,
+    stack: Vec<Addr>,
+}
+
+impl<M: StableMemory> StableIter<'_, M> {
+    fn push_left_spine(&mut self, mut n: Addr) {
+        while n != NULL {
+            self.stack.push(n);
+            n = self.tree.left(n);
+        }
+    }
+}
+
+impl<M: StableMemory> fmt::Debug for StableIter<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StableIter").finish_non_exhaustive()
+    }
+}
+
+impl<M: StableMemory> Iterator for StableIter<'_, M> {
+    type Item = (Vec<u8>, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        self.push_left_spine(self.tree.right(n));
+        Some((self.tree.key(n), self.tree.value(n)))
+    }
+}
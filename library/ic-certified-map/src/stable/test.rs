@@ -0,0 +1,179 @@
+use super::*;
+use crate::RbTree;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Stable memory shared between the maps of a test, like the stable memory of a canister across
+// upgrades.
+#[derive(Clone, Default)]
+struct TestMemory {
+    bytes: Rc<RefCell<Vec<u8>>>,
+    max_pages: Option<u64>,
+}
+
+impl StableMemory for TestMemory {
+    fn stable_size(&self) -> u64 {
+        self.bytes.borrow().len() as u64 / WASM_PAGE_SIZE_IN_BYTES
+    }
+
+    fn stable_grow(&self, new_pages: u64) -> Result<u64, StableMemoryError> {
+        let old_pages = self.stable_size();
+        if self
+            .max_pages
+            .is_some_and(|max| old_pages + new_pages > max)
+        {
+            return Err(StableMemoryError::OutOfMemory);
+        }
+        let len = (old_pages + new_pages) * WASM_PAGE_SIZE_IN_BYTES;
+        self.bytes.borrow_mut().resize(len as usize, 0);
+        Ok(old_pages)
+    }
+
+    fn stable_write(&self, offset: u64, buf: &[u8]) {
+        let offset = offset as usize;
+        self.bytes.borrow_mut()[offset..offset + buf.len()].copy_from_slice(buf);
+    }
+
+    fn stable_read(&self, offset: u64, buf: &mut [u8]) {
+        let offset = offset as usize;
+        buf.copy_from_slice(&self.bytes.borrow()[offset..offset + buf.len()]);
+    }
+}
+
+fn cbor(t: &HashTree<'_>) -> Vec<u8> {
+    serde_cbor::to_vec(t).unwrap()
+}
+
+// Applies the same operations to a `StableRbTree` and an `RbTree`, which must stay equal.
+fn assert_same(stable: &StableRbTree<TestMemory>, heap: &RbTree<Vec<u8>, Vec<u8>>) {
+    assert_eq!(stable.root_hash(), heap.root_hash());
+    assert_eq!(cbor(&stable.as_hash_tree()), cbor(&heap.as_hash_tree()));
+    assert_eq!(cbor(&stable.keys()), cbor(&heap.keys()));
+    assert_eq!(
+        stable.iter().collect::<Vec<_>>(),
+        heap.iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>()
+    );
+    for key in [&b""[..], b"0", b"a", b"k10", b"k25", b"k5", b"k99", b"z"] {
+        assert_eq!(stable.get(key).as_ref(), heap.get(key));
+        assert_eq!(cbor(&stable.witness(key)), cbor(&heap.witness(key)));
+        assert_eq!(
+            cbor(&stable.keys_with_prefix(key)),
+            cbor(&heap.keys_with_prefix(key))
+        );
+        for last in [&b"k3"[..], b"k50", b"z"] {
+            if key <= last {
+                assert_eq!(
+                    cbor(&stable.key_range(key, last)),
+                    cbor(&heap.key_range(key, last))
+                );
+                assert_eq!(
+                    cbor(&stable.value_range(key, last)),
+                    cbor(&heap.value_range(key, last))
+                );
+            }
+        }
+    }
+    assert_eq!(
+        cbor(&stable.multi_witness([&b"k1"[..], b"k40", b"x"])),
+        cbor(&heap.multi_witness([&b"k1"[..], b"k40", b"x"]))
+    );
+}
+
+#[test]
+fn test_same_as_rbtree() {
+    let mut stable = StableRbTree::init(TestMemory::default(), 8, 16).unwrap();
+    let mut heap = RbTree::new();
+    assert_same(&stable, &heap);
+
+    for i in 0..60u32 {
+        let key = format!("k{}", (i * 37) % 60).into_bytes();
+        let value = format!("value {i}").into_bytes();
+        stable.insert(&key, &value).unwrap();
+        heap.insert(key, value);
+        assert_same(&stable, &heap);
+    }
+    stable.insert(b"k10", b"updated").unwrap();
+    heap.insert(b"k10".to_vec(), b"updated".to_vec());
+    assert_same(&stable, &heap);
+
+    for i in 0..70u32 {
+        let key = format!("k{}", (i * 13) % 70).into_bytes();
+        stable.delete(&key);
+        heap.delete(&key);
+        assert_same(&stable, &heap);
+    }
+    assert!(stable.is_empty());
+    assert_eq!(stable.len(), 0);
+}
+
+#[test]
+fn test_load_after_upgrade() {
+    let memory = TestMemory::default();
+    assert!(StableRbTree::load(memory.clone()).is_none());
+
+    let mut map = StableRbTree::init(memory.clone(), 8, 8).unwrap();
+    for i in 0..20u8 {
+        map.insert(&[i], &[i, i]).unwrap();
+    }
+    map.delete(&[3]);
+    let root_hash = map.root_hash();
+    drop(map);
+
+    let mut map = StableRbTree::load(memory).unwrap();
+    assert_eq!(map.len(), 19);
+    assert_eq!(map.root_hash(), root_hash);
+    assert_eq!(map.get(&[7]), Some(vec![7, 7]));
+    assert_eq!(map.get(&[3]), None);
+    map.insert(&[3], &[3, 3]).unwrap();
+    assert_eq!(map.len(), 20);
+}
+
+#[test]
+fn test_reuse_deleted_slots() {
+    let memory = TestMemory::default();
+    let mut map = StableRbTree::init(memory.clone(), 4, 4).unwrap();
+    for i in 0..100u32 {
+        map.insert(&i.to_be_bytes(), b"v").unwrap();
+    }
+    let end = map.next;
+    for i in 0..50u32 {
+        map.delete(&i.to_be_bytes());
+    }
+    for i in 100..150u32 {
+        map.insert(&i.to_be_bytes(), b"v").unwrap();
+    }
+    assert_eq!(map.next, end);
+    assert_eq!(map.len(), 100);
+}
+
+#[test]
+fn test_out_of_memory() {
+    let memory = TestMemory {
+        max_pages: Some(1),
+        ..Default::default()
+    };
+    let mut map = StableRbTree::init(memory, 1, 1024).unwrap();
+    let mut heap = RbTree::new();
+    let value = [1; 1024];
+    let error = (0..=u8::MAX)
+        .find_map(|n| {
+            let result = map.insert(&[n], &value);
+            if result.is_ok() {
+                heap.insert(vec![n], value.to_vec());
+            }
+            result.err()
+        })
+        .expect("the memory should be full");
+    assert!(matches!(error, StableMemoryError::OutOfMemory));
+    assert_eq!(map.len(), 58);
+    assert_same(&map, &heap);
+}
+
+#[test]
+#[should_panic(expected = "the key is 5 bytes long, the map accepts at most 4")]
+fn test_key_too_long() {
+    let mut map = StableRbTree::init(TestMemory::default(), 4, 4).unwrap();
+    map.insert(b"hello", b"").unwrap();
+}
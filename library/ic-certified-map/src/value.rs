@@ -0,0 +1,158 @@
+//! Certified values: strings, integers and Candid-encoded values.
+
+#[cfg(test)]
+mod test;
+
+use crate::hashtree::{leaf_hash, Hash, HashTree, HashTree::Leaf};
+use crate::rbtree::AsHashTree;
+use candid::CandidType;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+
+impl AsHashTree for String {
+    fn root_hash(&self) -> Hash {
+        leaf_hash(self.as_bytes())
+    }
+
+    fn as_hash_tree(&self) -> HashTree<'_> {
+        Leaf(Cow::from(self.as_bytes()))
+    }
+}
+
+impl AsHashTree for &str {
+    fn root_hash(&self) -> Hash {
+        leaf_hash(self.as_bytes())
+    }
+
+    fn as_hash_tree(&self) -> HashTree<'_> {
+        Leaf(Cow::from(self.as_bytes()))
+    }
+}
+
+// Integers are LEB128-encoded, like the `/time` of the state tree.
+fn leb128(mut n: u128) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn sleb128(mut n: i128) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if (n == 0 && byte & 0x40 == 0) || (n == -1 && byte & 0x40 != 0) {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+macro_rules! integer_value {
+    ($encode:ident, $wide:ty; $($t:ty),*) => {$(
+        /// A leaf of the LEB128 encoding of the integer.
+        impl AsHashTree for $t {
+            fn root_hash(&self) -> Hash {
+                leaf_hash(&$encode(<$wide>::from(*self)))
+            }
+
+            fn as_hash_tree(&self) -> HashTree<'_> {
+                Leaf(Cow::Owned($encode(<$wide>::from(*self))))
+            }
+        }
+    )*};
+}
+
+integer_value!(leb128, u128; u8, u16, u32, u64, u128);
+integer_value!(sleb128, i128; i8, i16, i32, i64, i128);
+
+/// A value certified as a leaf of its Candid encoding, e.g. a record.
+///
+/// The encoding and its hash are computed once, when the value is set.
+///
+/// ```
+/// # use ic_certified_map::*;
+/// # use candid::CandidType;
+/// #[derive(CandidType, Clone)]
+/// struct Profile {
+///     name: String,
+///     age: u8,
+/// }
+///
+/// let mut profiles: RbTree<Key<u64>, Candid<Profile>> = RbTree::new();
+/// let profile = Profile { name: "alice".to_string(), age: 30 };
+/// profiles.insert(Key::new(1), Candid::new(profile).unwrap());
+/// assert_eq!(profiles.get(&1u64.to_key_bytes()).unwrap().value().age, 30);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candid<T> {
+    value: T,
+    bytes: Vec<u8>,
+    hash: Hash,
+}
+
+impl<T: CandidType> Candid<T> {
+    /// Encodes a value.
+    pub fn new(value: T) -> Result<Self, candid::Error> {
+        let bytes = candid::encode_one(&value)?;
+        let hash = leaf_hash(&bytes);
+        Ok(Self { value, bytes, hash })
+    }
+}
+
+impl<T> Candid<T> {
+    /// The value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// The Candid encoding of the value, certified as a leaf.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<T> AsHashTree for Candid<T> {
+    fn root_hash(&self) -> Hash {
+        self.hash
+    }
+
+    fn as_hash_tree(&self) -> HashTree<'_> {
+        Leaf(Cow::from(&self.bytes[..]))
+    }
+}
+
+impl<T: CandidType> CandidType for Candid<T> {
+    fn _ty() -> candid::types::internal::Type {
+        T::_ty()
+    }
+    fn idl_serialize<S: candid::types::Serializer>(&self, serializer: S) -> Result<(), S::Error> {
+        self.value.idl_serialize(serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for Candid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + CandidType> Deserialize<'de> for Candid<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = T::deserialize(deserializer)?;
+        Self::new(value).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,57 @@
+use super::*;
+
+#[test]
+fn test_leb128() {
+    for (n, bytes) in [
+        (0u128, &[0x00][..]),
+        (127, &[0x7f]),
+        (128, &[0x80, 0x01]),
+        (624_485, &[0xe5, 0x8e, 0x26]),
+        (1_000_000_000, &[0x80, 0x94, 0xeb, 0xdc, 0x03]),
+    ] {
+        assert_eq!(leb128(n), bytes);
+    }
+    for (n, bytes) in [
+        (0i128, &[0x00][..]),
+        (-1, &[0x7f]),
+        (63, &[0x3f]),
+        (64, &[0xc0, 0x00]),
+        (-64, &[0x40]),
+        (-65, &[0xbf, 0x7f]),
+        (-123_456, &[0xc0, 0xbb, 0x78]),
+    ] {
+        assert_eq!(sleb128(n), bytes);
+    }
+}
+
+fn assert_consistent(value: &impl AsHashTree) {
+    assert_eq!(value.root_hash(), value.as_hash_tree().reconstruct());
+}
+
+#[test]
+fn test_values() {
+    assert_eq!("abc".to_string().root_hash(), leaf_hash(b"abc"));
+    assert_eq!("abc".root_hash(), leaf_hash(b"abc"));
+    assert_eq!(
+        1_000_000_000u64.root_hash(),
+        leaf_hash(&[0x80, 0x94, 0xeb, 0xdc, 0x03])
+    );
+    assert_eq!((-1i8).root_hash(), leaf_hash(&[0x7f]));
+    assert_consistent(&"abc".to_string());
+    assert_consistent(&u128::MAX);
+    assert_consistent(&i64::MIN);
+}
+
+#[test]
+fn test_candid() {
+    let value = Candid::new((7u64, "seven".to_string())).unwrap();
+    let bytes = candid::encode_one((7u64, "seven".to_string())).unwrap();
+    assert_eq!(value.bytes(), bytes);
+    assert_eq!(value.root_hash(), leaf_hash(&bytes));
+    assert_consistent(&value);
+    assert_eq!(value.value().1, "seven");
+
+    let encoded = serde_cbor::to_vec(&value).unwrap();
+    let decoded: Candid<(u64, String)> = serde_cbor::from_slice(&encoded).unwrap();
+    assert_eq!(decoded, value);
+}
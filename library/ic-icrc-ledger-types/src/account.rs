@@ -0,0 +1,301 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use candid::types::principal::PrincipalError;
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+/// Subaccount is an arbitrary 32-byte byte array.
+/// Ledgers use subaccounts to enable one principal to control multiple accounts.
+pub type Subaccount = [u8; 32];
+
+/// The subaccount that is used by default.
+pub const DEFAULT_SUBACCOUNT: &Subaccount = &[0; 32];
+
+/// An account on an ICRC-1 ledger: a principal and an optional subaccount.
+///
+/// The accounts with no subaccount and with the [default subaccount](DEFAULT_SUBACCOUNT) are
+/// the same account, and compare equal.
+///
+/// The textual encoding of an account is the one of the [ICRC-1 standard]:
+///
+/// ```
+/// use candid::Principal;
+/// use ic_icrc_ledger_types::Account;
+///
+/// let owner = Principal::from_text("k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae").unwrap();
+/// let mut subaccount = [0; 32];
+/// subaccount[31] = 1;
+/// let account = Account { owner, subaccount: Some(subaccount) };
+///
+/// let text = "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae-6cc627i.1";
+/// assert_eq!(account.to_string(), text);
+/// assert_eq!(text.parse::<Account>(), Ok(account));
+/// ```
+///
+/// [ICRC-1 standard]: https://github.com/dfinity/ICRC-1/blob/main/standards/ICRC-1/TextualEncoding.md
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Account {
+    /// The principal owning the account.
+    pub owner: Principal,
+    /// The subaccount of the principal.
+    /// If `None`, the account is the one of the default (all zeros) subaccount.
+    pub subaccount: Option<Subaccount>,
+}
+
+impl Account {
+    /// Returns the subaccount, which is the default (all zeros) subaccount if not set.
+    pub fn effective_subaccount(&self) -> &Subaccount {
+        self.subaccount.as_ref().unwrap_or(DEFAULT_SUBACCOUNT)
+    }
+}
+
+impl From<Principal> for Account {
+    fn from(owner: Principal) -> Self {
+        Self {
+            owner,
+            subaccount: None,
+        }
+    }
+}
+
+impl PartialEq for Account {
+    fn eq(&self, other: &Self) -> bool {
+        self.owner == other.owner && self.effective_subaccount() == other.effective_subaccount()
+    }
+}
+
+impl Eq for Account {}
+
+impl PartialOrd for Account {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Account {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.owner.cmp(&other.owner).then_with(|| {
+            self.effective_subaccount()
+                .cmp(other.effective_subaccount())
+        })
+    }
+}
+
+impl Hash for Account {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.owner.hash(state);
+        self.effective_subaccount().hash(state);
+    }
+}
+
+// The checksum of the textual encoding: the CRC-32 of the owner and the subaccount, in base 32.
+fn checksum(owner: &Principal, subaccount: &Subaccount) -> String {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(owner.as_slice());
+    hasher.update(subaccount);
+    base32(&hasher.finalize().to_be_bytes())
+}
+
+// Lowercase base 32 of RFC 4648, without padding, as in the textual encoding of principals.
+fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::new();
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = buffer << 8 | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[usize::from(buffer >> bits & 0x1f)] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[usize::from(buffer << (5 - bits) & 0x1f)] as char);
+    }
+    out
+}
+
+impl Display for Account {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.subaccount {
+            Some(subaccount) if subaccount != *DEFAULT_SUBACCOUNT => write!(
+                f,
+                "{}-{}.{}",
+                self.owner,
+                checksum(&self.owner, &subaccount),
+                hex::encode(subaccount).trim_start_matches('0')
+            ),
+            _ => write!(f, "{}", self.owner),
+        }
+    }
+}
+
+impl FromStr for Account {
+    type Err = AccountParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((owner_and_checksum, hex_subaccount)) = s.split_once('.') else {
+            let owner = Principal::from_text(s).map_err(AccountParseError::InvalidPrincipal)?;
+            return Ok(Self::from(owner));
+        };
+        let (owner, found_checksum) = owner_and_checksum
+            .rsplit_once('-')
+            .ok_or(AccountParseError::NotCanonical)?;
+        let owner = Principal::from_text(owner).map_err(AccountParseError::InvalidPrincipal)?;
+        // The default subaccount and the leading zeros are omitted.
+        if hex_subaccount.is_empty() || hex_subaccount.starts_with('0') {
+            return Err(AccountParseError::NotCanonical);
+        }
+        if hex_subaccount.len() > 64 {
+            return Err(AccountParseError::InvalidSubaccount(
+                hex_subaccount.to_string(),
+            ));
+        }
+        let mut subaccount = [0; 32];
+        hex::decode_to_slice(format!("{:0>64}", hex_subaccount), &mut subaccount)
+            .map_err(|_| AccountParseError::InvalidSubaccount(hex_subaccount.to_string()))?;
+        let expected_checksum = checksum(&owner, &subaccount);
+        if found_checksum != expected_checksum {
+            return Err(AccountParseError::InvalidChecksum {
+                expected_checksum,
+                found_checksum: found_checksum.to_string(),
+            });
+        }
+        Ok(Self {
+            owner,
+            subaccount: Some(subaccount),
+        })
+    }
+}
+
+/// An error for reporting an invalid textual encoding of an [`Account`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountParseError {
+    /// The owner is not a valid principal.
+    InvalidPrincipal(PrincipalError),
+    /// The checksum does not match the owner and the subaccount.
+    InvalidChecksum {
+        /// The checksum of the owner and the subaccount.
+        expected_checksum: String,
+        /// The checksum found in the text.
+        found_checksum: String,
+    },
+    /// The subaccount is not a hexadecimal number of at most 64 digits.
+    InvalidSubaccount(String),
+    /// The text is not the canonical encoding of the account, e.g. the checksum is missing, or
+    /// the subaccount has leading zeros.
+    NotCanonical,
+}
+
+impl Display for AccountParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPrincipal(err) => write!(f, "invalid owner: {}", err),
+            Self::InvalidChecksum {
+                expected_checksum,
+                found_checksum,
+            } => write!(
+                f,
+                "invalid checksum, expected {} but found {}",
+                expected_checksum, found_checksum
+            ),
+            Self::InvalidSubaccount(subaccount) => {
+                write!(f, "invalid subaccount {}", subaccount)
+            }
+            Self::NotCanonical => write!(f, "the account is not in its canonical form"),
+        }
+    }
+}
+
+impl std::error::Error for AccountParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWNER: &str = "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae";
+
+    fn account(subaccount: Option<Subaccount>) -> Account {
+        Account {
+            owner: Principal::from_text(OWNER).unwrap(),
+            subaccount,
+        }
+    }
+
+    #[test]
+    fn test_base32() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "my"),
+            (b"fo", "mzxq"),
+            (b"foo", "mzxw6"),
+            (b"foob", "mzxw6yq"),
+            (b"fooba", "mzxw6ytb"),
+            (b"foobar", "mzxw6ytboi"),
+        ] {
+            assert_eq!(base32(bytes), encoded);
+        }
+    }
+
+    #[test]
+    fn test_account_to_string() {
+        assert_eq!(account(None).to_string(), OWNER);
+        assert_eq!(account(Some([0; 32])).to_string(), OWNER);
+        let mut subaccount = [0; 32];
+        subaccount[31] = 1;
+        assert_eq!(
+            account(Some(subaccount)).to_string(),
+            format!("{}-6cc627i.1", OWNER)
+        );
+        let subaccount: Subaccount = std::array::from_fn(|i| i as u8 + 1);
+        assert_eq!(
+            account(Some(subaccount)).to_string(),
+            format!(
+                "{}-dfxgiyy.102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20",
+                OWNER
+            )
+        );
+    }
+
+    #[test]
+    fn test_account_from_str() {
+        for subaccount in [None, Some([1; 32]), Some([0xff; 32]), Some([0x10; 32])] {
+            let account = account(subaccount);
+            assert_eq!(account.to_string().parse(), Ok(account));
+        }
+        assert_eq!(account(None), account(Some([0; 32])));
+
+        assert_eq!(
+            format!("{}-6cc627i.01", OWNER).parse::<Account>(),
+            Err(AccountParseError::NotCanonical)
+        );
+        assert_eq!(
+            format!("{}-6cc627i.", OWNER).parse::<Account>(),
+            Err(AccountParseError::NotCanonical)
+        );
+        // Without a checksum, the last group of the principal is taken for the checksum.
+        assert!(matches!(
+            format!("{}.1", OWNER).parse::<Account>(),
+            Err(AccountParseError::InvalidPrincipal(_))
+        ));
+        assert_eq!(
+            format!("{}-6cc627j.1", OWNER).parse::<Account>(),
+            Err(AccountParseError::InvalidChecksum {
+                expected_checksum: "6cc627i".to_string(),
+                found_checksum: "6cc627j".to_string(),
+            })
+        );
+        assert_eq!(
+            format!("{}-6cc627i.1g", OWNER).parse::<Account>(),
+            Err(AccountParseError::InvalidSubaccount("1g".to_string()))
+        );
+        assert!(matches!(
+            "k2t6j-2nvnp".parse::<Account>(),
+            Err(AccountParseError::InvalidPrincipal(_))
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::{self, Display, Formatter};
+
+use candid::{CandidType, Int, Nat, Principal};
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+
+use ic_cdk::api::call::CallResult;
+
+use crate::{Account, Subaccount};
+
+/// The index of a block in the ledger.
+pub type BlockIndex = Nat;
+
+/// An arbitrary blob associated with a transaction, of at most 32 bytes on most ledgers.
+/// The caller can set it in a transfer as a correlation identifier.
+#[derive(
+    CandidType, Serialize, Deserialize, Clone, Hash, Debug, Default, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(transparent)]
+pub struct Memo(pub ByteBuf);
+
+impl From<u64> for Memo {
+    /// The big-endian encoding of the number.
+    fn from(number: u64) -> Self {
+        Self(ByteBuf::from(number.to_be_bytes().to_vec()))
+    }
+}
+
+impl From<Vec<u8>> for Memo {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(ByteBuf::from(bytes))
+    }
+}
+
+/// A value of the metadata of a ledger.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MetadataValue {
+    /// A natural number.
+    Nat(Nat),
+    /// An integer.
+    Int(Int),
+    /// A text.
+    Text(String),
+    /// A blob.
+    Blob(ByteBuf),
+}
+
+/// A standard supported by a ledger.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StandardRecord {
+    /// The name of the standard, e.g. `ICRC-1`.
+    pub name: String,
+    /// The URL of the specification of the standard.
+    pub url: String,
+}
+
+/// Arguments for the `icrc1_transfer` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferArg {
+    /// The subaccount from which the caller wants to transfer tokens.
+    /// If `None`, the ledger uses the default (all zeros) subaccount.
+    pub from_subaccount: Option<Subaccount>,
+    /// The destination account.
+    pub to: Account,
+    /// The amount that the caller pays for the transaction.
+    /// If `None`, the ledger charges its current fee.
+    pub fee: Option<Nat>,
+    /// The point in time when the caller created this request, in nanoseconds since the UNIX
+    /// epoch. If set, the ledger deduplicates the transactions created within its window.
+    pub created_at_time: Option<u64>,
+    /// Transaction memo.
+    pub memo: Option<Memo>,
+    /// The amount that the caller wants to transfer to the destination account.
+    pub amount: Nat,
+}
+
+/// Result of the `icrc1_transfer` call.
+pub type TransferResult = Result<BlockIndex, TransferError>;
+
+/// Error of the `icrc1_transfer` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferError {
+    /// The fee that the caller specified was not the one that the ledger expects.
+    /// The caller can change the fee to `expected_fee` and retry the request.
+    BadFee {
+        /// The fee that the ledger expects.
+        expected_fee: Nat,
+    },
+    /// The caller tried to burn fewer tokens than the ledger allows.
+    BadBurn {
+        /// The minimum amount of tokens that can be burned.
+        min_burn_amount: Nat,
+    },
+    /// The caller did not have enough tokens in the specified subaccount.
+    InsufficientFunds {
+        /// The caller's balance.
+        balance: Nat,
+    },
+    /// The request is older than the deduplication window of the ledger.
+    TooOld,
+    /// The caller specified a `created_at_time` that is too far in the future.
+    CreatedInFuture {
+        /// The time of the ledger.
+        ledger_time: u64,
+    },
+    /// The ledger cannot process the request at the moment.
+    /// The caller can retry the request later.
+    TemporarilyUnavailable,
+    /// The ledger has already executed the request.
+    Duplicate {
+        /// The index of the block containing the original transaction.
+        duplicate_of: BlockIndex,
+    },
+    /// Any other error.
+    GenericError {
+        /// A machine-readable error code.
+        error_code: Nat,
+        /// A human-readable error message.
+        message: String,
+    },
+}
+
+impl Display for TransferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadFee { expected_fee } => {
+                write!(f, "transaction fee should be {}", expected_fee)
+            }
+            Self::BadBurn { min_burn_amount } => {
+                write!(f, "the minimum amount to burn is {}", min_burn_amount)
+            }
+            Self::InsufficientFunds { balance } => write!(
+                f,
+                "the debit account doesn't have enough funds to complete the transaction, current balance: {}",
+                balance
+            ),
+            Self::TooOld => write!(f, "transaction is too old"),
+            Self::CreatedInFuture { ledger_time } => write!(
+                f,
+                "transaction's created_at_time is in future, ledger time: {}",
+                ledger_time
+            ),
+            Self::TemporarilyUnavailable => write!(f, "the ledger is temporarily unavailable"),
+            Self::Duplicate { duplicate_of } => write!(
+                f,
+                "transaction is a duplicate of another transaction in block {}",
+                duplicate_of
+            ),
+            Self::GenericError {
+                error_code,
+                message,
+            } => write!(f, "error {}: {}", error_code, message),
+        }
+    }
+}
+
+/// Calls the "icrc1_name" method on the specified canister.
+pub async fn icrc1_name(ledger_canister_id: Principal) -> CallResult<String> {
+    let (name,) = ic_cdk::call(ledger_canister_id, "icrc1_name", ()).await?;
+    Ok(name)
+}
+
+/// Calls the "icrc1_symbol" method on the specified canister.
+pub async fn icrc1_symbol(ledger_canister_id: Principal) -> CallResult<String> {
+    let (symbol,) = ic_cdk::call(ledger_canister_id, "icrc1_symbol", ()).await?;
+    Ok(symbol)
+}
+
+/// Calls the "icrc1_decimals" method on the specified canister.
+pub async fn icrc1_decimals(ledger_canister_id: Principal) -> CallResult<u8> {
+    let (decimals,) = ic_cdk::call(ledger_canister_id, "icrc1_decimals", ()).await?;
+    Ok(decimals)
+}
+
+/// Calls the "icrc1_fee" method on the specified canister.
+pub async fn icrc1_fee(ledger_canister_id: Principal) -> CallResult<Nat> {
+    let (fee,) = ic_cdk::call(ledger_canister_id, "icrc1_fee", ()).await?;
+    Ok(fee)
+}
+
+/// Calls the "icrc1_metadata" method on the specified canister.
+pub async fn icrc1_metadata(
+    ledger_canister_id: Principal,
+) -> CallResult<Vec<(String, MetadataValue)>> {
+    let (metadata,) = ic_cdk::call(ledger_canister_id, "icrc1_metadata", ()).await?;
+    Ok(metadata)
+}
+
+/// Calls the "icrc1_total_supply" method on the specified canister.
+pub async fn icrc1_total_supply(ledger_canister_id: Principal) -> CallResult<Nat> {
+    let (total_supply,) = ic_cdk::call(ledger_canister_id, "icrc1_total_supply", ()).await?;
+    Ok(total_supply)
+}
+
+/// Calls the "icrc1_minting_account" method on the specified canister.
+pub async fn icrc1_minting_account(ledger_canister_id: Principal) -> CallResult<Option<Account>> {
+    let (account,) = ic_cdk::call(ledger_canister_id, "icrc1_minting_account", ()).await?;
+    Ok(account)
+}
+
+/// Calls the "icrc1_supported_standards" method on the specified canister.
+pub async fn icrc1_supported_standards(
+    ledger_canister_id: Principal,
+) -> CallResult<Vec<StandardRecord>> {
+    let (standards,) = ic_cdk::call(ledger_canister_id, "icrc1_supported_standards", ()).await?;
+    Ok(standards)
+}
+
+/// Calls the "icrc1_balance_of" method on the specified canister.
+///
+/// # Example
+/// ```no_run
+/// use candid::{Nat, Principal};
+/// use ic_cdk::api::caller;
+/// use ic_icrc_ledger_types::{icrc1_balance_of, Account};
+///
+/// async fn check_callers_balance(ledger_canister_id: Principal) -> Nat {
+///   icrc1_balance_of(ledger_canister_id, Account::from(caller()))
+///     .await
+///     .expect("call to ledger failed")
+/// }
+/// ```
+pub async fn icrc1_balance_of(ledger_canister_id: Principal, account: Account) -> CallResult<Nat> {
+    let (balance,) = ic_cdk::call(ledger_canister_id, "icrc1_balance_of", (account,)).await?;
+    Ok(balance)
+}
+
+/// Calls the "icrc1_transfer" method on the specified canister.
+///
+/// # Example
+/// ```no_run
+/// use candid::{Nat, Principal};
+/// use ic_cdk::api::caller;
+/// use ic_icrc_ledger_types::{icrc1_transfer, Account, BlockIndex, TransferArg};
+///
+/// async fn transfer_to_caller(ledger_canister_id: Principal) -> BlockIndex {
+///   icrc1_transfer(
+///     ledger_canister_id,
+///     TransferArg {
+///       from_subaccount: None,
+///       to: Account::from(caller()),
+///       fee: None,
+///       created_at_time: None,
+///       memo: None,
+///       amount: Nat::from(1_000_000u64),
+///     },
+///   )
+///   .await
+///   .expect("call to ledger failed")
+///   .expect("transfer failed")
+/// }
+/// ```
+pub async fn icrc1_transfer(
+    ledger_canister_id: Principal,
+    arg: TransferArg,
+) -> CallResult<TransferResult> {
+    let (result,) = ic_cdk::call(ledger_canister_id, "icrc1_transfer", (arg,)).await?;
+    Ok(result)
+}
@@ -0,0 +1,327 @@
+use std::fmt::{self, Display, Formatter};
+
+use candid::{CandidType, Nat, Principal};
+use serde::{Deserialize, Serialize};
+
+use ic_cdk::api::call::CallResult;
+
+use crate::{Account, BlockIndex, Memo, Subaccount};
+
+/// Arguments for the `icrc2_approve` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApproveArgs {
+    /// The subaccount of the caller owning the tokens.
+    /// If `None`, the ledger uses the default (all zeros) subaccount.
+    pub from_subaccount: Option<Subaccount>,
+    /// The account that is allowed to transfer the tokens.
+    pub spender: Account,
+    /// The allowance, which replaces the current one.
+    pub amount: Nat,
+    /// If set, the approval fails unless the current allowance is equal to this amount.
+    pub expected_allowance: Option<Nat>,
+    /// The time at which the approval expires, in nanoseconds since the UNIX epoch.
+    pub expires_at: Option<u64>,
+    /// The amount that the caller pays for the approval.
+    /// If `None`, the ledger charges its current fee.
+    pub fee: Option<Nat>,
+    /// Transaction memo.
+    pub memo: Option<Memo>,
+    /// The point in time when the caller created this request, in nanoseconds since the UNIX
+    /// epoch.
+    pub created_at_time: Option<u64>,
+}
+
+/// Result of the `icrc2_approve` call.
+pub type ApproveResult = Result<BlockIndex, ApproveError>;
+
+/// Error of the `icrc2_approve` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ApproveError {
+    /// The fee that the caller specified was not the one that the ledger expects.
+    BadFee {
+        /// The fee that the ledger expects.
+        expected_fee: Nat,
+    },
+    /// The caller did not have enough tokens to pay the fee.
+    InsufficientFunds {
+        /// The caller's balance.
+        balance: Nat,
+    },
+    /// The current allowance is not the `expected_allowance` of the request.
+    AllowanceChanged {
+        /// The current allowance.
+        current_allowance: Nat,
+    },
+    /// The `expires_at` of the request is in the past.
+    Expired {
+        /// The time of the ledger.
+        ledger_time: u64,
+    },
+    /// The request is older than the deduplication window of the ledger.
+    TooOld,
+    /// The caller specified a `created_at_time` that is too far in the future.
+    CreatedInFuture {
+        /// The time of the ledger.
+        ledger_time: u64,
+    },
+    /// The ledger has already executed the request.
+    Duplicate {
+        /// The index of the block containing the original transaction.
+        duplicate_of: BlockIndex,
+    },
+    /// The ledger cannot process the request at the moment.
+    TemporarilyUnavailable,
+    /// Any other error.
+    GenericError {
+        /// A machine-readable error code.
+        error_code: Nat,
+        /// A human-readable error message.
+        message: String,
+    },
+}
+
+impl Display for ApproveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadFee { expected_fee } => {
+                write!(f, "approval fee should be {}", expected_fee)
+            }
+            Self::InsufficientFunds { balance } => write!(
+                f,
+                "the account doesn't have enough funds to pay the fee, current balance: {}",
+                balance
+            ),
+            Self::AllowanceChanged { current_allowance } => {
+                write!(
+                    f,
+                    "the allowance changed, current allowance: {}",
+                    current_allowance
+                )
+            }
+            Self::Expired { ledger_time } => {
+                write!(f, "the approval expired, ledger time: {}", ledger_time)
+            }
+            Self::TooOld => write!(f, "transaction is too old"),
+            Self::CreatedInFuture { ledger_time } => write!(
+                f,
+                "transaction's created_at_time is in future, ledger time: {}",
+                ledger_time
+            ),
+            Self::Duplicate { duplicate_of } => write!(
+                f,
+                "transaction is a duplicate of another transaction in block {}",
+                duplicate_of
+            ),
+            Self::TemporarilyUnavailable => write!(f, "the ledger is temporarily unavailable"),
+            Self::GenericError {
+                error_code,
+                message,
+            } => write!(f, "error {}: {}", error_code, message),
+        }
+    }
+}
+
+/// Arguments for the `icrc2_transfer_from` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferFromArgs {
+    /// The subaccount of the caller, which is the spender of the approval.
+    pub spender_subaccount: Option<Subaccount>,
+    /// The account from which the tokens are transferred.
+    pub from: Account,
+    /// The destination account.
+    pub to: Account,
+    /// The amount that is transferred.
+    pub amount: Nat,
+    /// The amount that the `from` account pays for the transaction.
+    /// If `None`, the ledger charges its current fee.
+    pub fee: Option<Nat>,
+    /// Transaction memo.
+    pub memo: Option<Memo>,
+    /// The point in time when the caller created this request, in nanoseconds since the UNIX
+    /// epoch.
+    pub created_at_time: Option<u64>,
+}
+
+/// Result of the `icrc2_transfer_from` call.
+pub type TransferFromResult = Result<BlockIndex, TransferFromError>;
+
+/// Error of the `icrc2_transfer_from` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferFromError {
+    /// The fee that the caller specified was not the one that the ledger expects.
+    BadFee {
+        /// The fee that the ledger expects.
+        expected_fee: Nat,
+    },
+    /// The caller tried to burn fewer tokens than the ledger allows.
+    BadBurn {
+        /// The minimum amount of tokens that can be burned.
+        min_burn_amount: Nat,
+    },
+    /// The `from` account did not have enough tokens.
+    InsufficientFunds {
+        /// The balance of the `from` account.
+        balance: Nat,
+    },
+    /// The allowance of the caller is smaller than the amount and the fee.
+    InsufficientAllowance {
+        /// The allowance of the caller.
+        allowance: Nat,
+    },
+    /// The request is older than the deduplication window of the ledger.
+    TooOld,
+    /// The caller specified a `created_at_time` that is too far in the future.
+    CreatedInFuture {
+        /// The time of the ledger.
+        ledger_time: u64,
+    },
+    /// The ledger has already executed the request.
+    Duplicate {
+        /// The index of the block containing the original transaction.
+        duplicate_of: BlockIndex,
+    },
+    /// The ledger cannot process the request at the moment.
+    TemporarilyUnavailable,
+    /// Any other error.
+    GenericError {
+        /// A machine-readable error code.
+        error_code: Nat,
+        /// A human-readable error message.
+        message: String,
+    },
+}
+
+impl Display for TransferFromError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadFee { expected_fee } => {
+                write!(f, "transaction fee should be {}", expected_fee)
+            }
+            Self::BadBurn { min_burn_amount } => {
+                write!(f, "the minimum amount to burn is {}", min_burn_amount)
+            }
+            Self::InsufficientFunds { balance } => write!(
+                f,
+                "the debit account doesn't have enough funds to complete the transaction, current balance: {}",
+                balance
+            ),
+            Self::InsufficientAllowance { allowance } => write!(
+                f,
+                "the allowance is smaller than the amount and the fee, current allowance: {}",
+                allowance
+            ),
+            Self::TooOld => write!(f, "transaction is too old"),
+            Self::CreatedInFuture { ledger_time } => write!(
+                f,
+                "transaction's created_at_time is in future, ledger time: {}",
+                ledger_time
+            ),
+            Self::Duplicate { duplicate_of } => write!(
+                f,
+                "transaction is a duplicate of another transaction in block {}",
+                duplicate_of
+            ),
+            Self::TemporarilyUnavailable => write!(f, "the ledger is temporarily unavailable"),
+            Self::GenericError {
+                error_code,
+                message,
+            } => write!(f, "error {}: {}", error_code, message),
+        }
+    }
+}
+
+/// Arguments for the `icrc2_allowance` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AllowanceArgs {
+    /// The account owning the tokens.
+    pub account: Account,
+    /// The account allowed to transfer them.
+    pub spender: Account,
+}
+
+/// Result of the `icrc2_allowance` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Allowance {
+    /// The amount that the spender can transfer.
+    pub allowance: Nat,
+    /// The time at which the allowance expires, in nanoseconds since the UNIX epoch.
+    pub expires_at: Option<u64>,
+}
+
+/// Calls the "icrc2_approve" method on the specified canister.
+///
+/// # Example
+/// ```no_run
+/// use candid::{Nat, Principal};
+/// use ic_icrc_ledger_types::{icrc2_approve, Account, ApproveArgs, BlockIndex};
+///
+/// async fn approve(ledger_canister_id: Principal, spender: Principal) -> BlockIndex {
+///   icrc2_approve(
+///     ledger_canister_id,
+///     ApproveArgs {
+///       from_subaccount: None,
+///       spender: Account::from(spender),
+///       amount: Nat::from(1_000_000u64),
+///       expected_allowance: None,
+///       expires_at: None,
+///       fee: None,
+///       memo: None,
+///       created_at_time: None,
+///     },
+///   )
+///   .await
+///   .expect("call to ledger failed")
+///   .expect("approval failed")
+/// }
+/// ```
+pub async fn icrc2_approve(
+    ledger_canister_id: Principal,
+    args: ApproveArgs,
+) -> CallResult<ApproveResult> {
+    let (result,) = ic_cdk::call(ledger_canister_id, "icrc2_approve", (args,)).await?;
+    Ok(result)
+}
+
+/// Calls the "icrc2_allowance" method on the specified canister.
+pub async fn icrc2_allowance(
+    ledger_canister_id: Principal,
+    args: AllowanceArgs,
+) -> CallResult<Allowance> {
+    let (allowance,) = ic_cdk::call(ledger_canister_id, "icrc2_allowance", (args,)).await?;
+    Ok(allowance)
+}
+
+/// Calls the "icrc2_transfer_from" method on the specified canister.
+///
+/// # Example
+/// ```no_run
+/// use candid::{Nat, Principal};
+/// use ic_cdk::api::{caller, id};
+/// use ic_icrc_ledger_types::{icrc2_transfer_from, Account, BlockIndex, TransferFromArgs};
+///
+/// // Charges the caller, who approved this canister beforehand.
+/// async fn charge_caller(ledger_canister_id: Principal) -> BlockIndex {
+///   icrc2_transfer_from(
+///     ledger_canister_id,
+///     TransferFromArgs {
+///       spender_subaccount: None,
+///       from: Account::from(caller()),
+///       to: Account::from(id()),
+///       amount: Nat::from(1_000_000u64),
+///       fee: None,
+///       memo: None,
+///       created_at_time: None,
+///     },
+///   )
+///   .await
+///   .expect("call to ledger failed")
+///   .expect("transfer failed")
+/// }
+/// ```
+pub async fn icrc2_transfer_from(
+    ledger_canister_id: Principal,
+    args: TransferFromArgs,
+) -> CallResult<TransferFromResult> {
+    let (result,) = ic_cdk::call(ledger_canister_id, "icrc2_transfer_from", (args,)).await?;
+    Ok(result)
+}
@@ -0,0 +1,714 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use candid::{types::reference::Func, CandidType, Int, Nat, Principal};
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
+
+use ic_cdk::api::call::CallResult;
+
+use crate::{Account, Memo, Subaccount};
+
+/// The generic value of the ICRC-3 blocks.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// A blob.
+    Blob(ByteBuf),
+    /// A text.
+    Text(String),
+    /// A natural number.
+    Nat(Nat),
+    /// An integer.
+    Int(Int),
+    /// An array of values.
+    Array(Vec<Value>),
+    /// A map of names to values.
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Returns the representation-independent hash of the value, as defined by ICRC-3.
+    ///
+    /// The hash of a block is the `phash` field of the next block.
+    pub fn hash(&self) -> [u8; 32] {
+        match self {
+            Self::Blob(bytes) => Sha256::digest(bytes).into(),
+            Self::Text(text) => Sha256::digest(text.as_bytes()).into(),
+            Self::Nat(nat) => {
+                let mut buf = vec![];
+                nat.encode(&mut buf).expect("failed to encode a nat");
+                Sha256::digest(buf).into()
+            }
+            Self::Int(int) => {
+                let mut buf = vec![];
+                int.encode(&mut buf).expect("failed to encode an int");
+                Sha256::digest(buf).into()
+            }
+            Self::Array(values) => {
+                let mut hasher = Sha256::new();
+                for value in values {
+                    hasher.update(value.hash());
+                }
+                hasher.finalize().into()
+            }
+            Self::Map(map) => {
+                let mut entries: Vec<[u8; 64]> = map
+                    .iter()
+                    .map(|(name, value)| {
+                        let mut entry = [0; 64];
+                        entry[..32].copy_from_slice(&Sha256::digest(name.as_bytes()));
+                        entry[32..].copy_from_slice(&value.hash());
+                        entry
+                    })
+                    .collect();
+                entries.sort_unstable();
+                let mut hasher = Sha256::new();
+                for entry in entries {
+                    hasher.update(entry);
+                }
+                hasher.finalize().into()
+            }
+        }
+    }
+}
+
+/// A range of blocks to fetch.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetBlocksRequest {
+    /// The index of the first block to fetch.
+    pub start: Nat,
+    /// Max number of blocks to fetch.
+    pub length: Nat,
+}
+
+/// Arguments for the `icrc3_get_blocks` call: the ranges of blocks to fetch.
+pub type GetBlocksArgs = Vec<GetBlocksRequest>;
+
+/// A block and its index.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockWithId {
+    /// The index of the block.
+    pub id: Nat,
+    /// The block, which can be decoded into a [`Block`].
+    pub block: Value,
+}
+
+/// Blocks moved to an archive, and the function fetching them.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedBlocks {
+    /// The ranges of the requested blocks that the archive holds.
+    pub args: GetBlocksArgs,
+    /// The function that should be called with `args` to fetch the archived blocks.
+    pub callback: QueryBlocksFn,
+}
+
+/// Return type of the `icrc3_get_blocks` call.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GetBlocksResult {
+    /// The total number of blocks in the ledger.
+    pub log_length: Nat,
+    /// The requested blocks that the ledger holds.
+    pub blocks: Vec<BlockWithId>,
+    /// The requested blocks that the ledger moved to its archives.
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+/// Function type used by `icrc3_get_blocks` for fetching blocks from an archive.
+/// Has the signature `(`[`GetBlocksArgs`]`) -> (`[`GetBlocksResult`]`)`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct QueryBlocksFn(Func);
+
+impl From<Func> for QueryBlocksFn {
+    fn from(func: Func) -> Self {
+        Self(func)
+    }
+}
+
+impl From<QueryBlocksFn> for Func {
+    fn from(query_func: QueryBlocksFn) -> Self {
+        query_func.0
+    }
+}
+
+impl CandidType for QueryBlocksFn {
+    fn _ty() -> candid::types::Type {
+        candid::func!((GetBlocksArgs) -> (GetBlocksResult) query)
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: candid::types::Serializer,
+    {
+        Func::from(self.clone()).idl_serialize(serializer)
+    }
+}
+
+/// Arguments for the `icrc3_get_archives` call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetArchivesArgs {
+    /// If set, only the archives after this one are returned.
+    pub from: Option<Principal>,
+}
+
+/// An archive of a ledger.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveInfo {
+    /// The id of the archive canister.
+    pub canister_id: Principal,
+    /// The index of the first block of the archive.
+    pub start: Nat,
+    /// The index of the last block of the archive.
+    pub end: Nat,
+}
+
+/// The certificate of the last block of a ledger.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DataCertificate {
+    /// The certificate of the certified data of the ledger.
+    pub certificate: ByteBuf,
+    /// The CBOR-encoded hash tree, with the `last_block_index` and `last_block_hash` of the
+    /// ledger.
+    pub hash_tree: ByteBuf,
+}
+
+/// A block type supported by a ledger.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SupportedBlockType {
+    /// The block type, e.g. `1xfer`.
+    pub block_type: String,
+    /// The URL of the specification of the block type.
+    pub url: String,
+}
+
+/// A block of an ICRC-1 or ICRC-2 ledger, decoded from its ICRC-3 [`Value`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    /// The hash of the parent block, unless it is the first block.
+    pub parent_hash: Option<[u8; 32]>,
+    /// The time at which the ledger constructed the block, in nanoseconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// The fee that the ledger charged, if the transaction does not specify it.
+    pub fee: Option<Nat>,
+    /// The transaction of the block.
+    pub transaction: Transaction,
+}
+
+impl Block {
+    /// Returns the fee that the ledger charged for the transaction, if any.
+    pub fn effective_fee(&self) -> Option<&Nat> {
+        match &self.transaction.operation {
+            Operation::Transfer { fee, .. } | Operation::Approve { fee, .. } => {
+                self.fee.as_ref().or(fee.as_ref())
+            }
+            _ => self.fee.as_ref(),
+        }
+    }
+}
+
+/// A transaction of an ICRC-1 or ICRC-2 ledger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    /// The content of the transaction.
+    pub operation: Operation,
+    /// The memo that was provided for the transaction.
+    pub memo: Option<Memo>,
+    /// The time at which the client of the ledger created the transaction, in nanoseconds since
+    /// the UNIX epoch.
+    pub created_at_time: Option<u64>,
+}
+
+/// The content of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Tokens were minted.
+    Mint {
+        /// The account that the tokens were transferred to.
+        to: Account,
+        /// The amount that was minted.
+        amount: Nat,
+    },
+    /// Tokens were burned.
+    Burn {
+        /// The account that the tokens were burned from.
+        from: Account,
+        /// The account that burned them on behalf of `from`, following an approval.
+        spender: Option<Account>,
+        /// The amount that was burned.
+        amount: Nat,
+    },
+    /// Tokens were transferred from one account to another.
+    Transfer {
+        /// The account the tokens were transferred from.
+        from: Account,
+        /// The account the tokens were transferred to.
+        to: Account,
+        /// The account that performed the transfer on behalf of `from`, following an approval.
+        spender: Option<Account>,
+        /// The amount of tokens that were transferred.
+        amount: Nat,
+        /// The fee specified in the transfer request.
+        fee: Option<Nat>,
+    },
+    /// An account approved another account to transfer tokens on its behalf.
+    Approve {
+        /// The account that owns the tokens.
+        from: Account,
+        /// The account that was enabled to spend them.
+        spender: Account,
+        /// The allowance.
+        amount: Nat,
+        /// The allowance that the approval expected to replace.
+        expected_allowance: Option<Nat>,
+        /// The expiration date for this approval.
+        expires_at: Option<u64>,
+        /// The fee specified in the approval request.
+        fee: Option<Nat>,
+    },
+}
+
+/// An error for reporting a [`Value`] that is not a valid [`Block`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockParseError {
+    /// A required field is missing.
+    MissingField(String),
+    /// A field does not have the expected type.
+    InvalidField(String),
+    /// The type of the block, or the operation of its transaction, is not known.
+    UnknownBlockType(String),
+}
+
+impl Display for BlockParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(name) => write!(f, "missing field {}", name),
+            Self::InvalidField(name) => write!(f, "invalid field {}", name),
+            Self::UnknownBlockType(block_type) => write!(f, "unknown block type {}", block_type),
+        }
+    }
+}
+
+impl std::error::Error for BlockParseError {}
+
+// The fields of a map, prefixed with the name of the map in the errors.
+struct Fields<'a> {
+    map: &'a BTreeMap<String, Value>,
+    prefix: &'static str,
+}
+
+impl<'a> Fields<'a> {
+    fn optional<T>(
+        &self,
+        name: &str,
+        decode: fn(&'a Value) -> Option<T>,
+    ) -> Result<Option<T>, BlockParseError> {
+        self.map
+            .get(name)
+            .map(|value| {
+                decode(value).ok_or_else(|| {
+                    BlockParseError::InvalidField(format!("{}{}", self.prefix, name))
+                })
+            })
+            .transpose()
+    }
+
+    fn required<T>(
+        &self,
+        name: &str,
+        decode: fn(&'a Value) -> Option<T>,
+    ) -> Result<T, BlockParseError> {
+        self.optional(name, decode)?
+            .ok_or_else(|| BlockParseError::MissingField(format!("{}{}", self.prefix, name)))
+    }
+}
+
+fn as_map(value: &Value) -> Option<&BTreeMap<String, Value>> {
+    match value {
+        Value::Map(map) => Some(map),
+        _ => None,
+    }
+}
+
+fn as_text(value: &Value) -> Option<&str> {
+    match value {
+        Value::Text(text) => Some(text),
+        _ => None,
+    }
+}
+
+fn as_blob(value: &Value) -> Option<&[u8]> {
+    match value {
+        Value::Blob(bytes) => Some(bytes),
+        _ => None,
+    }
+}
+
+fn as_nat(value: &Value) -> Option<Nat> {
+    match value {
+        Value::Nat(nat) => Some(nat.clone()),
+        _ => None,
+    }
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Nat(nat) => u64::try_from(&nat.0).ok(),
+        _ => None,
+    }
+}
+
+fn as_hash(value: &Value) -> Option<[u8; 32]> {
+    as_blob(value)?.try_into().ok()
+}
+
+fn as_memo(value: &Value) -> Option<Memo> {
+    as_blob(value).map(|bytes| Memo::from(bytes.to_vec()))
+}
+
+// An account is an array of the owner and, unless it is the default one, the subaccount.
+fn as_account(value: &Value) -> Option<Account> {
+    let Value::Array(values) = value else {
+        return None;
+    };
+    let (owner, subaccount) = match &values[..] {
+        [owner] => (owner, None),
+        [owner, subaccount] => (owner, Some(subaccount)),
+        _ => return None,
+    };
+    let owner = Principal::try_from_slice(as_blob(owner)?).ok()?;
+    let subaccount = match subaccount {
+        Some(subaccount) => Some(Subaccount::try_from(as_blob(subaccount)?).ok()?),
+        None => None,
+    };
+    Some(Account { owner, subaccount })
+}
+
+impl TryFrom<&Value> for Block {
+    type Error = BlockParseError;
+
+    /// Decodes a block of the ICRC-1 and ICRC-2 [block types], as well as the blocks of the
+    /// ledgers predating them, which have no `btype` and set the operation in `tx.op`.
+    ///
+    /// [block types]: https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3#supported-standards
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let block = Fields {
+            map: as_map(value).ok_or_else(|| BlockParseError::InvalidField("block".into()))?,
+            prefix: "",
+        };
+        let tx = Fields {
+            map: block.required("tx", as_map)?,
+            prefix: "tx.",
+        };
+        let block_type = match block.optional("btype", as_text)? {
+            Some(btype) => btype,
+            None => tx.required("op", as_text)?,
+        };
+        let amount = || tx.required("amt", as_nat);
+        let operation = match block_type {
+            "1mint" | "mint" => Operation::Mint {
+                to: tx.required("to", as_account)?,
+                amount: amount()?,
+            },
+            "1burn" | "burn" => Operation::Burn {
+                from: tx.required("from", as_account)?,
+                spender: tx.optional("spender", as_account)?,
+                amount: amount()?,
+            },
+            "1xfer" | "2xfer" | "xfer" => Operation::Transfer {
+                from: tx.required("from", as_account)?,
+                to: tx.required("to", as_account)?,
+                spender: match block_type {
+                    "2xfer" => Some(tx.required("spender", as_account)?),
+                    _ => tx.optional("spender", as_account)?,
+                },
+                amount: amount()?,
+                fee: tx.optional("fee", as_nat)?,
+            },
+            "2approve" | "approve" => Operation::Approve {
+                from: tx.required("from", as_account)?,
+                spender: tx.required("spender", as_account)?,
+                amount: amount()?,
+                expected_allowance: tx.optional("expected_allowance", as_nat)?,
+                expires_at: tx.optional("expires_at", as_u64)?,
+                fee: tx.optional("fee", as_nat)?,
+            },
+            block_type => return Err(BlockParseError::UnknownBlockType(block_type.to_string())),
+        };
+        Ok(Self {
+            parent_hash: block.optional("phash", as_hash)?,
+            timestamp: block.required("ts", as_u64)?,
+            fee: block.optional("fee", as_nat)?,
+            transaction: Transaction {
+                operation,
+                memo: tx.optional("memo", as_memo)?,
+                created_at_time: tx.optional("ts", as_u64)?,
+            },
+        })
+    }
+}
+
+/// Calls the "icrc3_get_blocks" method on the specified canister.
+///
+/// The blocks that the ledger moved to its archives are fetched with
+/// [`query_archived_blocks`].
+///
+/// # Example
+/// ```no_run
+/// use candid::{Nat, Principal};
+/// use ic_cdk::api::call::CallResult;
+/// use ic_icrc_ledger_types::{
+///     icrc3_get_blocks, query_archived_blocks, Block, BlockWithId, GetBlocksRequest,
+/// };
+///
+/// async fn get_block(ledger: Principal, index: u64) -> CallResult<Option<Block>> {
+///   let args = vec![GetBlocksRequest { start: Nat::from(index), length: Nat::from(1u64) }];
+///   let mut result = icrc3_get_blocks(ledger, args).await?;
+///   for archived in result.archived_blocks {
+///     let archived = query_archived_blocks(&archived.callback, archived.args).await?;
+///     result.blocks.extend(archived.blocks);
+///   }
+///   Ok(result
+///     .blocks
+///     .into_iter()
+///     .find(|block| block.id == Nat::from(index))
+///     .map(|block| Block::try_from(&block.block).expect("invalid block")))
+/// }
+/// ```
+pub async fn icrc3_get_blocks(
+    ledger_canister_id: Principal,
+    args: GetBlocksArgs,
+) -> CallResult<GetBlocksResult> {
+    let (result,) = ic_cdk::call(ledger_canister_id, "icrc3_get_blocks", (args,)).await?;
+    Ok(result)
+}
+
+/// Continues a query started in [`icrc3_get_blocks`] by calling its returned archive function.
+pub async fn query_archived_blocks(
+    func: &QueryBlocksFn,
+    args: GetBlocksArgs,
+) -> CallResult<GetBlocksResult> {
+    let (result,) = ic_cdk::api::call::call(func.0.principal, &func.0.method, (args,)).await?;
+    Ok(result)
+}
+
+/// Calls the "icrc3_get_archives" method on the specified canister.
+pub async fn icrc3_get_archives(
+    ledger_canister_id: Principal,
+    args: GetArchivesArgs,
+) -> CallResult<Vec<ArchiveInfo>> {
+    let (archives,) = ic_cdk::call(ledger_canister_id, "icrc3_get_archives", (args,)).await?;
+    Ok(archives)
+}
+
+/// Calls the "icrc3_get_tip_certificate" method on the specified canister.
+pub async fn icrc3_get_tip_certificate(
+    ledger_canister_id: Principal,
+) -> CallResult<Option<DataCertificate>> {
+    let (certificate,) = ic_cdk::call(ledger_canister_id, "icrc3_get_tip_certificate", ()).await?;
+    Ok(certificate)
+}
+
+/// Calls the "icrc3_supported_block_types" method on the specified canister.
+pub async fn icrc3_supported_block_types(
+    ledger_canister_id: Principal,
+) -> CallResult<Vec<SupportedBlockType>> {
+    let (block_types,) =
+        ic_cdk::call(ledger_canister_id, "icrc3_supported_block_types", ()).await?;
+    Ok(block_types)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nat(n: u64) -> Value {
+        Value::Nat(Nat::from(n))
+    }
+
+    fn blob(bytes: &[u8]) -> Value {
+        Value::Blob(ByteBuf::from(bytes))
+    }
+
+    fn map<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+
+    // The examples of the ICRC-3 standard.
+    #[test]
+    fn test_value_hash() {
+        for (value, hash) in [
+            (
+                nat(42),
+                "684888c0ebb17f374298b65ee2807526c066094c701bcc7ebbe1c1095f494fc1",
+            ),
+            (
+                Value::Int(Int::from(-42)),
+                "de5a6f78116eca62d7fc5ce159d23ae6b889b365a1739ad2cf36f925a140d0cc",
+            ),
+            (
+                Value::Text("Hello, World!".to_string()),
+                "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f",
+            ),
+            (
+                blob(&[1, 2, 3, 4]),
+                "9f64a747e1b97f131fabb6b447296c9b6f0201e79fb3c5356e6c77e89b6a806a",
+            ),
+            (
+                Value::Array(vec![nat(3), Value::Text("foo".to_string()), blob(&[5, 6])]),
+                "514a04011caa503990d446b7dec5d79e19c221ae607fb08b2848c67734d468d6",
+            ),
+            (
+                map([
+                    (
+                        "from",
+                        blob(
+                            &hex::decode("00abcdef0012340056789a00bcdef000012345678900abcdef01")
+                                .unwrap(),
+                        ),
+                    ),
+                    (
+                        "to",
+                        blob(
+                            &hex::decode("00ab0def0012340056789a00bcdef000012345678900abcdef01")
+                                .unwrap(),
+                        ),
+                    ),
+                    ("amount", nat(42)),
+                    ("created_at", nat(1699218263)),
+                    ("memo", nat(0)),
+                ]),
+                "c56ece650e1de4269c5bdeff7875949e3e2033f85b2d193c2ff4f7f78bdcfc75",
+            ),
+        ] {
+            assert_eq!(hex::encode(value.hash()), hash);
+        }
+    }
+
+    #[test]
+    fn test_block_try_from_value() {
+        let alice = Principal::from_slice(&[1; 29]);
+        let bob = Principal::from_slice(&[2; 10]);
+        let parent_hash = [7; 32];
+        let block = map([
+            ("btype", Value::Text("2xfer".to_string())),
+            ("phash", blob(&parent_hash)),
+            ("ts", nat(1_700_000_000_000_000_000)),
+            ("fee", nat(10)),
+            (
+                "tx",
+                map([
+                    ("amt", nat(500)),
+                    ("from", Value::Array(vec![blob(alice.as_slice())])),
+                    (
+                        "to",
+                        Value::Array(vec![blob(bob.as_slice()), blob(&[3; 32])]),
+                    ),
+                    ("spender", Value::Array(vec![blob(bob.as_slice())])),
+                    ("memo", blob(b"order 42")),
+                ]),
+            ),
+        ]);
+        let block = Block::try_from(&block).unwrap();
+        assert_eq!(
+            block,
+            Block {
+                parent_hash: Some(parent_hash),
+                timestamp: 1_700_000_000_000_000_000,
+                fee: Some(Nat::from(10u64)),
+                transaction: Transaction {
+                    operation: Operation::Transfer {
+                        from: Account::from(alice),
+                        to: Account {
+                            owner: bob,
+                            subaccount: Some([3; 32]),
+                        },
+                        spender: Some(Account::from(bob)),
+                        amount: Nat::from(500u64),
+                        fee: None,
+                    },
+                    memo: Some(Memo::from(b"order 42".to_vec())),
+                    created_at_time: None,
+                },
+            }
+        );
+        assert_eq!(block.effective_fee(), Some(&Nat::from(10u64)));
+
+        // A block predating the block types.
+        let block = map([
+            ("ts", nat(1)),
+            (
+                "tx",
+                map([
+                    ("op", Value::Text("approve".to_string())),
+                    ("amt", nat(100)),
+                    ("from", Value::Array(vec![blob(alice.as_slice())])),
+                    ("spender", Value::Array(vec![blob(bob.as_slice())])),
+                    ("expires_at", nat(2)),
+                    ("fee", nat(5)),
+                    ("ts", nat(0)),
+                ]),
+            ),
+        ]);
+        let block = Block::try_from(&block).unwrap();
+        assert_eq!(
+            block.transaction.operation,
+            Operation::Approve {
+                from: Account::from(alice),
+                spender: Account::from(bob),
+                amount: Nat::from(100u64),
+                expected_allowance: None,
+                expires_at: Some(2),
+                fee: Some(Nat::from(5u64)),
+            }
+        );
+        assert_eq!(block.transaction.created_at_time, Some(0));
+        assert_eq!(block.parent_hash, None);
+        assert_eq!(block.effective_fee(), Some(&Nat::from(5u64)));
+    }
+
+    #[test]
+    fn test_block_try_from_invalid_value() {
+        let mint = |tx: Value| {
+            map([
+                ("btype", Value::Text("1mint".to_string())),
+                ("ts", nat(1)),
+                ("tx", tx),
+            ])
+        };
+        let to = Value::Array(vec![blob(&[1; 29])]);
+        assert!(Block::try_from(&mint(map([("amt", nat(1)), ("to", to.clone())]))).is_ok());
+        assert_eq!(
+            Block::try_from(&mint(map([("to", to.clone())]))),
+            Err(BlockParseError::MissingField("tx.amt".to_string()))
+        );
+        assert_eq!(
+            Block::try_from(&mint(map([("amt", nat(1)), ("to", blob(&[1; 29]))]))),
+            Err(BlockParseError::InvalidField("tx.to".to_string()))
+        );
+        assert_eq!(
+            Block::try_from(&mint(map([
+                ("amt", nat(1)),
+                ("to", Value::Array(vec![blob(&[1; 29]), blob(&[0; 31])])),
+            ]))),
+            Err(BlockParseError::InvalidField("tx.to".to_string()))
+        );
+        assert_eq!(
+            Block::try_from(&map([
+                ("btype", Value::Text("3vote".to_string())),
+                ("ts", nat(1)),
+                ("tx", map([])),
+            ])),
+            Err(BlockParseError::UnknownBlockType("3vote".to_string()))
+        );
+        assert_eq!(
+            Block::try_from(&nat(1)),
+            Err(BlockParseError::InvalidField("block".to_string()))
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! A library of types to communicate with ledger canisters implementing the ICRC-1, ICRC-2 and
+//! ICRC-3 standards.
+//!
+//! * [ICRC-1](https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-1): [`Account`] and
+//!   its textual encoding, and the token interface, e.g. [`icrc1_balance_of`] and
+//!   [`icrc1_transfer`].
+//! * [ICRC-2](https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2): the approvals,
+//!   with [`icrc2_approve`] and [`icrc2_transfer_from`].
+//! * [ICRC-3](https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3): the blocks of the
+//!   ledger, fetched with [`icrc3_get_blocks`] as generic [`Value`]s, which are decoded into
+//!   [`Block`]s.
+//!
+//! For the legacy interface of the ICP ledger, see the `ic-ledger-types` crate.
+
+#![warn(
+    elided_lifetimes_in_paths,
+    missing_debug_implementations,
+    missing_docs,
+    unsafe_op_in_unsafe_fn,
+    clippy::undocumented_unsafe_blocks,
+    clippy::missing_safety_doc
+)]
+
+mod account;
+mod icrc1;
+mod icrc2;
+mod icrc3;
+
+pub use crate::account::*;
+pub use crate::icrc1::*;
+pub use crate::icrc2::*;
+pub use crate::icrc3::*;
@@ -0,0 +1,564 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use candid::Principal;
+
+use ic_cdk::api::call::{CallResult, RejectionCode};
+
+use crate::{
+    ArchivedBlockRange, Block, BlockIndex, GetBlocksArgs, GetBlocksError, GetBlocksResult,
+    QueryArchiveFn, QueryBlocksResponse,
+};
+
+/// The maximum number of blocks that the ledger and its archives return in one call.
+pub const MAX_BLOCKS_PER_REQUEST: u64 = 2000;
+
+/// An async iterator over a range of blocks of the ledger.
+///
+/// The blocks are fetched in batches with [`query_blocks`](crate::query_blocks), and from the
+/// archives with [`query_archived_blocks`](crate::query_archived_blocks) when the ledger archived
+/// them. With
+/// [`with_parent_hash_validation`](Self::with_parent_hash_validation), the parent hash of every
+/// block is also checked against the hash of the previous block.
+///
+/// The stream stops at the end of the range, or at the end of the chain if the range goes
+/// beyond it.
+///
+/// # Example
+///
+/// ```no_run
+/// use ic_ledger_types::{BlockStream, BlockStreamError, MAINNET_LEDGER_CANISTER_ID};
+///
+/// async fn index_blocks(start: u64, end: u64) -> Result<(), BlockStreamError> {
+///   let mut blocks = BlockStream::new(MAINNET_LEDGER_CANISTER_ID, start..end);
+///   while let Some(result) = blocks.next().await {
+///     let (index, block) = result?;
+///     ic_cdk::println!("block {}: {:?}", index, block.transaction.operation);
+///   }
+///   Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BlockStream {
+    ledger_canister_id: Principal,
+    next: BlockIndex,
+    end: BlockIndex,
+    batch_size: u64,
+    // The fetched blocks, starting at `next`.
+    blocks: VecDeque<Block>,
+    // The archived blocks of the last response of the ledger.
+    archived_blocks: Vec<ArchivedBlockRange>,
+    // The hash of the block before `next`, if known.
+    parent_hash: Option<[u8; 32]>,
+    validate_parent_hashes: bool,
+}
+
+impl BlockStream {
+    /// Creates a stream of the blocks of `range`.
+    pub fn new(ledger_canister_id: Principal, range: Range<BlockIndex>) -> Self {
+        Self {
+            ledger_canister_id,
+            next: range.start,
+            end: range.end.max(range.start),
+            batch_size: MAX_BLOCKS_PER_REQUEST,
+            blocks: VecDeque::new(),
+            archived_blocks: vec![],
+            parent_hash: None,
+            validate_parent_hashes: false,
+        }
+    }
+
+    /// Sets the number of blocks requested in one call, [`MAX_BLOCKS_PER_REQUEST`] by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        assert!(batch_size > 0, "the batch size must be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Enables the check of the parent hashes, which are compared to the [`Block::hash`] of
+    /// the previous blocks.
+    ///
+    /// The hashes rest on a reconstruction of the encoding of the ledger, see
+    /// [`Block::encode`], so a block it gets wrong ends the stream with
+    /// [`BlockStreamError::InvalidParentHash`].
+    pub fn with_parent_hash_validation(mut self) -> Self {
+        self.validate_parent_hashes = true;
+        self
+    }
+
+    /// Sets the hash of the block before the range, e.g. the last block indexed so far, so that
+    /// the parent hash of the first block is checked too.
+    pub fn with_parent_hash(mut self, parent_hash: [u8; 32]) -> Self {
+        self.parent_hash = Some(parent_hash);
+        self
+    }
+
+    /// Returns the next block and its index, or `None` at the end of the range or of the chain.
+    ///
+    /// After a failed call, the next call to this method retries it. After an invalid parent
+    /// hash, the stream ends.
+    pub async fn next(&mut self) -> Option<Result<(BlockIndex, Block), BlockStreamError>> {
+        self.next_from(&Ledger).await
+    }
+
+    async fn next_from(
+        &mut self,
+        source: &impl BlockSource,
+    ) -> Option<Result<(BlockIndex, Block), BlockStreamError>> {
+        if self.next >= self.end {
+            return None;
+        }
+        if self.blocks.is_empty() {
+            match self.fetch(source).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.end = self.next;
+                    return None;
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        let block = self.blocks.pop_front()?;
+        let index = self.next;
+        if let Err(err) = self.check_parent_hash(index, &block) {
+            self.end = self.next;
+            self.blocks.clear();
+            return Some(Err(err));
+        }
+        self.next += 1;
+        Some(Ok((index, block)))
+    }
+
+    // Fetches the blocks from `next`, and returns false at the end of the chain.
+    async fn fetch(&mut self, source: &impl BlockSource) -> Result<bool, BlockStreamError> {
+        let length = (self.end - self.next).min(self.batch_size);
+        if self.archived_range().is_none() {
+            let args = GetBlocksArgs {
+                start: self.next,
+                length,
+            };
+            let response = source.query_blocks(self.ledger_canister_id, args).await?;
+            self.archived_blocks = response.archived_blocks;
+            if response.first_block_index == self.next && !response.blocks.is_empty() {
+                self.blocks
+                    .extend(response.blocks.into_iter().take(length as usize));
+                return Ok(true);
+            }
+            if self.next >= response.chain_length {
+                return Ok(false);
+            }
+        }
+        let Some(range) = self.archived_range() else {
+            return Err(BlockStreamError::MissingBlock(self.next));
+        };
+        let args = GetBlocksArgs {
+            start: self.next,
+            length: length.min(range.start + range.length - self.next),
+        };
+        let callback = range.callback.clone();
+        let blocks = source
+            .query_archived_blocks(&callback, args.clone())
+            .await??
+            .blocks;
+        if blocks.is_empty() {
+            return Err(BlockStreamError::MissingBlock(self.next));
+        }
+        self.blocks
+            .extend(blocks.into_iter().take(args.length as usize));
+        Ok(true)
+    }
+
+    // The range of archived blocks of the last response of the ledger holding `next`.
+    fn archived_range(&self) -> Option<&ArchivedBlockRange> {
+        self.archived_blocks
+            .iter()
+            .find(|range| range.start <= self.next && self.next - range.start < range.length)
+    }
+
+    fn check_parent_hash(
+        &mut self,
+        index: BlockIndex,
+        block: &Block,
+    ) -> Result<(), BlockStreamError> {
+        if !self.validate_parent_hashes {
+            return Ok(());
+        }
+        // The first block has no parent.
+        let expected = if index == 0 {
+            Some(None)
+        } else {
+            self.parent_hash.map(Some)
+        };
+        if let Some(expected) = expected {
+            if block.parent_hash != expected {
+                return Err(BlockStreamError::InvalidParentHash {
+                    index,
+                    expected,
+                    found: block.parent_hash,
+                });
+            }
+        }
+        self.parent_hash = Some(block.hash());
+        Ok(())
+    }
+}
+
+// The calls of a `BlockStream` to the ledger and its archives.
+trait BlockSource {
+    async fn query_blocks(
+        &self,
+        ledger_canister_id: Principal,
+        args: GetBlocksArgs,
+    ) -> CallResult<QueryBlocksResponse>;
+
+    async fn query_archived_blocks(
+        &self,
+        callback: &QueryArchiveFn,
+        args: GetBlocksArgs,
+    ) -> CallResult<GetBlocksResult>;
+}
+
+struct Ledger;
+
+impl BlockSource for Ledger {
+    async fn query_blocks(
+        &self,
+        ledger_canister_id: Principal,
+        args: GetBlocksArgs,
+    ) -> CallResult<QueryBlocksResponse> {
+        crate::query_blocks(ledger_canister_id, args).await
+    }
+
+    async fn query_archived_blocks(
+        &self,
+        callback: &QueryArchiveFn,
+        args: GetBlocksArgs,
+    ) -> CallResult<GetBlocksResult> {
+        crate::query_archived_blocks(callback, args).await
+    }
+}
+
+/// An error of a [`BlockStream`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlockStreamError {
+    /// A call to the ledger or to an archive failed.
+    CallRejected(RejectionCode, String),
+    /// An archive returned an error.
+    GetBlocks(GetBlocksError),
+    /// Neither the ledger nor its archives returned the block, although it is in the chain.
+    MissingBlock(BlockIndex),
+    /// The parent hash of the block is not the hash of the previous block.
+    InvalidParentHash {
+        /// The index of the block.
+        index: BlockIndex,
+        /// The hash of the previous block.
+        expected: Option<[u8; 32]>,
+        /// The parent hash of the block.
+        found: Option<[u8; 32]>,
+    },
+}
+
+impl From<(RejectionCode, String)> for BlockStreamError {
+    fn from((code, message): (RejectionCode, String)) -> Self {
+        Self::CallRejected(code, message)
+    }
+}
+
+impl From<GetBlocksError> for BlockStreamError {
+    fn from(err: GetBlocksError) -> Self {
+        Self::GetBlocks(err)
+    }
+}
+
+impl Display for BlockStreamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let hex = |hash: &Option<[u8; 32]>| hash.map_or("none".to_string(), hex::encode);
+        match self {
+            Self::CallRejected(code, message) => {
+                write!(f, "the call was rejected ({:?}): {}", code, message)
+            }
+            Self::GetBlocks(err) => write!(f, "{}", err),
+            Self::MissingBlock(index) => write!(f, "block {} could not be fetched", index),
+            Self::InvalidParentHash {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "invalid parent hash of block {}: expected {} but found {}",
+                index,
+                hex(expected),
+                hex(found)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccountIdentifier, BlockRange, Memo, Operation, Timestamp, Tokens, Transaction};
+    use candid::types::reference::Func;
+    use futures::executor::block_on;
+
+    fn chain(len: u64) -> Vec<Block> {
+        let mut blocks: Vec<Block> = vec![];
+        for i in 0..len {
+            blocks.push(Block {
+                parent_hash: blocks.last().map(Block::hash),
+                transaction: Transaction {
+                    memo: Memo(i),
+                    operation: Some(Operation::Mint {
+                        to: AccountIdentifier::try_from([i as u8; 28]).unwrap(),
+                        amount: Tokens::from_e8s(i * 100),
+                    }),
+                    created_at_time: Timestamp { timestamp_nanos: i },
+                    icrc1_memo: None,
+                },
+                timestamp: Timestamp {
+                    timestamp_nanos: 1_000 + i,
+                },
+            });
+        }
+        blocks
+    }
+
+    fn stream(range: Range<BlockIndex>) -> BlockStream {
+        BlockStream::new(Principal::management_canister(), range).with_parent_hash_validation()
+    }
+
+    // A ledger whose first blocks are in archives, which return at most `max_blocks` per call,
+    // and nothing from the `missing` block.
+    struct FakeLedger {
+        chain: Vec<Block>,
+        archives: Vec<Range<BlockIndex>>,
+        max_blocks: u64,
+        missing: Option<BlockIndex>,
+    }
+
+    impl FakeLedger {
+        fn blocks(&self, range: Range<BlockIndex>) -> Vec<Block> {
+            let end = range.end.min(range.start + self.max_blocks);
+            self.chain[range.start as usize..end as usize].to_vec()
+        }
+    }
+
+    fn intersection(a: &Range<BlockIndex>, b: &Range<BlockIndex>) -> Range<BlockIndex> {
+        a.start.max(b.start)..a.end.min(b.end).max(a.start.max(b.start))
+    }
+
+    impl BlockSource for FakeLedger {
+        async fn query_blocks(
+            &self,
+            _: Principal,
+            args: GetBlocksArgs,
+        ) -> CallResult<QueryBlocksResponse> {
+            let chain_length = self.chain.len() as u64;
+            let requested = args.start..args.start + args.length;
+            let first_local = self.archives.last().map_or(0, |archive| archive.end);
+            let local = intersection(&requested, &(first_local..chain_length));
+            let archived_blocks = self
+                .archives
+                .iter()
+                .enumerate()
+                .map(|(i, archive)| (i, intersection(&requested, archive)))
+                .filter(|(_, range)| !range.is_empty())
+                .map(|(i, range)| ArchivedBlockRange {
+                    start: range.start,
+                    length: range.end - range.start,
+                    callback: QueryArchiveFn::from(Func {
+                        principal: Principal::management_canister(),
+                        method: i.to_string(),
+                    }),
+                })
+                .collect();
+            Ok(QueryBlocksResponse {
+                chain_length,
+                certificate: None,
+                blocks: self.blocks(local.clone()),
+                first_block_index: local.start,
+                archived_blocks,
+            })
+        }
+
+        async fn query_archived_blocks(
+            &self,
+            callback: &QueryArchiveFn,
+            args: GetBlocksArgs,
+        ) -> CallResult<GetBlocksResult> {
+            let archive = &self.archives[Func::from(callback.clone())
+                .method
+                .parse::<usize>()
+                .unwrap()];
+            if !archive.contains(&args.start) {
+                return Ok(Err(GetBlocksError::BadFirstBlockIndex {
+                    requested_index: args.start,
+                    first_valid_index: archive.start,
+                }));
+            }
+            if self.missing == Some(args.start) {
+                return Ok(Ok(BlockRange { blocks: vec![] }));
+            }
+            let range = intersection(&(args.start..args.start + args.length), archive);
+            Ok(Ok(BlockRange {
+                blocks: self.blocks(range),
+            }))
+        }
+    }
+
+    // The blocks of the stream, up to the first error.
+    fn collect(
+        stream: &mut BlockStream,
+        ledger: &FakeLedger,
+    ) -> Vec<Result<(BlockIndex, Block), BlockStreamError>> {
+        block_on(async {
+            let mut results = vec![];
+            while let Some(result) = stream.next_from(ledger).await {
+                let is_err = result.is_err();
+                results.push(result);
+                if is_err {
+                    break;
+                }
+            }
+            results
+        })
+    }
+
+    #[test]
+    fn test_stream_across_archives() {
+        let ledger = FakeLedger {
+            chain: chain(20),
+            archives: vec![0..5, 5..12],
+            max_blocks: 3,
+            missing: None,
+        };
+        for batch_size in [1, 4, 7, MAX_BLOCKS_PER_REQUEST] {
+            for range in [0..20, 2..18, 4..6, 11..13, 12..20] {
+                let expected: Vec<_> = range
+                    .clone()
+                    .map(|i| Ok((i, ledger.chain[i as usize].clone())))
+                    .collect();
+                let mut blocks = stream(range).with_batch_size(batch_size);
+                assert_eq!(collect(&mut blocks, &ledger), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_stops_at_end_of_chain() {
+        let ledger = FakeLedger {
+            chain: chain(6),
+            archives: vec![0..2, 2..3],
+            max_blocks: 10,
+            missing: None,
+        };
+        let mut blocks = stream(2..100);
+        let indices: Vec<_> = collect(&mut blocks, &ledger)
+            .into_iter()
+            .map(|result| result.unwrap().0)
+            .collect();
+        assert_eq!(indices, [2, 3, 4, 5]);
+        assert_eq!(collect(&mut blocks, &ledger), []);
+        assert_eq!(collect(&mut stream(6..10), &ledger), []);
+        assert_eq!(collect(&mut stream(3..3), &ledger), []);
+    }
+
+    #[test]
+    fn test_missing_archived_block() {
+        let ledger = FakeLedger {
+            chain: chain(6),
+            archives: vec![0..2, 2..3],
+            max_blocks: 10,
+            missing: Some(1),
+        };
+        let mut blocks = stream(0..6).with_batch_size(1);
+        assert_eq!(
+            collect(&mut blocks, &ledger),
+            [
+                Ok((0, ledger.chain[0].clone())),
+                Err(BlockStreamError::MissingBlock(1))
+            ]
+        );
+        // The failed call is retried.
+        assert_eq!(
+            collect(&mut blocks, &ledger),
+            [Err(BlockStreamError::MissingBlock(1))]
+        );
+    }
+
+    #[test]
+    fn test_parent_hash_validation_is_opt_in() {
+        let mut ledger = FakeLedger {
+            chain: chain(6),
+            archives: vec![0..2, 2..3],
+            max_blocks: 10,
+            missing: None,
+        };
+        ledger.chain[4].parent_hash = Some([0; 32]);
+        let unchecked = BlockStream::new(Principal::management_canister(), 0..6);
+        assert!(collect(&mut { unchecked }, &ledger)
+            .iter()
+            .all(Result::is_ok));
+        let results = collect(&mut stream(0..6), &ledger);
+        assert_eq!(results.len(), 5);
+        assert!(matches!(
+            results[4],
+            Err(BlockStreamError::InvalidParentHash { index: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_parent_hash() {
+        let blocks = chain(5);
+        let mut from_start = stream(0..5);
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(from_start.check_parent_hash(i as u64, block), Ok(()));
+        }
+
+        // The first block is only checked with the hash of its parent.
+        let mut from_middle = stream(2..5);
+        let mut tampered = blocks[2].clone();
+        tampered.parent_hash = Some([0; 32]);
+        assert_eq!(from_middle.check_parent_hash(2, &tampered), Ok(()));
+        let mut from_middle = stream(2..5).with_parent_hash(blocks[1].hash());
+        assert_eq!(
+            from_middle.check_parent_hash(2, &tampered),
+            Err(BlockStreamError::InvalidParentHash {
+                index: 2,
+                expected: Some(blocks[1].hash()),
+                found: Some([0; 32]),
+            })
+        );
+
+        let mut tampered = blocks[3].clone();
+        tampered.transaction.memo = Memo(42);
+        let mut stream = stream(0..5);
+        for (i, block) in blocks[..3].iter().enumerate() {
+            assert_eq!(stream.check_parent_hash(i as u64, block), Ok(()));
+        }
+        assert_eq!(stream.check_parent_hash(3, &tampered), Ok(()));
+        assert!(matches!(
+            stream.check_parent_hash(4, &blocks[4]),
+            Err(BlockStreamError::InvalidParentHash { index: 4, .. })
+        ));
+
+        let mut unchecked =
+            BlockStream::new(Principal::management_canister(), 3..5).with_parent_hash([0; 32]);
+        assert_eq!(unchecked.check_parent_hash(3, &blocks[3]), Ok(()));
+    }
+
+    #[test]
+    fn test_first_block_has_no_parent() {
+        let mut block = chain(1).remove(0);
+        block.parent_hash = Some([1; 32]);
+        assert!(matches!(
+            stream(0..1).check_parent_hash(0, &block),
+            Err(BlockStreamError::InvalidParentHash { index: 0, .. })
+        ));
+    }
+}
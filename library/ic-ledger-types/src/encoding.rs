@@ -0,0 +1,317 @@
+//! The protobuf encoding of the blocks, which the ledger hashes to chain them.
+//!
+//! The messages are the ones of the `ic_ledger.pb.v1` package of the ledger, with the fields
+//! encoded in the order of their tags.
+//!
+//! The candid blocks returned by `query_blocks` don't hold everything the ledger encoded, so
+//! parts of the encoding are reconstructed: an approval is encoded as a `Send` of no tokens to
+//! the spender, and a `created_at_time` equal to the time of the block as absent.
+
+use sha2::{Digest, Sha256};
+
+use crate::{AccountIdentifier, Block, Operation, Timestamp, Tokens, Transaction};
+
+fn varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+// A `uint64` field, omitted when zero.
+fn uint64_field(buf: &mut Vec<u8>, tag: u64, n: u64) {
+    if n != 0 {
+        varint(buf, tag << 3);
+        varint(buf, n);
+    }
+}
+
+// A `bytes` field, omitted when empty.
+fn bytes_field(buf: &mut Vec<u8>, tag: u64, bytes: &[u8]) {
+    if !bytes.is_empty() {
+        varint(buf, tag << 3 | 2);
+        varint(buf, bytes.len() as u64);
+        buf.extend_from_slice(bytes);
+    }
+}
+
+// A message field, which is encoded even when empty.
+fn message_field(buf: &mut Vec<u8>, tag: u64, encode: impl FnOnce(&mut Vec<u8>)) {
+    let mut message = vec![];
+    encode(&mut message);
+    varint(buf, tag << 3 | 2);
+    varint(buf, message.len() as u64);
+    buf.extend_from_slice(&message);
+}
+
+fn timestamp(buf: &mut Vec<u8>, tag: u64, timestamp: &Timestamp) {
+    message_field(buf, tag, |buf| {
+        uint64_field(buf, 1, timestamp.timestamp_nanos)
+    });
+}
+
+fn tokens(buf: &mut Vec<u8>, tag: u64, tokens: &Tokens) {
+    message_field(buf, tag, |buf| uint64_field(buf, 1, tokens.e8s));
+}
+
+fn account(buf: &mut Vec<u8>, tag: u64, account: &AccountIdentifier) {
+    message_field(buf, tag, |buf| bytes_field(buf, 1, account.as_bytes()));
+}
+
+// A `Send` message: a transfer, a transfer on behalf of another account, or an approval.
+fn send(
+    buf: &mut Vec<u8>,
+    from: &AccountIdentifier,
+    to: &AccountIdentifier,
+    amount: &Tokens,
+    fee: &Tokens,
+    extension: impl FnOnce(&mut Vec<u8>),
+) {
+    message_field(buf, 3, |buf| {
+        account(buf, 1, from);
+        account(buf, 2, to);
+        tokens(buf, 3, amount);
+        tokens(buf, 4, fee);
+        extension(buf);
+    });
+}
+
+fn transfer_from(buf: &mut Vec<u8>, spender: Option<&AccountIdentifier>) {
+    if let Some(spender) = spender {
+        message_field(buf, 6, |buf| account(buf, 1, spender));
+    }
+}
+
+fn operation(buf: &mut Vec<u8>, operation: &Operation) {
+    match operation {
+        Operation::Burn {
+            from,
+            spender,
+            amount,
+        } => message_field(buf, 1, |buf| {
+            account(buf, 1, from);
+            tokens(buf, 3, amount);
+            if let Some(spender) = spender {
+                account(buf, 4, spender);
+            }
+        }),
+        Operation::Mint { to, amount } => message_field(buf, 2, |buf| {
+            account(buf, 2, to);
+            tokens(buf, 3, amount);
+        }),
+        Operation::Transfer {
+            from,
+            to,
+            amount,
+            fee,
+            spender,
+        } => send(buf, from, to, amount, fee, |buf| {
+            transfer_from(buf, spender.as_ref())
+        }),
+        Operation::TransferFrom {
+            from,
+            to,
+            spender,
+            amount,
+            fee,
+        } => send(buf, from, to, amount, fee, |buf| {
+            transfer_from(buf, Some(spender))
+        }),
+        // The spender is the destination of the `Send`, which transfers nothing.
+        Operation::Approve {
+            from,
+            spender,
+            allowance,
+            expected_allowance,
+            expires_at,
+            fee,
+        } => send(buf, from, spender, &Tokens::ZERO, fee, |buf| {
+            message_field(buf, 5, |buf| {
+                tokens(buf, 1, allowance);
+                if let Some(expires_at) = expires_at {
+                    timestamp(buf, 2, expires_at);
+                }
+                if let Some(expected_allowance) = expected_allowance {
+                    tokens(buf, 3, expected_allowance);
+                }
+            })
+        }),
+    }
+}
+
+// `created_at_time` is `None` if the ledger filled it in with the time of the block.
+fn transaction(buf: &mut Vec<u8>, transaction: &Transaction, created_at_time: Option<&Timestamp>) {
+    if let Some(op) = &transaction.operation {
+        operation(buf, op);
+    }
+    message_field(buf, 4, |buf| uint64_field(buf, 1, transaction.memo.0));
+    if let Some(created_at_time) = created_at_time {
+        timestamp(buf, 6, created_at_time);
+    }
+    if let Some(icrc1_memo) = &transaction.icrc1_memo {
+        message_field(buf, 7, |buf| bytes_field(buf, 1, icrc1_memo));
+    }
+}
+
+impl Transaction {
+    /// Returns the protobuf encoding of the transaction.
+    ///
+    /// The `created_at_time` is always encoded. In a block, it is omitted if it is the time of
+    /// the block, see [`Block::encode`].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        transaction(&mut buf, self, Some(&self.created_at_time));
+        buf
+    }
+}
+
+impl Block {
+    /// Returns the protobuf encoding of the block, as the ledger stores it.
+    ///
+    /// The ledger reports the time of the block as the `created_at_time` of the transactions
+    /// created without one, so a `created_at_time` equal to the time of the block is omitted.
+    /// This is a guess at what the ledger stored: a transaction created with the exact time of
+    /// its block encodes differently, and its hash doesn't match the `parent_hash` of the next
+    /// block.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        if let Some(parent_hash) = &self.parent_hash {
+            message_field(&mut buf, 1, |buf| bytes_field(buf, 1, parent_hash));
+        }
+        timestamp(&mut buf, 2, &self.timestamp);
+        let created_at_time = &self.transaction.created_at_time;
+        message_field(&mut buf, 3, |buf| {
+            transaction(
+                buf,
+                &self.transaction,
+                (*created_at_time != self.timestamp).then_some(created_at_time),
+            )
+        });
+        buf
+    }
+
+    /// Returns the hash of the block: the SHA-256 of its [encoding](Self::encode).
+    ///
+    /// The hash of a block is the `parent_hash` of the next block.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ic_cdk::api::call::CallResult;
+    /// use ic_ledger_types::{query_blocks, GetBlocksArgs, MAINNET_LEDGER_CANISTER_ID};
+    ///
+    /// // Checks that the blocks returned by the ledger are chained.
+    /// async fn check_chain(start: u64) -> CallResult<bool> {
+    ///   let args = GetBlocksArgs { start, length: 100 };
+    ///   let blocks = query_blocks(MAINNET_LEDGER_CANISTER_ID, args).await?.blocks;
+    ///   Ok(blocks
+    ///     .windows(2)
+    ///     .all(|pair| pair[1].parent_hash == Some(pair[0].hash())))
+    /// }
+    /// ```
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::digest(self.encode()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Memo;
+
+    fn account(n: u8) -> AccountIdentifier {
+        AccountIdentifier::try_from([n; 28]).unwrap()
+    }
+
+    fn mint() -> Block {
+        Block {
+            parent_hash: None,
+            transaction: Transaction {
+                memo: Memo(0),
+                operation: Some(Operation::Mint {
+                    to: account(1),
+                    amount: Tokens::from_e8s(100_000_000),
+                }),
+                created_at_time: Timestamp {
+                    timestamp_nanos: 1_000,
+                },
+                icrc1_memo: None,
+            },
+            timestamp: Timestamp {
+                timestamp_nanos: 1_000,
+            },
+        }
+    }
+
+    fn transfer() -> Block {
+        Block {
+            parent_hash: Some(mint().hash()),
+            transaction: Transaction {
+                memo: Memo(42),
+                operation: Some(Operation::Transfer {
+                    from: account(1),
+                    to: account(2),
+                    amount: Tokens::from_e8s(500),
+                    fee: Tokens::from_e8s(10_000),
+                    spender: None,
+                }),
+                created_at_time: Timestamp {
+                    timestamp_nanos: 1_500,
+                },
+                icrc1_memo: Some(b"hi".to_vec().into()),
+            },
+            timestamp: Timestamp {
+                timestamp_nanos: 2_000,
+            },
+        }
+    }
+
+    // These vectors come from this encoder, cross-checked with the messages of the ledger
+    // compiled by prost. They are not blocks of the mainnet ledger.
+    #[test]
+    fn test_block_encoding() {
+        assert_eq!(
+            hex::encode(mint().encode()),
+            concat!(
+                "120308e8071a2f122b12220a205b116adf01010101010101010101010101010101010101",
+                "0101010101010101011a050880c2d72f2200",
+            )
+        );
+        assert_eq!(
+            hex::encode(mint().hash()),
+            "e2ded0745fade475987f2cb45c75f298220dac995c865ed19fdd27591f223db7"
+        );
+        assert_eq!(
+            hex::encode(transfer().encode()),
+            concat!(
+                "0a220a20e2ded0745fade475987f2cb45c75f298220dac995c865ed19fdd27591f223db7",
+                "120308d00f1a631a520a220a205b116adf01010101010101010101010101010101010101",
+                "01010101010101010112220a20edc34bc402020202020202020202020202020202020202",
+                "0202020202020202021a0308f403220308904e2202082a320308dc0b3a040a026869",
+            )
+        );
+        assert_eq!(
+            hex::encode(transfer().hash()),
+            "8480b5e8400c27803f1e63c29e4c846e1dd99f306e2449aab4c5fa1ab84848e9"
+        );
+    }
+
+    #[test]
+    fn test_transaction_encoding() {
+        let block = transfer();
+        // The transaction of a block is its field 3.
+        assert_eq!(block.transaction.encode(), &block.encode()[43..]);
+
+        // The ledger omits the `created_at_time` that is the time of the block.
+        let mut block = mint();
+        let encoding = block.transaction.encode();
+        assert_eq!(
+            &encoding[encoding.len() - 5..],
+            [0x32, 0x03, 0x08, 0xe8, 0x07]
+        );
+        assert!(block.encode().ends_with(&encoding[..encoding.len() - 5]));
+        block.transaction.created_at_time.timestamp_nanos += 1;
+        assert!(block.encode().ends_with(&block.transaction.encode()));
+    }
+}
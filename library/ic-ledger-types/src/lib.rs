@@ -20,6 +20,13 @@ use sha2::Digest;
 
 use ic_cdk::api::call::CallResult;
 
+mod block_stream;
+mod encoding;
+mod payment;
+
+pub use block_stream::*;
+pub use payment::*;
+
 /// The subaccount that is used by default.
 pub const DEFAULT_SUBACCOUNT: Subaccount = Subaccount([0; 32]);
 
@@ -457,6 +464,8 @@ pub enum Operation {
     Burn {
         /// The account that sent the tokens to be burned.
         from: AccountIdentifier,
+        /// The account that burned the tokens on behalf of `from`, following an approval.
+        spender: Option<AccountIdentifier>,
         /// The amount that was burned.
         amount: Tokens,
     },
@@ -470,6 +479,8 @@ pub enum Operation {
         amount: Tokens,
         /// The fee that was charged for the transfer.
         fee: Tokens,
+        /// The account that performed the transfer on behalf of `from`, following an approval.
+        spender: Option<AccountIdentifier>,
     },
     /// An account approved another account to transfer tokens on its behalf.
     Approve {
@@ -477,7 +488,10 @@ pub enum Operation {
         from: AccountIdentifier,
         /// The account that was enabled to spend them.
         spender: AccountIdentifier,
-        // TODO: add the allowance_e8s field after the official ICRC-2 release.
+        /// The amount that the spender can transfer.
+        allowance: Tokens,
+        /// The allowance that the approval expected to replace.
+        expected_allowance: Option<Tokens>,
         /// The expiration date for this approval.
         expires_at: Option<Timestamp>,
         /// The fee that was charged for the approval.
@@ -0,0 +1,434 @@
+use std::fmt::{self, Display, Formatter};
+
+use candid::Principal;
+use serde_bytes::ByteBuf;
+
+use crate::{
+    AccountIdentifier, Block, BlockIndex, BlockStream, BlockStreamError, Memo, Operation, Tokens,
+};
+
+/// A transfer that a canister expects to receive, e.g. the payment of an invoice.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedTransfer {
+    /// The account the tokens are expected from.
+    pub from: AccountIdentifier,
+    /// The account the tokens are expected to, usually a subaccount of the canister.
+    pub to: AccountIdentifier,
+    /// The amount of tokens expected, not including the fee.
+    pub amount: Tokens,
+    /// The memo expected, which identifies the payment.
+    ///
+    /// The ledger records the transfers of `icrc1_transfer` with a memo of 0.
+    pub memo: Memo,
+    /// The memo of `icrc1_transfer` expected, if any.
+    ///
+    /// If `None`, the ICRC-1 memo of the transfer is not checked.
+    pub icrc1_memo: Option<ByteBuf>,
+}
+
+impl ExpectedTransfer {
+    /// Checks that `block` holds the expected transfer.
+    ///
+    /// Both a transfer and a transfer on behalf of `from`, following an approval, are payments
+    /// from `from`.
+    pub fn verify_block(&self, block: Block) -> PaymentVerification {
+        let (from, to, amount) = match &block.transaction.operation {
+            Some(Operation::Transfer {
+                from, to, amount, ..
+            })
+            | Some(Operation::TransferFrom {
+                from, to, amount, ..
+            }) => (*from, *to, *amount),
+            _ => {
+                return PaymentVerification::Mismatch {
+                    block,
+                    mismatches: vec![PaymentMismatch::NotATransfer],
+                }
+            }
+        };
+        let mut mismatches = vec![];
+        if from != self.from {
+            mismatches.push(PaymentMismatch::From {
+                expected: self.from,
+                found: from,
+            });
+        }
+        if to != self.to {
+            mismatches.push(PaymentMismatch::To {
+                expected: self.to,
+                found: to,
+            });
+        }
+        if amount != self.amount {
+            mismatches.push(PaymentMismatch::Amount {
+                expected: self.amount,
+                found: amount,
+            });
+        }
+        if block.transaction.memo != self.memo {
+            mismatches.push(PaymentMismatch::Memo {
+                expected: self.memo,
+                found: block.transaction.memo,
+            });
+        }
+        if let Some(expected) = &self.icrc1_memo {
+            if block.transaction.icrc1_memo.as_ref() != Some(expected) {
+                mismatches.push(PaymentMismatch::Icrc1Memo {
+                    expected: expected.clone(),
+                    found: block.transaction.icrc1_memo.clone(),
+                });
+            }
+        }
+        if mismatches.is_empty() {
+            PaymentVerification::Verified { block }
+        } else {
+            PaymentVerification::Mismatch { block, mismatches }
+        }
+    }
+}
+
+/// The result of the verification of a payment, see [`verify_payment`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaymentVerification {
+    /// The block holds the expected transfer.
+    Verified {
+        /// The block of the transfer.
+        block: Block,
+    },
+    /// The block does not hold the expected transfer.
+    Mismatch {
+        /// The block at the index.
+        block: Block,
+        /// The differences with the expected transfer.
+        mismatches: Vec<PaymentMismatch>,
+    },
+    /// There is no block at the index yet.
+    NotFound,
+}
+
+impl PaymentVerification {
+    /// Returns true if the block holds the expected transfer.
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Self::Verified { .. })
+    }
+}
+
+/// A difference between a block and an [`ExpectedTransfer`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaymentMismatch {
+    /// The block is not a transfer, e.g. it is a mint, a burn or an approval.
+    NotATransfer,
+    /// The tokens were transferred from another account.
+    From {
+        /// The account of the expected transfer.
+        expected: AccountIdentifier,
+        /// The account of the block.
+        found: AccountIdentifier,
+    },
+    /// The tokens were transferred to another account.
+    To {
+        /// The account of the expected transfer.
+        expected: AccountIdentifier,
+        /// The account of the block.
+        found: AccountIdentifier,
+    },
+    /// Another amount of tokens was transferred.
+    Amount {
+        /// The amount of the expected transfer.
+        expected: Tokens,
+        /// The amount of the block.
+        found: Tokens,
+    },
+    /// The transfer has another memo.
+    Memo {
+        /// The memo of the expected transfer.
+        expected: Memo,
+        /// The memo of the block.
+        found: Memo,
+    },
+    /// The transfer has another ICRC-1 memo, or none.
+    Icrc1Memo {
+        /// The ICRC-1 memo of the expected transfer.
+        expected: ByteBuf,
+        /// The ICRC-1 memo of the block.
+        found: Option<ByteBuf>,
+    },
+}
+
+impl Display for PaymentMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotATransfer => write!(f, "the block is not a transfer"),
+            Self::From { expected, found } => write!(
+                f,
+                "expected a transfer from {} but found {}",
+                expected, found
+            ),
+            Self::To { expected, found } => {
+                write!(f, "expected a transfer to {} but found {}", expected, found)
+            }
+            Self::Amount { expected, found } => {
+                write!(f, "expected a transfer of {} but found {}", expected, found)
+            }
+            Self::Memo { expected, found } => write!(
+                f,
+                "expected a transfer with memo {} but found {}",
+                expected.0, found.0
+            ),
+            Self::Icrc1Memo { expected, found } => write!(
+                f,
+                "expected a transfer with ICRC-1 memo {} but found {}",
+                hex::encode(expected),
+                found.as_ref().map_or("none".to_string(), hex::encode)
+            ),
+        }
+    }
+}
+
+/// An error of [`verify_payment`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyPaymentError {
+    /// The block index is `u64::MAX`, which no block can have.
+    InvalidBlockIndex(BlockIndex),
+    /// The block could not be fetched.
+    BlockStream(BlockStreamError),
+}
+
+impl From<BlockStreamError> for VerifyPaymentError {
+    fn from(err: BlockStreamError) -> Self {
+        Self::BlockStream(err)
+    }
+}
+
+impl Display for VerifyPaymentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidBlockIndex(index) => write!(f, "invalid block index {}", index),
+            Self::BlockStream(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Fetches the block at `block_index` from the ledger or its archives, and checks that it holds
+/// the expected transfer.
+///
+/// A block holds a single transfer, so a payment is identified by the index of its block. To not
+/// accept the same payment twice, e.g. when a caller reports the same block index for two
+/// orders, record the indices of the verified blocks, and reject those already recorded. The
+/// memo, e.g. the number of an invoice, tells which order a payment is for.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::cell::RefCell;
+/// use std::collections::BTreeSet;
+/// use ic_ledger_types::{
+///   verify_payment, AccountIdentifier, BlockIndex, ExpectedTransfer, Memo, Tokens,
+///   MAINNET_LEDGER_CANISTER_ID,
+/// };
+///
+/// thread_local! {
+///   static PAID_BLOCKS: RefCell<BTreeSet<BlockIndex>> = RefCell::default();
+/// }
+///
+/// async fn confirm_payment(
+///   block_index: BlockIndex,
+///   from: AccountIdentifier,
+///   to: AccountIdentifier,
+///   invoice: u64,
+/// ) -> Result<(), String> {
+///   if PAID_BLOCKS.with(|paid| paid.borrow().contains(&block_index)) {
+///     return Err(format!("block {} already paid an invoice", block_index));
+///   }
+///   let expected = ExpectedTransfer {
+///     from,
+///     to,
+///     amount: Tokens::from_e8s(100_000_000),
+///     memo: Memo(invoice),
+///     icrc1_memo: None,
+///   };
+///   let verification = verify_payment(MAINNET_LEDGER_CANISTER_ID, block_index, &expected)
+///     .await
+///     .map_err(|err| err.to_string())?;
+///   if !verification.is_verified() {
+///     return Err(format!("block {} does not pay invoice {}", block_index, invoice));
+///   }
+///   // Another call may have recorded the block while this one awaited the ledger.
+///   if !PAID_BLOCKS.with(|paid| paid.borrow_mut().insert(block_index)) {
+///     return Err(format!("block {} already paid an invoice", block_index));
+///   }
+///   Ok(())
+/// }
+/// ```
+pub async fn verify_payment(
+    ledger_canister_id: Principal,
+    block_index: BlockIndex,
+    expected: &ExpectedTransfer,
+) -> Result<PaymentVerification, VerifyPaymentError> {
+    let end = block_index
+        .checked_add(1)
+        .ok_or(VerifyPaymentError::InvalidBlockIndex(block_index))?;
+    let mut blocks = BlockStream::new(ledger_canister_id, block_index..end);
+    match blocks.next().await {
+        Some(Ok((_, block))) => Ok(expected.verify_block(block)),
+        Some(Err(err)) => Err(err.into()),
+        None => Ok(PaymentVerification::NotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Timestamp, Transaction};
+
+    fn account(n: u8) -> AccountIdentifier {
+        AccountIdentifier::try_from([n; 28]).unwrap()
+    }
+
+    fn expected() -> ExpectedTransfer {
+        ExpectedTransfer {
+            from: account(1),
+            to: account(2),
+            amount: Tokens::from_e8s(500),
+            memo: Memo(42),
+            icrc1_memo: None,
+        }
+    }
+
+    fn block(operation: Operation, memo: Memo) -> Block {
+        Block {
+            parent_hash: None,
+            transaction: Transaction {
+                memo,
+                operation: Some(operation),
+                created_at_time: Timestamp { timestamp_nanos: 0 },
+                icrc1_memo: None,
+            },
+            timestamp: Timestamp { timestamp_nanos: 0 },
+        }
+    }
+
+    fn transfer(from: u8, to: u8, e8s: u64) -> Operation {
+        Operation::Transfer {
+            from: account(from),
+            to: account(to),
+            amount: Tokens::from_e8s(e8s),
+            fee: Tokens::from_e8s(10_000),
+            spender: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_block() {
+        let paid = block(transfer(1, 2, 500), Memo(42));
+        assert_eq!(
+            expected().verify_block(paid.clone()),
+            PaymentVerification::Verified { block: paid }
+        );
+        let paid_by_spender = block(
+            Operation::TransferFrom {
+                from: account(1),
+                to: account(2),
+                spender: account(3),
+                amount: Tokens::from_e8s(500),
+                fee: Tokens::from_e8s(10_000),
+            },
+            Memo(42),
+        );
+        assert!(expected().verify_block(paid_by_spender).is_verified());
+
+        let other = block(transfer(3, 4, 400), Memo(43));
+        assert_eq!(
+            expected().verify_block(other.clone()),
+            PaymentVerification::Mismatch {
+                block: other,
+                mismatches: vec![
+                    PaymentMismatch::From {
+                        expected: account(1),
+                        found: account(3),
+                    },
+                    PaymentMismatch::To {
+                        expected: account(2),
+                        found: account(4),
+                    },
+                    PaymentMismatch::Amount {
+                        expected: Tokens::from_e8s(500),
+                        found: Tokens::from_e8s(400),
+                    },
+                    PaymentMismatch::Memo {
+                        expected: Memo(42),
+                        found: Memo(43),
+                    },
+                ],
+            }
+        );
+
+        let mint = block(
+            Operation::Mint {
+                to: account(2),
+                amount: Tokens::from_e8s(500),
+            },
+            Memo(42),
+        );
+        assert_eq!(
+            expected().verify_block(mint.clone()),
+            PaymentVerification::Mismatch {
+                block: mint,
+                mismatches: vec![PaymentMismatch::NotATransfer],
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_icrc1_memo() {
+        let mut paid = block(transfer(1, 2, 500), Memo(0));
+        paid.transaction.icrc1_memo = Some(ByteBuf::from(b"invoice 42".to_vec()));
+        let expected = ExpectedTransfer {
+            memo: Memo(0),
+            icrc1_memo: Some(ByteBuf::from(b"invoice 42".to_vec())),
+            ..expected()
+        };
+        assert!(expected.verify_block(paid.clone()).is_verified());
+        assert!(ExpectedTransfer {
+            icrc1_memo: None,
+            ..expected.clone()
+        }
+        .verify_block(paid.clone())
+        .is_verified());
+
+        let other = ByteBuf::from(b"invoice 43".to_vec());
+        assert_eq!(
+            ExpectedTransfer {
+                icrc1_memo: Some(other.clone()),
+                ..expected.clone()
+            }
+            .verify_block(paid.clone()),
+            PaymentVerification::Mismatch {
+                block: paid.clone(),
+                mismatches: vec![PaymentMismatch::Icrc1Memo {
+                    expected: other,
+                    found: paid.transaction.icrc1_memo.clone(),
+                }],
+            }
+        );
+        paid.transaction.icrc1_memo = None;
+        assert!(matches!(
+            expected.verify_block(paid),
+            PaymentVerification::Mismatch { mismatches, .. }
+                if mismatches == [PaymentMismatch::Icrc1Memo {
+                    expected: ByteBuf::from(b"invoice 42".to_vec()),
+                    found: None,
+                }]
+        ));
+    }
+
+    #[test]
+    fn test_verify_payment_at_last_index() {
+        let result = futures::executor::block_on(verify_payment(
+            Principal::management_canister(),
+            u64::MAX,
+            &expected(),
+        ));
+        assert_eq!(result, Err(VerifyPaymentError::InvalidBlockIndex(u64::MAX)));
+    }
+}
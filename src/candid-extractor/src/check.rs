@@ -0,0 +1,143 @@
+use anyhow::{anyhow, Result};
+use candid::types::subtype::subtype;
+use candid::types::{Type, TypeEnv, TypeInner};
+use candid_parser::utils::CandidSource;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+/// Differences between the Candid interface of a canister and the `.did` file it is checked against.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// Changes that break clients of the `.did` file, one per method.
+    pub breaking: Vec<String>,
+    /// Methods of the canister that are not in the `.did` file.
+    pub added: Vec<String>,
+}
+
+impl CompatibilityReport {
+    /// Whether the canister interface is a subtype of the `.did` file,
+    /// i.e. upgrading to it is backward compatible.
+    pub fn is_compatible(&self) -> bool {
+        self.breaking.is_empty()
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_compatible() {
+            writeln!(f, "The Candid interface is compatible.")?;
+        } else {
+            writeln!(f, "The Candid interface has breaking changes:")?;
+            for change in &self.breaking {
+                writeln!(f, "  - {change}")?;
+            }
+        }
+        if !self.added.is_empty() {
+            writeln!(f, "Methods not in the .did file:")?;
+            for method in &self.added {
+                writeln!(f, "  + {method}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the Candid interface from a Canister WASM file and checks it against a `.did` file.
+pub fn check_wasm<P, Q>(wasm_path: P, did_path: Q) -> Result<CompatibilityReport>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let candid = crate::extract_candid(wasm_path)?;
+    check_candid(&candid, did_path)
+}
+
+/// Checks that the Candid interface `candid` is a subtype of the service in a `.did` file.
+///
+/// Every method of the `.did` file must exist in `candid` with a subtype of its function type.
+pub fn check_candid<P>(candid: &str, did_path: P) -> Result<CompatibilityReport>
+where
+    P: AsRef<Path>,
+{
+    let (mut env, new) = CandidSource::Text(candid).load()?;
+    let new = new.ok_or_else(|| anyhow!("the extracted Candid interface has no main service"))?;
+    let (did_env, old) = CandidSource::File(did_path.as_ref()).load()?;
+    let old = old.ok_or_else(|| anyhow!("the .did file has no main service"))?;
+    let old = env.merge_type(did_env, old);
+    compare_services(&env, &new, &old)
+}
+
+fn compare_services(env: &TypeEnv, new: &Type, old: &Type) -> Result<CompatibilityReport> {
+    let new_methods = env.as_service(new)?;
+    let old_methods = env.as_service(old)?;
+    let mut report = CompatibilityReport::default();
+    for (name, old_ty) in old_methods {
+        match new_methods.iter().find(|(n, _)| n == name) {
+            None => report
+                .breaking
+                .push(format!("method `{name}` is missing, expected {old_ty}")),
+            Some((_, new_ty)) => {
+                let mut gamma = HashSet::new();
+                if let Err(e) = subtype(&mut gamma, env, new_ty, old_ty) {
+                    report.breaking.push(format!(
+                        "method `{name}` changed from {} to {}: {e}",
+                        pp_func(env, old_ty),
+                        pp_func(env, new_ty),
+                    ));
+                }
+            }
+        }
+    }
+    for (name, new_ty) in new_methods {
+        if !old_methods.iter().any(|(n, _)| n == name) {
+            report
+                .added
+                .push(format!("{name} : {}", pp_func(env, new_ty)));
+        }
+    }
+    Ok(report)
+}
+
+// Methods referring to a named function type are shown with its definition.
+fn pp_func(env: &TypeEnv, ty: &Type) -> String {
+    match env.as_func(ty) {
+        Ok(func) => Type::from(TypeInner::Func(func.clone())).to_string(),
+        Err(_) => ty.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(new: &str, old: &str) -> CompatibilityReport {
+        let (mut env, new) = CandidSource::Text(new).load().unwrap();
+        let (old_env, old) = CandidSource::Text(old).load().unwrap();
+        let old = env.merge_type(old_env, old.unwrap());
+        compare_services(&env, &new.unwrap(), &old).unwrap()
+    }
+
+    #[test]
+    fn compatible() {
+        let report = check(
+            "service : { get : () -> (nat) query; set : (opt nat) -> (); reset : () -> () }",
+            "service : { get : () -> (nat) query; set : (nat) -> () }",
+        );
+        assert!(report.is_compatible());
+        assert_eq!(report.added.len(), 1);
+        assert!(report.added[0].starts_with("reset : "));
+    }
+
+    #[test]
+    fn breaking() {
+        let report = check(
+            "type T = record { a : nat }; service : { get : () -> (text) query; put : (T) -> () }",
+            "type T = record { a : nat; b : nat }; service : { get : () -> (nat) query; put : (T) -> (); del : () -> () }",
+        );
+        assert!(!report.is_compatible());
+        assert_eq!(report.breaking.len(), 2);
+        assert!(report.breaking[0].starts_with("method `del` is missing"));
+        assert!(report.breaking[1].starts_with("method `get` changed"));
+    }
+}
@@ -1,38 +1,262 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use std::path::Path;
 use wasmtime::*;
 
 static IC0: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/ic_mock.wat"));
 
-pub(crate) fn extract_candid<P>(wasm_path: P) -> Result<String>
+/// Resource limits for running a Canister WASM during the extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Fuel available to instantiate the module and call `get_candid_pointer`,
+    /// roughly one unit per executed WASM instruction.
+    pub fuel: u64,
+    /// Maximum size in bytes of each WASM memory.
+    pub memory: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000_000,
+            memory: 1 << 30,
+        }
+    }
+}
+
+/// Extracts the Candid interface exported by `export_candid!` from a Canister WASM file.
+pub fn extract_candid<P>(wasm_path: P) -> Result<String>
 where
     P: AsRef<Path>,
 {
-    let mut store: Store<()> = Store::<()>::default();
+    extract_candid_with_limits(wasm_path, Limits::default())
+}
+
+/// Same as [`extract_candid`], with custom resource limits.
+///
+/// Canisters can only import the `ic0` system API. Its functions that the extractor doesn't provide
+/// are stubbed: the module links, and calling one of them fails with an error naming the import.
+pub fn extract_candid_with_limits<P>(wasm_path: P, limits: Limits) -> Result<String>
+where
+    P: AsRef<Path>,
+{
+    let wasm_path = wasm_path.as_ref();
+    let wasm = std::fs::read(wasm_path)
+        .with_context(|| format!("failed to read `{}`", wasm_path.display()))?;
+    extract(&wasm, limits)
+}
 
-    let mut linker = Linker::new(store.engine());
-    let ic0_module = Module::new(store.engine(), IC0)?;
+fn extract(wasm: &[u8], limits: Limits) -> Result<String> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let store_limits = StoreLimitsBuilder::new()
+        .memory_size(limits.memory)
+        .trap_on_grow_failure(true)
+        .build();
+    let mut store = Store::new(&engine, store_limits);
+    store.limiter(|store_limits| store_limits);
+    store.set_fuel(limits.fuel)?;
+
+    let mut linker = Linker::new(&engine);
+    let ic0_module = Module::new(&engine, IC0)?;
     let ic0 = linker.instantiate(&mut store, &ic0_module)?;
     linker.instance(&mut store, "ic0", ic0)?;
 
-    let module = Module::from_file(store.engine(), wasm_path)?;
-    let canister = linker.instantiate(&mut store, &module)?;
+    let module = Module::new(&engine, wasm).context("failed to compile the Canister WASM")?;
+    link_imports(&mut linker, &mut store, &module)?;
+    let canister = linker
+        .instantiate(&mut store, &module)
+        .map_err(|e| diagnose(e, limits))
+        .context("failed to instantiate the Canister WASM")?;
 
-    let get_candid_pointer =
-        canister.get_typed_func::<(), i32>(&mut store, "get_candid_pointer")?;
-    let candid_pointer = get_candid_pointer.call(&mut store, ())?;
+    let get_candid_pointer = canister
+        .get_typed_func::<(), i32>(&mut store, "get_candid_pointer")
+        .context("the Canister WASM has no `get_candid_pointer` export, did it call `ic_cdk::export_candid!()`?")?;
+    let candid_pointer = get_candid_pointer
+        .call(&mut store, ())
+        .map_err(|e| diagnose(e, limits))
+        .context("failed to call `get_candid_pointer`")?;
 
     let memory = canister
         .get_memory(&mut store, "memory")
-        .ok_or_else(|| anyhow::format_err!("failed to find `memory` export"))?;
+        .ok_or_else(|| anyhow!("failed to find `memory` export"))?;
     let memory_buffer = memory.data(&store);
+    let candid = memory_buffer
+        .get(candid_pointer as u32 as usize..)
+        .ok_or_else(|| {
+            anyhow!("`get_candid_pointer` returned {candid_pointer:#x}, out of the WASM memory")
+        })?;
+    let len = candid
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| anyhow!("the Candid interface is not NUL-terminated"))?;
+    String::from_utf8(candid[..len].to_vec()).context("the Candid interface is not valid UTF-8")
+}
+
+// Checks the function imports against the `ic0` functions of `ic_mock.wat`, and defines the ones
+// missing from it, e.g. a system API newer than `ic_mock.wat`, as functions failing with the name
+// of the import when called.
+fn link_imports(
+    linker: &mut Linker<StoreLimits>,
+    store: &mut Store<StoreLimits>,
+    module: &Module,
+) -> Result<()> {
+    for import in module.imports() {
+        let ExternType::Func(ty) = import.ty() else {
+            continue;
+        };
+        let name = format!("{}.{}", import.module(), import.name());
+        if import.module() != "ic0" {
+            bail!("the Canister WASM imports `{name}`, but canisters can only import `ic0`");
+        }
+        match linker.get(&mut *store, "ic0", import.name()) {
+            Some(ic0) => {
+                if let ExternType::Func(expected) = ic0.ty(&*store) {
+                    let (found, expected) = (signature(&ty), signature(&expected));
+                    if found != expected {
+                        bail!("the Canister WASM imports `{name}` as `{found}`, but the system API defines it as `{expected}`");
+                    }
+                }
+            }
+            None => {
+                linker.func_new("ic0", import.name(), ty, move |_, _, _| {
+                    Err(anyhow!(
+                        "the canister called `{name}`, which is not available during the Candid extraction"
+                    ))
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// The type of a function in the text format, e.g. `(func (param i32 i32) (result i64))`.
+fn signature(ty: &FuncType) -> String {
+    let mut signature = "(func".to_string();
+    let params: Vec<_> = ty.params().map(|ty| ty.to_string()).collect();
+    if !params.is_empty() {
+        signature.push_str(&format!(" (param {})", params.join(" ")));
+    }
+    let results: Vec<_> = ty.results().map(|ty| ty.to_string()).collect();
+    if !results.is_empty() {
+        signature.push_str(&format!(" (result {})", results.join(" ")));
+    }
+    signature.push(')');
+    signature
+}
+
+fn diagnose(e: anyhow::Error, limits: Limits) -> anyhow::Error {
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => e.context(format!(
+            "the Canister WASM ran out of fuel after {} units, it may loop forever",
+            limits.fuel
+        )),
+        _ => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICE: &str = r#"(data (i32.const 16) "service : {}\00")"#;
+
+    fn module(body: &str) -> String {
+        format!(
+            r#"(module (import "ic0" "msg_reply" (func)) (memory (export "memory") 1) {SERVICE} {body})"#
+        )
+    }
+
+    fn extract_err(wat: &str, limits: Limits) -> String {
+        format!("{:?}", extract(wat.as_bytes(), limits).unwrap_err())
+    }
+
+    #[test]
+    fn extract_service() {
+        let wat = module(r#"(func (export "get_candid_pointer") (result i32) i32.const 16)"#);
+        assert_eq!(
+            extract(wat.as_bytes(), Limits::default()).unwrap(),
+            "service : {}"
+        );
+    }
+
+    #[test]
+    fn unknown_import() {
+        let wat = format!(
+            r#"(module (import "ic0" "future_api" (func $f (param i64))) (memory (export "memory") 1) {SERVICE} {})"#,
+            r#"(func (export "get_candid_pointer") (result i32) i32.const 16)
+               (func (export "canister_update f") i64.const 0 call $f)"#
+        );
+        // Linking succeeds while the import isn't called.
+        assert_eq!(
+            extract(wat.as_bytes(), Limits::default()).unwrap(),
+            "service : {}"
+        );
+
+        let wat = format!(
+            r#"(module (import "ic0" "future_api" (func $f (param i64))) (memory (export "memory") 1) {SERVICE} {})"#,
+            r#"(func (export "get_candid_pointer") (result i32) i64.const 0 call $f i32.const 16)"#
+        );
+        let err = extract_err(&wat, Limits::default());
+        assert!(
+            err.contains("the canister called `ic0.future_api`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn import_outside_ic0() {
+        let wat = r#"(module (import "env" "f" (func)) (memory (export "memory") 1))"#;
+        let err = extract_err(wat, Limits::default());
+        assert!(
+            err.contains("imports `env.f`, but canisters can only import `ic0`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn mismatched_import() {
+        let wat = format!(
+            r#"(module (import "ic0" "msg_cycles_accept" (func (param i32) (result i32))) (memory (export "memory") 1) {SERVICE} {})"#,
+            r#"(func (export "get_candid_pointer") (result i32) i32.const 16)"#
+        );
+        let err = extract_err(&wat, Limits::default());
+        assert!(
+            err.contains("imports `ic0.msg_cycles_accept` as `(func (param i32) (result i32))`, but the system API defines it as `(func (param i64) (result i64))`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn out_of_fuel() {
+        let wat = module(
+            r#"(func (export "get_candid_pointer") (result i32) (loop $l (br $l)) i32.const 16)"#,
+        );
+        let limits = Limits {
+            fuel: 1_000_000,
+            ..Limits::default()
+        };
+        let err = extract_err(&wat, limits);
+        assert!(err.contains("ran out of fuel after 1000000 units"), "{err}");
+    }
+
+    #[test]
+    fn memory_limit() {
+        let wat = module(
+            r#"(func (export "get_candid_pointer") (result i32) (drop (memory.grow (i32.const 100))) i32.const 16)"#,
+        );
+        let limits = Limits {
+            memory: 1 << 20,
+            ..Limits::default()
+        };
+        assert!(extract(wat.as_bytes(), limits).is_err());
+        assert!(extract(wat.as_bytes(), Limits::default()).is_ok());
+    }
 
-    let mut i = candid_pointer as usize;
-    let mut str_vec = vec![];
-    while memory_buffer[i] != 0 {
-        str_vec.push(memory_buffer[i]);
-        i += 1;
+    #[test]
+    fn invalid_pointer() {
+        let wat = module(r#"(func (export "get_candid_pointer") (result i32) i32.const -1)"#);
+        let err = extract_err(&wat, Limits::default());
+        assert!(err.contains("out of the WASM memory"), "{err}");
     }
-    let s = String::from_utf8(str_vec)?;
-    Ok(s)
 }
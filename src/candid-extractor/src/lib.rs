@@ -0,0 +1,12 @@
+//! Extract the Candid interface from a Canister WASM file, check it against a `.did` file, and
+//! read or write the canister metadata stored in the WASM custom sections.
+
+mod check;
+mod extract;
+mod metadata;
+
+pub use check::{check_candid, check_wasm, CompatibilityReport};
+pub use extract::{extract_candid, extract_candid_with_limits, Limits};
+pub use metadata::{
+    list_metadata, read_metadata, write_metadata, Metadata, Visibility, CANDID_SERVICE,
+};
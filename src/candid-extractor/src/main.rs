@@ -1,20 +1,127 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{anyhow, Result};
+use candid_extractor::{Limits, Visibility, CANDID_SERVICE};
+use clap::{ArgGroup, Parser, Subcommand};
 use std::path::PathBuf;
 
-mod extract;
-
 /// Extract the Candid interface from a Canister WASM file.
 #[derive(Parser)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+#[command(group(ArgGroup::new("embedded_wasm").args(["wasm_output", "in_place"])))]
 struct Cli {
     /// Path to the Canister WASM file.
-    path: PathBuf,
+    #[arg(required = true)]
+    path: Option<PathBuf>,
+    /// Write the Candid interface to this file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Read the Candid interface from the `candid:service` metadata instead of running the WASM.
+    #[arg(long, conflicts_with = "embed")]
+    from_metadata: bool,
+    /// Embed the Candid interface into the WASM as `candid:service` metadata.
+    ///
+    /// Requires either `--wasm-output` or `--in-place`.
+    #[arg(long, requires = "embedded_wasm")]
+    embed: bool,
+    /// Visibility of the embedded metadata.
+    #[arg(long, default_value_t = Visibility::Public, requires = "embed")]
+    visibility: Visibility,
+    /// Write the WASM with the embedded metadata to this file.
+    #[arg(long, requires = "embed")]
+    wasm_output: Option<PathBuf>,
+    /// Overwrite the input WASM with the embedded metadata.
+    #[arg(long, requires = "embed")]
+    in_place: bool,
+    /// Fuel available to run the WASM, roughly one unit per executed instruction.
+    #[arg(long, default_value_t = Limits::default().fuel)]
+    fuel: u64,
+    /// Maximum size in bytes of the WASM memory.
+    #[arg(long, default_value_t = Limits::default().memory)]
+    memory_limit: usize,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check that the Candid interface of a Canister WASM file is a subtype of a .did file.
+    ///
+    /// Exits with a non-zero code if there are breaking changes.
+    Check {
+        /// Path to the Canister WASM file.
+        wasm: PathBuf,
+        /// Path to the .did file to check against.
+        did: PathBuf,
+    },
+    /// List the metadata of a Canister WASM file, or print the content of one entry.
+    Metadata {
+        /// Path to the Canister WASM file.
+        wasm: PathBuf,
+        /// Name of the metadata to print, e.g. `candid:service`.
+        name: Option<String>,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let candid = extract::extract_candid(cli.path)?;
-    println!("{candid}");
+    match (cli.command, cli.path) {
+        (Some(Command::Check { wasm, did }), _) => {
+            let report = candid_extractor::check_wasm(wasm, did)?;
+            print!("{report}");
+            if !report.is_compatible() {
+                std::process::exit(1);
+            }
+        }
+        (Some(Command::Metadata { wasm, name }), _) => {
+            let wasm = std::fs::read(wasm)?;
+            match name {
+                Some(name) => {
+                    let metadata = candid_extractor::read_metadata(&wasm, &name)?
+                        .ok_or_else(|| anyhow!("no metadata named `{name}`"))?;
+                    println!("{}", String::from_utf8_lossy(&metadata.content));
+                }
+                None => {
+                    for metadata in candid_extractor::list_metadata(&wasm)? {
+                        println!(
+                            "{} ({}, {} bytes)",
+                            metadata.name,
+                            metadata.visibility,
+                            metadata.content.len()
+                        );
+                    }
+                }
+            }
+        }
+        (None, Some(path)) => {
+            let candid = if cli.from_metadata {
+                let wasm = std::fs::read(&path)?;
+                let metadata = candid_extractor::read_metadata(&wasm, CANDID_SERVICE)?
+                    .ok_or_else(|| anyhow!("no `{CANDID_SERVICE}` metadata in the WASM"))?;
+                String::from_utf8(metadata.content)?
+            } else {
+                let limits = Limits {
+                    fuel: cli.fuel,
+                    memory: cli.memory_limit,
+                };
+                candid_extractor::extract_candid_with_limits(&path, limits)?
+            };
+            if cli.embed {
+                let wasm = std::fs::read(&path)?;
+                let wasm = candid_extractor::write_metadata(
+                    &wasm,
+                    CANDID_SERVICE,
+                    cli.visibility,
+                    candid.as_bytes(),
+                )?;
+                // Without `--wasm-output`, `--in-place` is set.
+                std::fs::write(cli.wasm_output.unwrap_or(path), wasm)?;
+            }
+            match cli.output {
+                Some(output) => std::fs::write(output, format!("{candid}\n"))?,
+                None if cli.embed => {}
+                None => println!("{candid}"),
+            }
+        }
+        (None, None) => unreachable!("clap requires either a path or a subcommand"),
+    }
     Ok(())
 }
@@ -0,0 +1,260 @@
+use anyhow::{bail, Result};
+use std::fmt;
+use std::str::FromStr;
+
+const WASM_HEADER: [u8; 8] = *b"\0asm\x01\0\0\0";
+const CUSTOM_SECTION_ID: u8 = 0;
+
+/// Name of the metadata holding the Candid interface of a canister.
+pub const CANDID_SERVICE: &str = "candid:service";
+
+/// Visibility of canister metadata, encoded in the name of its WASM custom section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    /// `icp:public <name>`: readable by anyone.
+    Public,
+    /// `icp:private <name>`: readable by the controllers only.
+    Private,
+}
+
+impl Visibility {
+    fn prefix(self) -> &'static str {
+        match self {
+            Visibility::Public => "icp:public ",
+            Visibility::Private => "icp:private ",
+        }
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Visibility::Public => "public",
+            Visibility::Private => "private",
+        })
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "public" => Ok(Visibility::Public),
+            "private" => Ok(Visibility::Private),
+            _ => bail!("invalid visibility `{s}`, expected `public` or `private`"),
+        }
+    }
+}
+
+/// A canister metadata entry, stored in a WASM custom section named `icp:public <name>` or `icp:private <name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// Name of the metadata, e.g. `candid:service`.
+    pub name: String,
+    /// Visibility of the metadata.
+    pub visibility: Visibility,
+    /// Content of the metadata.
+    pub content: Vec<u8>,
+}
+
+struct Section<'a> {
+    id: u8,
+    // The whole section, including id and size.
+    range: std::ops::Range<usize>,
+    payload: &'a [u8],
+}
+
+fn read_leb128(bytes: &[u8], pos: &mut usize) -> Result<u32> {
+    let mut result = 0u64;
+    for shift in (0..35).step_by(7) {
+        let Some(&byte) = bytes.get(*pos) else {
+            bail!("unexpected end of WASM module");
+        };
+        *pos += 1;
+        result |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return u32::try_from(result).map_err(|_| anyhow::anyhow!("invalid LEB128 integer"));
+        }
+    }
+    bail!("invalid LEB128 integer")
+}
+
+fn write_leb128(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn sections(wasm: &[u8]) -> Result<Vec<Section<'_>>> {
+    if !wasm.starts_with(&WASM_HEADER) {
+        bail!("not a WASM module");
+    }
+    let mut sections = vec![];
+    let mut pos = WASM_HEADER.len();
+    while pos < wasm.len() {
+        let start = pos;
+        let id = wasm[pos];
+        pos += 1;
+        let size = read_leb128(wasm, &mut pos)? as usize;
+        let Some(payload) = wasm.get(pos..pos + size) else {
+            bail!("unexpected end of WASM module");
+        };
+        pos += size;
+        sections.push(Section {
+            id,
+            range: start..pos,
+            payload,
+        });
+    }
+    Ok(sections)
+}
+
+// Splits a custom section payload into its name and content.
+fn custom_section(payload: &[u8]) -> Result<(&str, &[u8])> {
+    let mut pos = 0;
+    let len = read_leb128(payload, &mut pos)? as usize;
+    let Some(name) = payload.get(pos..pos + len) else {
+        bail!("invalid custom section name");
+    };
+    Ok((std::str::from_utf8(name)?, &payload[pos + len..]))
+}
+
+fn parse_metadata(section_name: &str, content: &[u8]) -> Option<Metadata> {
+    [Visibility::Public, Visibility::Private]
+        .into_iter()
+        .find_map(|visibility| {
+            let name = section_name.strip_prefix(visibility.prefix())?;
+            Some(Metadata {
+                name: name.to_string(),
+                visibility,
+                content: content.to_vec(),
+            })
+        })
+}
+
+/// Lists the canister metadata stored in a WASM module.
+pub fn list_metadata(wasm: &[u8]) -> Result<Vec<Metadata>> {
+    let mut metadata = vec![];
+    for section in sections(wasm)? {
+        if section.id == CUSTOM_SECTION_ID {
+            let (name, content) = custom_section(section.payload)?;
+            metadata.extend(parse_metadata(name, content));
+        }
+    }
+    Ok(metadata)
+}
+
+/// Reads the canister metadata `name` from a WASM module, whatever its visibility.
+pub fn read_metadata(wasm: &[u8], name: &str) -> Result<Option<Metadata>> {
+    Ok(list_metadata(wasm)?.into_iter().find(|m| m.name == name))
+}
+
+/// Stores the canister metadata `name` in a WASM module, replacing any existing entry of that name.
+pub fn write_metadata(
+    wasm: &[u8],
+    name: &str,
+    visibility: Visibility,
+    content: &[u8],
+) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(wasm.len() + content.len() + name.len() + 16);
+    out.extend_from_slice(&WASM_HEADER);
+    for section in sections(wasm)? {
+        if section.id == CUSTOM_SECTION_ID {
+            let (section_name, content) = custom_section(section.payload)?;
+            if parse_metadata(section_name, content).is_some_and(|m| m.name == name) {
+                continue;
+            }
+        }
+        out.extend_from_slice(&wasm[section.range]);
+    }
+
+    let section_name = format!("{}{name}", visibility.prefix());
+    let mut payload = vec![];
+    write_leb128(&mut payload, section_name.len() as u32);
+    payload.extend_from_slice(section_name.as_bytes());
+    payload.extend_from_slice(content);
+    out.push(CUSTOM_SECTION_ID);
+    write_leb128(&mut out, payload.len() as u32);
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (module (func (export "f"))) with a `name` custom section.
+    fn module() -> Vec<u8> {
+        let mut wasm = WASM_HEADER.to_vec();
+        // type section: one `() -> ()` function type
+        wasm.extend_from_slice(&[1, 4, 1, 0x60, 0, 0]);
+        // function section
+        wasm.extend_from_slice(&[3, 2, 1, 0]);
+        // export section: "f"
+        wasm.extend_from_slice(&[7, 5, 1, 1, b'f', 0, 0]);
+        // code section
+        wasm.extend_from_slice(&[10, 4, 1, 2, 0, 0x0b]);
+        // custom section "name" with empty content
+        wasm.extend_from_slice(&[0, 5, 4, b'n', b'a', b'm', b'e']);
+        wasm
+    }
+
+    #[test]
+    fn leb128() {
+        for value in [0, 1, 127, 128, 300, 16_384, u32::MAX] {
+            let mut bytes = vec![];
+            write_leb128(&mut bytes, value);
+            let mut pos = 0;
+            assert_eq!(read_leb128(&bytes, &mut pos).unwrap(), value);
+            assert_eq!(pos, bytes.len());
+        }
+    }
+
+    #[test]
+    fn write_and_read() {
+        let wasm = module();
+        assert_eq!(list_metadata(&wasm).unwrap(), vec![]);
+
+        let wasm =
+            write_metadata(&wasm, CANDID_SERVICE, Visibility::Private, b"service : {}").unwrap();
+        let wasm = write_metadata(&wasm, "git_commit_id", Visibility::Public, b"abc").unwrap();
+        assert_eq!(
+            read_metadata(&wasm, CANDID_SERVICE).unwrap(),
+            Some(Metadata {
+                name: CANDID_SERVICE.to_string(),
+                visibility: Visibility::Private,
+                content: b"service : {}".to_vec(),
+            })
+        );
+
+        // Replacing keeps the other sections untouched.
+        let wasm = write_metadata(
+            &wasm,
+            CANDID_SERVICE,
+            Visibility::Public,
+            b"service : { f : () -> () }",
+        )
+        .unwrap();
+        let metadata = list_metadata(&wasm).unwrap();
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata[0].name, "git_commit_id");
+        assert_eq!(metadata[1].visibility, Visibility::Public);
+        assert_eq!(metadata[1].content, b"service : { f : () -> () }");
+        assert!(wasm.starts_with(&module()));
+    }
+
+    #[test]
+    fn invalid_module() {
+        assert!(list_metadata(b"not wasm").is_err());
+        let mut wasm = module();
+        wasm.pop();
+        assert!(list_metadata(&wasm).is_err());
+    }
+}
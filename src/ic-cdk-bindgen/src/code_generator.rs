@@ -3,7 +3,7 @@ use candid::types::{Field, Function, Label, SharedLabel, Type, TypeEnv, TypeInne
 use candid_parser::bindings::analysis::{chase_actor, infer_rec};
 use convert_case::{Case, Casing};
 use pretty::RcDoc;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Clone)]
 pub enum Target {
@@ -19,6 +19,13 @@ pub struct Config {
     canister_id: Option<candid::Principal>,
     service_name: String,
     target: Target,
+    stub_impl: Option<String>,
+    external_types: BTreeMap<String, String>,
+    extra_type_attributes: BTreeMap<String, Vec<String>>,
+    field_names: BTreeMap<String, FieldNames>,
+    client_trait: bool,
+    network_ids: BTreeMap<String, candid::Principal>,
+    network: Option<String>,
 }
 impl Config {
     pub fn new() -> Self {
@@ -28,6 +35,13 @@ impl Config {
             canister_id: None,
             service_name: "service".to_string(),
             target: Target::CanisterCall,
+            stub_impl: None,
+            external_types: BTreeMap::new(),
+            extra_type_attributes: BTreeMap::new(),
+            field_names: BTreeMap::new(),
+            client_trait: false,
+            network_ids: BTreeMap::new(),
+            network: None,
         }
     }
     pub fn set_candid_crate(&mut self, name: String) -> &mut Self {
@@ -53,6 +67,65 @@ impl Config {
         self.target = name;
         self
     }
+    /// Type implementing the service trait generated by `Target::CanisterStub`, e.g. `crate::Canister`,
+    /// which the exported methods delegate to. Required by `Target::CanisterStub`.
+    pub fn set_stub_impl(&mut self, path: String) -> &mut Self {
+        self.stub_impl = Some(path);
+        self
+    }
+    /// Uses an existing Rust type for the Candid type `name` instead of generating it,
+    /// e.g. `icrc_ledger_types::icrc1::account::Account`. The type must have a compatible Candid encoding.
+    pub fn set_external_type(&mut self, name: &str, path: String) -> &mut Self {
+        self.external_types.insert(name.to_string(), path);
+        self
+    }
+    /// Adds attributes to the generated Candid type `name`, after the ones set by `set_type_attributes`,
+    /// e.g. `#[derive(Clone, Debug, PartialEq)]`.
+    /// The type must be generated as a struct or an enum: type aliases, external types, functions
+    /// and services have no definition to apply them to, and are rejected when generating.
+    pub fn add_type_attributes(&mut self, name: &str, attr: String) -> &mut Self {
+        self.extra_type_attributes
+            .entry(name.to_string())
+            .or_default()
+            .push(attr);
+        self
+    }
+    /// Names the Rust field (or enum variant) generated for the field `label` of the record
+    /// (or variant) type `name`. The Candid label is kept with `#[serde(rename)]`.
+    /// The type must be generated as a struct or an enum with the field `label`, otherwise it is
+    /// rejected when generating. The fields of records nested in a type, e.g. the inline record
+    /// of a variant, are not renamed.
+    pub fn set_field_name(&mut self, name: &str, label: &str, rust_name: String) -> &mut Self {
+        self.field_names
+            .entry(name.to_string())
+            .or_default()
+            .insert(label.to_string(), rust_name);
+        self
+    }
+    /// Also generates the service as a trait, e.g. `CounterApi`, implemented by the generated struct
+    /// and by an in-memory mock, e.g. `MockCounter`, whose responses are set by the tests.
+    /// Doesn't apply to `Target::CanisterStub`.
+    pub fn set_client_trait(&mut self, client_trait: bool) -> &mut Self {
+        self.client_trait = client_trait;
+        self
+    }
+    /// Canister id on a network, e.g. `ic` or `local`.
+    /// Generates `canister_id_for_network` returning the id of a network at runtime.
+    pub fn add_network_id(&mut self, network: &str, id: candid::Principal) -> &mut Self {
+        self.network_ids.insert(network.to_string(), id);
+        self
+    }
+    /// Uses the canister id of `network`, set by `add_network_id`, as `CANISTER_ID`.
+    pub fn set_network(&mut self, network: &str) -> &mut Self {
+        self.network = Some(network.to_string());
+        self
+    }
+    pub(crate) fn network(&self) -> Option<&str> {
+        self.network.as_deref()
+    }
+    pub(crate) fn network_id(&self, network: &str) -> Option<candid::Principal> {
+        self.network_ids.get(network).copied()
+    }
 }
 impl Default for Config {
     fn default() -> Self {
@@ -61,6 +134,8 @@ impl Default for Config {
 }
 
 type RecPoints<'a> = BTreeSet<&'a str>;
+// Rust names of the fields of a type, by Candid label.
+type FieldNames = BTreeMap<String, String>;
 // The definition of tuple is language specific.
 pub(crate) fn is_tuple(fs: &[Field]) -> bool {
     if fs.is_empty() {
@@ -135,7 +210,7 @@ fn pp_ty<'a>(ty: &'a Type, recs: &RecPoints) -> RcDoc<'a> {
         // It's a bit tricky to use `deserialize_with = "serde_bytes"`. It's not working for `type t = blob`
         Vec(ref t) if matches!(t.as_ref(), Nat8) => str("serde_bytes::ByteBuf"),
         Vec(ref t) => str("Vec").append(enclose("<", pp_ty(t, recs), ">")),
-        Record(ref fs) => pp_record_fields(fs, recs, ""),
+        Record(ref fs) => pp_record_fields(fs, recs, "", None),
         Variant(_) => unreachable!(), // not possible after rewriting
         Func(_) => unreachable!(),    // not possible after rewriting
         Service(_) => unreachable!(), // not possible after rewriting
@@ -144,7 +219,12 @@ fn pp_ty<'a>(ty: &'a Type, recs: &RecPoints) -> RcDoc<'a> {
     }
 }
 
-fn pp_label<'a>(id: &'a SharedLabel, is_variant: bool, vis: &'a str) -> RcDoc<'a> {
+fn pp_label<'a>(
+    id: &'a SharedLabel,
+    is_variant: bool,
+    vis: &'a str,
+    names: Option<&'a FieldNames>,
+) -> RcDoc<'a> {
     let vis = if vis.is_empty() {
         RcDoc::nil()
     } else {
@@ -153,7 +233,10 @@ fn pp_label<'a>(id: &'a SharedLabel, is_variant: bool, vis: &'a str) -> RcDoc<'a
     match &**id {
         Label::Named(id) => {
             let case = if is_variant { Some(Case::Pascal) } else { None };
-            let (doc, is_rename) = ident_(id, case);
+            let (doc, is_rename) = match names.and_then(|names| names.get(id)) {
+                Some(name) => (RcDoc::text(name.as_str()), name != id),
+                None => ident_(id, case),
+            };
             if is_rename {
                 str("#[serde(rename=\"")
                     .append(id.escape_debug().to_string())
@@ -169,13 +252,23 @@ fn pp_label<'a>(id: &'a SharedLabel, is_variant: bool, vis: &'a str) -> RcDoc<'a
     }
 }
 
-fn pp_record_field<'a>(field: &'a Field, recs: &RecPoints, vis: &'a str) -> RcDoc<'a> {
-    pp_label(&field.id, false, vis)
+fn pp_record_field<'a>(
+    field: &'a Field,
+    recs: &RecPoints,
+    vis: &'a str,
+    names: Option<&'a FieldNames>,
+) -> RcDoc<'a> {
+    pp_label(&field.id, false, vis, names)
         .append(kwd(":"))
         .append(pp_ty(&field.ty, recs))
 }
 
-fn pp_record_fields<'a>(fs: &'a [Field], recs: &RecPoints, vis: &'a str) -> RcDoc<'a> {
+fn pp_record_fields<'a>(
+    fs: &'a [Field],
+    recs: &RecPoints,
+    vis: &'a str,
+    names: Option<&'a FieldNames>,
+) -> RcDoc<'a> {
     if is_tuple(fs) {
         let vis = if vis.is_empty() {
             RcDoc::nil()
@@ -188,23 +281,30 @@ fn pp_record_fields<'a>(fs: &'a [Field], recs: &RecPoints, vis: &'a str) -> RcDo
         );
         enclose("(", tuple, ")")
     } else {
-        let fields = concat(fs.iter().map(|f| pp_record_field(f, recs, vis)), ",");
+        let fields = concat(fs.iter().map(|f| pp_record_field(f, recs, vis, names)), ",");
         enclose_space("{", fields, "}")
     }
 }
 
-fn pp_variant_field<'a>(field: &'a Field, recs: &RecPoints) -> RcDoc<'a> {
+fn pp_variant_field<'a>(
+    field: &'a Field,
+    recs: &RecPoints,
+    names: Option<&'a FieldNames>,
+) -> RcDoc<'a> {
+    let label = pp_label(&field.id, true, "", names);
     match field.ty.as_ref() {
-        TypeInner::Null => pp_label(&field.id, true, ""),
-        TypeInner::Record(fs) => {
-            pp_label(&field.id, true, "").append(pp_record_fields(fs, recs, ""))
-        }
-        _ => pp_label(&field.id, true, "").append(enclose("(", pp_ty(&field.ty, recs), ")")),
+        TypeInner::Null => label,
+        TypeInner::Record(fs) => label.append(pp_record_fields(fs, recs, "", None)),
+        _ => label.append(enclose("(", pp_ty(&field.ty, recs), ")")),
     }
 }
 
-fn pp_variant_fields<'a>(fs: &'a [Field], recs: &RecPoints) -> RcDoc<'a> {
-    let fields = concat(fs.iter().map(|f| pp_variant_field(f, recs)), ",");
+fn pp_variant_fields<'a>(
+    fs: &'a [Field],
+    recs: &RecPoints,
+    names: Option<&'a FieldNames>,
+) -> RcDoc<'a> {
+    let fields = concat(fs.iter().map(|f| pp_variant_field(f, recs, names)), ",");
     enclose_space("{", fields, "}")
 }
 
@@ -223,6 +323,22 @@ fn pp_defs<'a>(
         let ty = env.find_type(id).unwrap();
         let name = ident(id, Some(Case::Pascal)).append(" ");
         let vis = "pub ";
+        if let Some(path) = config.external_types.get(*id) {
+            return str(vis)
+                .append(kwd("type"))
+                .append(name)
+                .append("= ")
+                .append(path.as_str())
+                .append(";");
+        }
+        let derive = match config.extra_type_attributes.get(*id) {
+            Some(attrs) => RcDoc::intersperse(
+                std::iter::once(derive).chain(attrs.iter().map(|a| a.as_str())),
+                RcDoc::line(),
+            ),
+            None => str(derive),
+        };
+        let names = config.field_names.get(*id);
         match ty.as_ref() {
             TypeInner::Record(fs) => {
                 let separator = if is_tuple(fs) {
@@ -230,21 +346,21 @@ fn pp_defs<'a>(
                 } else {
                     RcDoc::nil()
                 };
-                str(derive)
+                derive
                     .append(RcDoc::line())
                     .append(vis)
                     .append("struct ")
                     .append(name)
-                    .append(pp_record_fields(fs, recs, "pub"))
+                    .append(pp_record_fields(fs, recs, "pub", names))
                     .append(separator)
                     .append(RcDoc::hardline())
             }
-            TypeInner::Variant(fs) => str(derive)
+            TypeInner::Variant(fs) => derive
                 .append(RcDoc::line())
                 .append(vis)
                 .append("enum ")
                 .append(name)
-                .append(pp_variant_fields(fs, recs))
+                .append(pp_variant_fields(fs, recs, names))
                 .append(RcDoc::hardline()),
             TypeInner::Func(func) => str("candid::define_function!(")
                 .append(vis)
@@ -260,7 +376,7 @@ fn pp_defs<'a>(
                 .append(");"),
             _ => {
                 if recs.contains(id) {
-                    str(derive)
+                    derive
                         .append(RcDoc::line())
                         .append(vis)
                         .append("struct ")
@@ -281,6 +397,72 @@ fn pp_defs<'a>(
     }))
 }
 
+// Attributes can only be added to the types generated as a struct or an enum, and only their
+// named fields can be renamed.
+fn check_type_attributes(
+    config: &Config,
+    env: &TypeEnv,
+    def_list: &[&str],
+    recs: &RecPoints,
+) -> Result<(), String> {
+    for name in config.extra_type_attributes.keys() {
+        let name = name.as_str();
+        if config.external_types.contains_key(name) {
+            return Err(format!(
+                "Cannot add attributes to type {name}, which is an external type"
+            ));
+        }
+        if !def_list.contains(&name) {
+            return Err(format!(
+                "Cannot add attributes to type {name}, which is not generated"
+            ));
+        }
+        let is_struct_or_enum = match env.find_type(name).unwrap().as_ref() {
+            TypeInner::Record(_) | TypeInner::Variant(_) => true,
+            TypeInner::Func(_) | TypeInner::Service(_) => false,
+            _ => recs.contains(name),
+        };
+        if !is_struct_or_enum {
+            return Err(format!(
+                "Cannot add attributes to type {name}, which is generated as a type alias"
+            ));
+        }
+    }
+    for (name, names) in config.field_names.iter() {
+        let name = name.as_str();
+        if config.external_types.contains_key(name) {
+            return Err(format!(
+                "Cannot rename the fields of type {name}, which is an external type"
+            ));
+        }
+        if !def_list.contains(&name) {
+            return Err(format!(
+                "Cannot rename the fields of type {name}, which is not generated"
+            ));
+        }
+        let fields = match env.find_type(name).unwrap().as_ref() {
+            TypeInner::Record(fs) if !is_tuple(fs) => fs,
+            TypeInner::Variant(fs) => fs,
+            _ => {
+                return Err(format!(
+                    "Cannot rename the fields of type {name}, which has no named fields"
+                ))
+            }
+        };
+        for label in names.keys() {
+            if !fields
+                .iter()
+                .any(|f| matches!(&*f.id, Label::Named(l) if l == label))
+            {
+                return Err(format!(
+                    "Cannot rename the field {label} of type {name}, which has no such field"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 fn pp_args(args: &[Type]) -> RcDoc {
     let empty = RecPoints::default();
     let doc = concat(args.iter().map(|t| pp_ty(t, &empty)), ",");
@@ -313,23 +495,16 @@ fn pp_ty_service(serv: &[(String, Type)]) -> RcDoc {
     enclose_space("{", doc, "}")
 }
 
-fn pp_function<'a>(config: &Config, id: &'a str, func: &'a Function) -> RcDoc<'a> {
-    let name = ident(id, Some(Case::Snake));
+// One-way methods are notified by canisters, without waiting for a reply.
+fn is_function_async(config: &Config, func: &Function) -> bool {
+    !(is_oneway(func) && matches!(config.target, Target::CanisterCall))
+}
+
+fn pp_function_ret<'a>(config: &Config, func: &'a Function) -> RcDoc<'a> {
     let empty = BTreeSet::new();
-    let arg_prefix = str(match config.target {
-        Target::CanisterCall => "&self",
-        Target::Agent => "&self",
-        Target::CanisterStub => unimplemented!(),
-    });
-    let args = concat(
-        std::iter::once(arg_prefix).chain(
-            func.args
-                .iter()
-                .enumerate()
-                .map(|(i, ty)| RcDoc::as_string(format!("arg{i}: ")).append(pp_ty(ty, &empty))),
-        ),
-        ",",
-    );
+    if !is_function_async(config, func) {
+        return str("std::result::Result<(), ic_cdk::api::call::RejectionCode>");
+    }
     let rets = match config.target {
         Target::CanisterCall => enclose(
             "(",
@@ -348,22 +523,84 @@ fn pp_function<'a>(config: &Config, id: &'a str, func: &'a Function) -> RcDoc<'a
                 ")",
             ),
         },
-        Target::CanisterStub => unimplemented!(),
+        Target::CanisterStub => unreachable!(),
     };
-    let sig = kwd("pub async fn")
+    enclose("Result<", rets, ">")
+}
+
+fn pp_function_sig<'a>(
+    config: &Config,
+    vis: &'a str,
+    id: &'a str,
+    func: &'a Function,
+    payment: Option<&str>,
+) -> RcDoc<'a> {
+    let name = match payment {
+        Some(name) => RcDoc::text(name.to_string()),
+        None => ident(id, Some(Case::Snake)),
+    };
+    let empty = BTreeSet::new();
+    let arg_prefix = str(match config.target {
+        Target::CanisterCall => "&self",
+        Target::Agent => "&self",
+        Target::CanisterStub => unreachable!(),
+    });
+    let args = concat(
+        std::iter::once(arg_prefix)
+            .chain(
+                func.args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| RcDoc::as_string(format!("arg{i}: ")).append(pp_ty(ty, &empty))),
+            )
+            .chain(payment.map(|_| str("cycles: u128"))),
+        ",",
+    );
+    let fn_kwd = if is_function_async(config, func) {
+        "async fn"
+    } else {
+        "fn"
+    };
+    str(vis)
+        .append(kwd(fn_kwd))
         .append(name)
         .append(enclose("(", args, ")"))
         .append(kwd(" ->"))
-        .append(enclose("Result<", rets, "> "));
+        .append(pp_function_ret(config, func))
+}
+
+fn pp_function<'a>(
+    config: &Config,
+    id: &'a str,
+    func: &'a Function,
+    payment: Option<&str>,
+) -> RcDoc<'a> {
+    let empty = BTreeSet::new();
+    let oneway = is_oneway(func);
+    let with_payment = payment.is_some();
+    let sig = pp_function_sig(config, "pub ", id, func, payment).append(" ");
     let method = id.escape_debug().to_string();
     let body = match config.target {
         Target::CanisterCall => {
             let args = RcDoc::concat((0..func.args.len()).map(|i| RcDoc::text(format!("arg{i},"))));
-            str("ic_cdk::call(self.0, \"")
+            let (call, payment, await_) = match (oneway, with_payment) {
+                (false, false) => ("ic_cdk::call", "", ".await"),
+                (false, true) => (
+                    "ic_cdk::api::call::call_with_payment128",
+                    ", cycles",
+                    ".await",
+                ),
+                (true, false) => ("ic_cdk::notify", "", ""),
+                (true, true) => ("ic_cdk::api::call::notify_with_payment128", ", cycles", ""),
+            };
+            str(call)
+                .append("(self.0, \"")
                 .append(method)
                 .append("\", ")
                 .append(enclose("(", args, ")"))
-                .append(").await")
+                .append(payment)
+                .append(")")
+                .append(await_)
         }
         Target::Agent => {
             let is_query = func.is_query();
@@ -374,28 +611,378 @@ fn pp_function<'a>(config: &Config, id: &'a str, func: &'a Function) -> RcDoc<'a
                 RcDoc::text(", "),
             );
             let blob = str("Encode!").append(enclose("(", args, ")?;"));
-            let rets = RcDoc::concat(
-                func.rets
-                    .iter()
-                    .map(|ty| str(", ").append(pp_ty(ty, &empty))),
-            );
-            str("let args = ").append(blob).append(RcDoc::hardline())
-                .append(format!("let bytes = self.1.{builder_method}(&self.0, \"{method}\").with_arg(args).{call}().await?;"))
-                .append(RcDoc::hardline())
-                .append("Ok(Decode!(&bytes").append(rets).append(")?)")
+            if oneway {
+                str("let args = ")
+                    .append(blob)
+                    .append(RcDoc::hardline())
+                    .append(format!(
+                        "self.1.update(&self.0, \"{method}\").with_arg(args).call().await?;"
+                    ))
+                    .append(RcDoc::hardline())
+                    .append("Ok(())")
+            } else {
+                let rets = RcDoc::concat(
+                    func.rets
+                        .iter()
+                        .map(|ty| str(", ").append(pp_ty(ty, &empty))),
+                );
+                str("let args = ").append(blob).append(RcDoc::hardline())
+                    .append(format!("let bytes = self.1.{builder_method}(&self.0, \"{method}\").with_arg(args).{call}().await?;"))
+                    .append(RcDoc::hardline())
+                    .append("Ok(Decode!(&bytes").append(rets).append(")?)")
+            }
         }
-        Target::CanisterStub => unimplemented!(),
+        Target::CanisterStub => unreachable!(),
     };
-    sig.append(enclose_space("{", body, "}"))
+    RcDoc::text(pp_method_doc(id, func, with_payment))
+        .append(RcDoc::hardline())
+        .append(sig)
+        .append(enclose_space("{", body, "}"))
+}
+
+fn pp_method_doc(id: &str, func: &Function, with_payment: bool) -> String {
+    let mode = if is_oneway(func) {
+        "One-way method"
+    } else if is_composite_query(func) {
+        "Composite query method"
+    } else if func.is_query() {
+        "Query method"
+    } else {
+        "Update method"
+    };
+    let id = id.escape_debug();
+    if with_payment {
+        format!("/// {mode} `{id}`, attaching `cycles` to the call.")
+    } else if is_oneway(func) {
+        format!("/// {mode} `{id}`: the call is sent without waiting for a reply.")
+    } else {
+        format!("/// {mode} `{id}`.")
+    }
+}
+
+fn is_oneway(func: &Function) -> bool {
+    func.modes.contains(&candid::types::FuncMode::Oneway)
+}
+// The name of the mock method setting the response of `id`.
+fn mock_setter(id: &str) -> String {
+    if id.is_empty()
+        || id.starts_with(|c: char| !c.is_ascii_alphabetic() && c != '_')
+        || id.chars().any(|c| !c.is_ascii_alphanumeric() && c != '_')
+    {
+        format!("on_{}", candid::idl_hash(id))
+    } else {
+        format!("on_{}", id.to_case(Case::Snake))
+    }
+}
+
+// The Rust name of the method `id`, without the `r#` of keywords.
+fn method_name(id: &str) -> String {
+    let name = ident(id, Some(Case::Snake)).pretty(LINE_WIDTH).to_string();
+    match name.strip_prefix("r#") {
+        Some(name) => name.to_string(),
+        None => name,
+    }
+}
+// The name of the `_with_payment` variant of each method. It is suffixed with `_` when another
+// method has the same name, e.g. `transfer_with_payment_` next to a `transfer_with_payment` method.
+fn payment_names(serv: &[(String, Type)]) -> BTreeMap<&str, String> {
+    let names: BTreeSet<_> = serv.iter().map(|(id, _)| method_name(id)).collect();
+    serv.iter()
+        .map(|(id, _)| {
+            let mut name = format!("{}_with_payment", method_name(id));
+            while names.contains(&name) {
+                name.push('_');
+            }
+            (id.as_str(), name)
+        })
+        .collect()
+}
+
+// The service as a trait, implemented by the generated struct and by a mock.
+fn pp_client_trait<'a>(
+    config: &'a Config,
+    env: &'a TypeEnv,
+    serv: &'a [(String, Type)],
+    struct_name: &str,
+) -> RcDoc<'a> {
+    let empty = BTreeSet::new();
+    let trait_name = format!("{struct_name}Api");
+    let mock_name = format!("Mock{struct_name}");
+    let with_payment = matches!(config.target, Target::CanisterCall);
+    let payment_names = payment_names(serv);
+    let variants = || {
+        serv.iter().flat_map(|(id, func)| {
+            let func = env.as_func(func).unwrap();
+            let payment = payment_names[id.as_str()].as_str();
+            std::iter::once((id.as_str(), func, None)).chain(with_payment.then_some((
+                id.as_str(),
+                func,
+                Some(payment),
+            )))
+        })
+    };
+    let call_args = |func: &Function, payment: Option<&str>| {
+        std::iter::once("self".to_string())
+            .chain((0..func.args.len()).map(|i| format!("arg{i}")))
+            .chain(payment.map(|_| "cycles".to_string()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let await_ = |func: &Function| {
+        if is_function_async(config, func) {
+            ".await"
+        } else {
+            ""
+        }
+    };
+
+    let sigs = RcDoc::intersperse(
+        variants().map(|(id, func, payment)| {
+            RcDoc::text(pp_method_doc(id, func, payment.is_some()))
+                .append(RcDoc::hardline())
+                .append(pp_function_sig(config, "", id, func, payment))
+                .append(";")
+        }),
+        RcDoc::hardline(),
+    );
+    let service_impl = match config.target {
+        Target::CanisterCall => format!("impl {trait_name} for {struct_name} "),
+        Target::Agent => format!("impl<'a> {trait_name} for {struct_name}<'a> "),
+        Target::CanisterStub => unreachable!(),
+    };
+    let service_methods = RcDoc::intersperse(
+        variants().map(|(id, func, payment)| {
+            let name = match payment {
+                Some(name) => RcDoc::text(name.to_string()),
+                None => ident(id, Some(Case::Snake)),
+            };
+            let body = RcDoc::text(format!("{struct_name}::"))
+                .append(name)
+                .append(format!("({}){}", call_args(func, payment), await_(func)));
+            pp_function_sig(config, "", id, func, payment)
+                .append(" ")
+                .append(enclose_space("{", body, "}"))
+        }),
+        RcDoc::hardline(),
+    );
+
+    // The type of the closure answering a method, as `dyn Fn(..) -> ..` or `impl Fn(..) -> ..`.
+    let responder = |kind: &'a str, func: &'a Function| {
+        let args = RcDoc::intersperse(
+            func.args.iter().map(|ty| pp_ty(ty, &empty)),
+            RcDoc::text(", "),
+        );
+        kwd(kind)
+            .append("Fn")
+            .append(enclose("(", args, ")"))
+            .append(kwd(" ->"))
+            .append(pp_function_ret(config, func))
+    };
+    let fields = concat(
+        serv.iter().map(|(id, func)| {
+            let func = env.as_func(func).unwrap();
+            ident(id, Some(Case::Snake))
+                .append(": std::cell::RefCell<Option<std::rc::Rc<")
+                .append(responder("dyn", func))
+                .append(">>>")
+        }),
+        ",",
+    );
+    let setters = RcDoc::intersperse(
+        serv.iter().map(|(id, func)| {
+            let func = env.as_func(func).unwrap();
+            RcDoc::text(format!("/// Sets the response of `{}`.", id.escape_debug()))
+                .append(RcDoc::hardline())
+                .append(format!("pub fn {}(&self, f: ", mock_setter(id)))
+                .append(responder("impl", func))
+                .append(" + 'static) -> &Self ")
+                .append(enclose_space(
+                    "{",
+                    str("*self.")
+                        .append(ident(id, Some(Case::Snake)))
+                        .append(".borrow_mut() = Some(std::rc::Rc::new(f));")
+                        .append(RcDoc::hardline())
+                        .append("self"),
+                    "}",
+                ))
+        }),
+        RcDoc::hardline(),
+    );
+    let mock_methods = RcDoc::intersperse(
+        variants().map(|(id, func, payment)| {
+            let args = (0..func.args.len())
+                .map(|i| format!("arg{i}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let ignore_cycles = if payment.is_some() {
+                str("let _ = cycles;").append(RcDoc::hardline())
+            } else {
+                RcDoc::nil()
+            };
+            let body = ignore_cycles
+                .append("let f = self.")
+                .append(ident(id, Some(Case::Snake)))
+                .append(format!(
+                    ".borrow().clone().expect(\"{mock_name}: no response set for `{}`\");",
+                    id.escape_debug()
+                ))
+                .append(RcDoc::hardline())
+                .append(format!("f({args})"));
+            pp_function_sig(config, "", id, func, payment)
+                .append(" ")
+                .append(enclose_space("{", body, "}"))
+        }),
+        RcDoc::hardline(),
+    );
+
+    RcDoc::text("#[allow(async_fn_in_trait)]")
+        .append(RcDoc::hardline())
+        .append(format!("pub trait {trait_name} "))
+        .append(enclose_space("{", sigs, "}"))
+        .append(RcDoc::hardline())
+        .append(service_impl)
+        .append(enclose_space("{", service_methods, "}"))
+        .append(RcDoc::hardline())
+        .append(format!(
+            "/// An in-memory [`{trait_name}`] for tests, answering with the responses set by its `on_*` methods."
+        ))
+        .append(RcDoc::hardline())
+        .append("#[derive(Default)]")
+        .append(RcDoc::hardline())
+        .append(format!("pub struct {mock_name} "))
+        .append(enclose_space("{", fields, "}"))
+        .append(RcDoc::hardline())
+        .append(format!("impl {mock_name} "))
+        .append(enclose_space(
+            "{",
+            str("pub fn new() -> Self { Self::default() }")
+                .append(RcDoc::hardline())
+                .append(setters),
+            "}",
+        ))
+        .append(RcDoc::hardline())
+        .append(format!("impl {trait_name} for {mock_name} "))
+        .append(enclose_space("{", mock_methods, "}"))
+        .append(RcDoc::hardline())
+}
+
+fn is_composite_query(func: &Function) -> bool {
+    func.modes
+        .contains(&candid::types::FuncMode::CompositeQuery)
+}
+// Queries are implemented synchronously, updates and composite queries asynchronously.
+fn is_stub_async(func: &Function) -> bool {
+    !func.is_query() || is_composite_query(func)
+}
+
+fn pp_stub_rets(func: &Function) -> RcDoc {
+    let empty = BTreeSet::new();
+    match func.rets.len() {
+        0 => RcDoc::nil(),
+        1 => kwd(" ->").append(pp_ty(&func.rets[0], &empty)),
+        _ => kwd(" ->").append(enclose(
+            "(",
+            RcDoc::intersperse(
+                func.rets.iter().map(|ty| pp_ty(ty, &empty)),
+                RcDoc::text(", "),
+            ),
+            ")",
+        )),
+    }
+}
+
+fn pp_stub_sig<'a>(id: &'a str, func: &'a Function) -> RcDoc<'a> {
+    let empty = BTreeSet::new();
+    let args = concat(
+        func.args
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| RcDoc::as_string(format!("arg{i}: ")).append(pp_ty(ty, &empty))),
+        ",",
+    );
+    kwd(if is_stub_async(func) {
+        "async fn"
+    } else {
+        "fn"
+    })
+    .append(ident(id, Some(Case::Snake)))
+    .append(enclose("(", args, ")"))
+    .append(pp_stub_rets(func))
+}
+
+fn pp_stub_method<'a>(
+    stub_impl: &str,
+    trait_name: &str,
+    id: &'a str,
+    func: &'a Function,
+) -> RcDoc<'a> {
+    let method = id.escape_debug().to_string();
+    let attr = if is_composite_query(func) {
+        format!("#[ic_cdk::query(composite = true, name = \"{method}\")]")
+    } else if func.is_query() {
+        format!("#[ic_cdk::query(name = \"{method}\")]")
+    } else {
+        format!("#[ic_cdk::update(name = \"{method}\")]")
+    };
+    let args = RcDoc::intersperse(
+        (0..func.args.len()).map(|i| RcDoc::text(format!("arg{i}"))),
+        RcDoc::text(", "),
+    );
+    let body = RcDoc::text(format!("<{stub_impl} as {trait_name}>::"))
+        .append(ident(id, Some(Case::Snake)))
+        .append(enclose("(", args, ")"))
+        .append(if is_stub_async(func) { ".await" } else { "" });
+    RcDoc::text(attr)
+        .append(RcDoc::hardline())
+        .append(pp_stub_sig(id, func))
+        .append(" ")
+        .append(enclose_space("{", body, "}"))
+}
+
+// The service as a trait, and the exported methods delegating to its implementation.
+fn pp_stub<'a>(config: &'a Config, env: &'a TypeEnv, actor: &'a Type) -> RcDoc<'a> {
+    let serv = env.as_service(actor).unwrap();
+    // Checked by `compile`.
+    let stub_impl = config.stub_impl.as_deref().unwrap();
+    let trait_name = config.service_name.to_case(Case::Pascal);
+    let sigs = RcDoc::intersperse(
+        serv.iter().map(|(id, func)| {
+            let func = env.as_func(func).unwrap();
+            pp_stub_sig(id, func).append(";")
+        }),
+        RcDoc::hardline(),
+    );
+    let methods = RcDoc::intersperse(
+        serv.iter().map(|(id, func)| {
+            let func = env.as_func(func).unwrap();
+            pp_stub_method(stub_impl, &trait_name, id, func)
+        }),
+        RcDoc::hardline(),
+    );
+    RcDoc::text("#[allow(async_fn_in_trait)]")
+        .append(RcDoc::hardline())
+        .append(format!("pub trait {trait_name} "))
+        .append(enclose_space("{", sigs, "}"))
+        .append(RcDoc::hardline())
+        .append(methods)
+        .append(RcDoc::hardline())
 }
 
 fn pp_actor<'a>(config: &'a Config, env: &'a TypeEnv, actor: &'a Type) -> RcDoc<'a> {
+    if let Target::CanisterStub = config.target {
+        return pp_stub(config, env, actor);
+    }
     // TODO trace to service before we figure out what canister means in Rust
     let serv = env.as_service(actor).unwrap();
+    let payment_names = payment_names(serv);
     let body = RcDoc::intersperse(
-        serv.iter().map(|(id, func)| {
+        serv.iter().flat_map(|(id, func)| {
             let func = env.as_func(func).unwrap();
-            pp_function(config, id, func)
+            // Agents cannot attach cycles to calls.
+            let with_payment = matches!(config.target, Target::CanisterCall);
+            let payment = payment_names[id.as_str()].as_str();
+            std::iter::once(pp_function(config, id, func, None))
+                .chain(with_payment.then(|| pp_function(config, id, func, Some(payment))))
         }),
         RcDoc::hardline(),
     );
@@ -406,28 +993,48 @@ fn pp_actor<'a>(config: &'a Config, env: &'a TypeEnv, actor: &'a Type) -> RcDoc<
             "pub struct {}<'a>(pub Principal, pub &'a ic_agent::Agent);",
             struct_name
         ),
-        Target::CanisterStub => unimplemented!(),
+        Target::CanisterStub => unreachable!(),
     };
     let service_impl = match config.target {
         Target::CanisterCall => format!("impl {} ", struct_name),
         Target::Agent => format!("impl<'a> {}<'a> ", struct_name),
-        Target::CanisterStub => unimplemented!(),
+        Target::CanisterStub => unreachable!(),
     };
     let res = RcDoc::text(service_def)
         .append(RcDoc::hardline())
         .append(service_impl)
         .append(enclose_space("{", body, "}"))
         .append(RcDoc::hardline());
+    let res = if config.client_trait {
+        res.append(pp_client_trait(config, env, serv, &struct_name))
+    } else {
+        res
+    };
+    let res = if config.network_ids.is_empty() {
+        res
+    } else {
+        let arms = RcDoc::concat(config.network_ids.iter().map(|(network, id)| {
+            RcDoc::text(format!(
+                "\"{}\" => Some(Principal::from_slice(&[{}])), // {}",
+                network.escape_debug(),
+                pp_principal_bytes(id),
+                id
+            ))
+            .append(RcDoc::hardline())
+        }));
+        RcDoc::text("pub fn canister_id_for_network(network: &str) -> Option<Principal> ")
+            .append(enclose_space(
+                "{",
+                str("match network ").append(enclose_space("{", arms.append("_ => None,"), "}")),
+                "}",
+            ))
+            .append(RcDoc::hardline())
+    };
     if let Some(cid) = config.canister_id {
-        let slice = cid
-            .as_slice()
-            .iter()
-            .map(|b| b.to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
         let id = RcDoc::text(format!(
             "pub const CANISTER_ID : Principal = Principal::from_slice(&[{}]); // {}",
-            slice, cid
+            pp_principal_bytes(&cid),
+            cid
         ));
         let instance = match config.target {
             Target::CanisterCall => format!(
@@ -435,7 +1042,7 @@ fn pp_actor<'a>(config: &'a Config, env: &'a TypeEnv, actor: &'a Type) -> RcDoc<
                 config.service_name, struct_name, struct_name
             ),
             Target::Agent => "".to_string(),
-            Target::CanisterStub => unimplemented!(),
+            Target::CanisterStub => unreachable!(),
         };
         res.append(id).append(RcDoc::hardline()).append(instance)
     } else {
@@ -443,7 +1050,24 @@ fn pp_actor<'a>(config: &'a Config, env: &'a TypeEnv, actor: &'a Type) -> RcDoc<
     }
 }
 
-pub fn compile(config: &Config, env: &TypeEnv, actor: &Option<Type>) -> String {
+fn pp_principal_bytes(id: &candid::Principal) -> String {
+    id.as_slice()
+        .iter()
+        .map(|b| b.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn compile(config: &Config, env: &TypeEnv, actor: &Option<Type>) -> Result<String, String> {
+    if matches!(config.target, Target::CanisterStub)
+        && actor.is_some()
+        && config.stub_impl.is_none()
+    {
+        return Err(
+            "Target::CanisterStub requires the type implementing the service, set with `set_stub_impl`"
+                .to_string(),
+        );
+    }
     let header = format!(
         r#"// This is an experimental feature to generate Rust binding from Candid.
 // You may want to manually adjust some of the types.
@@ -465,6 +1089,7 @@ use {}::{{self, CandidType, Deserialize, Principal, Encode, Decode}};
         env.0.iter().map(|pair| pair.0.as_ref()).collect()
     };
     let recs = infer_rec(&env, &def_list).unwrap();
+    check_type_attributes(config, &env, &def_list, &recs)?;
     let defs = pp_defs(config, &env, &def_list, &recs);
     let doc = match &actor {
         None => defs,
@@ -474,7 +1099,7 @@ use {}::{{self, CandidType, Deserialize, Principal, Encode, Decode}};
         }
     };
     let doc = RcDoc::text(header).append(RcDoc::line()).append(doc);
-    doc.pretty(LINE_WIDTH).to_string()
+    Ok(doc.pretty(LINE_WIDTH).to_string())
 }
 
 pub enum TypePath {
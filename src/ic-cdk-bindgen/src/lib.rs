@@ -6,6 +6,7 @@ use std::io::Write;
 use std::path::PathBuf;
 
 mod code_generator;
+pub use code_generator::Target;
 
 #[derive(Clone)]
 pub struct Config {
@@ -16,6 +17,8 @@ pub struct Config {
 }
 
 impl Config {
+    /// Resolves the Candid path and the canister id from the environment variables set by dfx,
+    /// see [`Config::from_candid_path`] to build without dfx.
     pub fn new(canister_name: &str) -> Self {
         let (candid_path, canister_id) = resolve_candid_path_and_canister_id(canister_name);
         let mut binding = code_generator::Config::new();
@@ -33,6 +36,72 @@ impl Config {
             binding,
         }
     }
+
+    /// Bindings for the Candid interface at `candid_path`, without reading any environment variable.
+    ///
+    /// No canister id is set: the generated struct is built at runtime, e.g. `Counter(id)` with an
+    /// id from the init arguments, unless one is set with [`set_canister_id`](Self::set_canister_id)
+    /// or [`set_network`](Self::set_network).
+    pub fn from_candid_path<P: Into<PathBuf>>(canister_name: &str, candid_path: P) -> Self {
+        let mut binding = code_generator::Config::new();
+        binding
+            .set_candid_crate("candid".to_string())
+            .set_service_name(canister_name.to_string())
+            .set_target(code_generator::Target::CanisterCall);
+
+        Config {
+            canister_name: canister_name.to_string(),
+            candid_path: candid_path.into(),
+            skip_existing_files: false,
+            binding,
+        }
+    }
+
+    /// Sets the canister id, generated as `CANISTER_ID`.
+    pub fn set_canister_id(&mut self, id: Principal) -> &mut Self {
+        self.binding.set_canister_id(id);
+        self
+    }
+
+    /// Sets the canister id on a network. All of them are returned by the generated
+    /// `canister_id_for_network` function.
+    pub fn add_network_canister_id(&mut self, network: &str, id: Principal) -> &mut Self {
+        self.binding.add_network_id(network, id);
+        self
+    }
+
+    /// Uses the canister id of `network` as `CANISTER_ID`.
+    pub fn set_network(&mut self, network: &str) -> &mut Self {
+        self.binding.set_network(network);
+        self
+    }
+
+    /// Generates the Rust bindings.
+    pub fn generate(&self) -> Result<String, String> {
+        let (env, actor) = pretty_check_file(&self.candid_path).map_err(|e| {
+            format!(
+                "Cannot parse candid file {}: {}",
+                self.candid_path.display(),
+                e
+            )
+        })?;
+        let mut binding = self.binding.clone();
+        if let Some(network) = self.binding.network() {
+            let id = self.binding.network_id(network).ok_or_else(|| {
+                format!(
+                    "No canister id for network {} of canister {}",
+                    network, self.canister_name
+                )
+            })?;
+            binding.set_canister_id(id);
+        }
+        code_generator::compile(&binding, &env, &actor).map_err(|e| {
+            format!(
+                "Cannot generate bindings of canister {}: {}",
+                self.canister_name, e
+            )
+        })
+    }
 }
 
 /// Resolve the candid path and canister id from environment variables.
@@ -116,9 +185,8 @@ impl Builder {
         });
         fs::create_dir_all(&out_path).unwrap();
         for conf in self.configs.iter() {
-            let (env, actor) =
-                pretty_check_file(&conf.candid_path).expect("Cannot parse candid file");
-            let content = code_generator::compile(&conf.binding, &env, &actor);
+            println!("cargo:rerun-if-changed={}", conf.candid_path.display());
+            let content = conf.generate().unwrap_or_else(|e| panic!("{}", e));
             let generated_path = out_path.join(format!("{}.rs", conf.canister_name));
             if !(conf.skip_existing_files && generated_path.exists()) {
                 fs::write(generated_path, content).expect("Cannot store generated binding");
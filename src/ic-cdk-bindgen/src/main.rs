@@ -0,0 +1,131 @@
+use candid::Principal;
+use clap::{Parser, ValueEnum};
+use ic_cdk_bindgen::{Config, Target};
+use std::path::PathBuf;
+
+/// Generate Rust bindings from a Candid file.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Path to the .did file.
+    did: PathBuf,
+    /// Kind of bindings to generate.
+    #[arg(short, long, value_enum, default_value_t = CliTarget::CanisterCall)]
+    target: CliTarget,
+    /// Name of the canister, used for the generated service. Defaults to the .did file name.
+    #[arg(short, long)]
+    name: Option<String>,
+    /// Canister id, generated as `CANISTER_ID`.
+    #[arg(long, value_parser = parse_principal, conflicts_with = "network")]
+    canister_id: Option<Principal>,
+    /// Canister id on a network, as `NETWORK=ID`. Can be repeated.
+    #[arg(long, value_parser = parse_network_id)]
+    network_id: Vec<(String, Principal)>,
+    /// Use the canister id of this network as `CANISTER_ID`.
+    #[arg(long)]
+    network: Option<String>,
+    /// Path of the candid crate in the generated code.
+    #[arg(long, default_value = "candid")]
+    candid_crate: String,
+    /// Attributes of every generated type, e.g. `#[derive(CandidType, Deserialize, Debug)]`.
+    #[arg(long)]
+    type_attributes: Option<String>,
+    /// Existing Rust type used for a Candid type, as `NAME=PATH`. Can be repeated.
+    #[arg(long, value_parser = parse_pair)]
+    external_type: Vec<(String, String)>,
+    /// Type implementing the service trait, required by `--target canister-stub`.
+    #[arg(long, required_if_eq("target", "canister-stub"))]
+    stub_impl: Option<String>,
+    /// Also generate the service as a trait, with an in-memory mock.
+    #[arg(long)]
+    client_trait: bool,
+    /// Write the bindings to this file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CliTarget {
+    /// Inter-canister calls with `ic_cdk::call`.
+    CanisterCall,
+    /// Calls from outside the IC with `ic-agent`.
+    Agent,
+    /// A trait to implement the service, and the methods exporting it.
+    CanisterStub,
+}
+
+impl From<CliTarget> for Target {
+    fn from(target: CliTarget) -> Self {
+        match target {
+            CliTarget::CanisterCall => Target::CanisterCall,
+            CliTarget::Agent => Target::Agent,
+            CliTarget::CanisterStub => Target::CanisterStub,
+        }
+    }
+}
+
+fn parse_principal(s: &str) -> Result<Principal, String> {
+    Principal::from_text(s).map_err(|e| e.to_string())
+}
+
+fn parse_pair(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, found `{s}`"))
+}
+
+fn parse_network_id(s: &str) -> Result<(String, Principal), String> {
+    let (network, id) = parse_pair(s)?;
+    Ok((network, parse_principal(&id)?))
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let name = cli.name.unwrap_or_else(|| {
+        cli.did
+            .file_stem()
+            .map(|s| s.to_string_lossy().replace('-', "_"))
+            .unwrap_or_else(|| "service".to_string())
+    });
+    let mut config = Config::from_candid_path(&name, cli.did.clone());
+    config
+        .binding
+        .set_target(cli.target.into())
+        .set_candid_crate(cli.candid_crate)
+        .set_client_trait(cli.client_trait);
+    if let Some(attrs) = cli.type_attributes {
+        config.binding.set_type_attributes(attrs);
+    }
+    for (name, path) in &cli.external_type {
+        config.binding.set_external_type(name, path.clone());
+    }
+    if let Some(path) = cli.stub_impl {
+        config.binding.set_stub_impl(path);
+    }
+    if let Some(id) = cli.canister_id {
+        config.set_canister_id(id);
+    }
+    for (network, id) in &cli.network_id {
+        config.add_network_canister_id(network, *id);
+    }
+    if let Some(network) = &cli.network {
+        config.set_network(network);
+    }
+
+    let bindings = match config.generate() {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    };
+    match cli.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, bindings) {
+                eprintln!("error: cannot write {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        }
+        None => println!("{bindings}"),
+    }
+}
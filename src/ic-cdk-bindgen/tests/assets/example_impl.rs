@@ -0,0 +1,22 @@
+struct Canister;
+
+impl Example for Canister {
+    fn balance(_: Account) -> Tokens {
+        unimplemented!()
+    }
+    async fn transfer(_: Account, _: candid::Nat) -> TransferRet {
+        unimplemented!()
+    }
+    async fn transfer_with_payment(_: candid::Nat) {}
+    async fn log(_: String) {}
+    async fn count() -> u64 {
+        0
+    }
+    fn r#type() -> Kind {
+        Kind::A
+    }
+    fn depth(_: Tree) -> u32 {
+        0
+    }
+    async fn subscribe(_: Callback) {}
+}
@@ -0,0 +1,173 @@
+//! Generates the bindings of the `.did` files in `tests/assets` for every target, compares them
+//! with the snapshots in `tests/snapshots`, and checks that they compile.
+//!
+//! A missing or outdated snapshot fails the test: rerun with `UPDATE_SNAPSHOTS=1` to write them.
+
+use candid::Principal;
+use ic_cdk_bindgen::{Config, Target};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TARGETS: [&str; 3] = ["call", "agent", "stub"];
+
+fn asset(file: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/assets")
+        .join(file)
+}
+
+fn config(name: &str, target: &str) -> Config {
+    let mut config = Config::from_candid_path(name, asset(&format!("{name}.did")));
+    match target {
+        "call" => {
+            config
+                .set_canister_id(Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap())
+                .binding
+                .set_client_trait(true);
+        }
+        "agent" => {
+            config
+                .binding
+                .set_target(Target::Agent)
+                .set_client_trait(true);
+        }
+        "stub" => {
+            config
+                .binding
+                .set_target(Target::CanisterStub)
+                .set_stub_impl("Canister".to_string());
+        }
+        _ => unreachable!(),
+    }
+    config
+}
+
+fn check_snapshot(file: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(file);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "cannot read {}: {e}, rerun with UPDATE_SNAPSHOTS=1 to write it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "{} is outdated, rerun with UPDATE_SNAPSHOTS=1 to update it",
+        path.display()
+    );
+}
+
+#[test]
+fn bindings() {
+    let t = trybuild::TestCases::new();
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bindgen");
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["counter", "example"] {
+        for target in TARGETS {
+            let bindings = config(name, target).generate().unwrap();
+            check_snapshot(&format!("{name}.{target}.rs"), &bindings);
+            let stub_impl = if target == "stub" {
+                fs::read_to_string(asset(&format!("{name}_impl.rs"))).unwrap()
+            } else {
+                String::new()
+            };
+            let path = dir.join(format!("{name}_{target}.rs"));
+            fs::write(
+                &path,
+                format!(
+                    "#![allow(non_upper_case_globals)]\n{bindings}\n{stub_impl}\nfn main() {{}}\n"
+                ),
+            )
+            .unwrap();
+            t.pass(path);
+        }
+    }
+}
+
+#[test]
+fn payment_variant_names() {
+    let bindings = config("example", "call").generate().unwrap();
+    // `transfer_with_payment` is a method of the service.
+    assert!(bindings.contains("pub async fn transfer_with_payment(&self, arg0"));
+    assert!(bindings.contains("pub async fn transfer_with_payment_("));
+    assert!(bindings.contains("pub async fn transfer_with_payment_with_payment("));
+}
+
+#[test]
+fn type_attributes() {
+    let mut example = config("example", "call");
+    example
+        .binding
+        .add_type_attributes("Account", "#[derive(Clone, Debug)]".to_string());
+    assert!(example
+        .generate()
+        .unwrap()
+        .contains("#[derive(Clone, Debug)]"));
+
+    // `Tokens` is an alias of `candid::Nat`, and `Callback` a function reference.
+    for name in ["Tokens", "Callback", "Missing"] {
+        let mut example = config("example", "call");
+        example
+            .binding
+            .add_type_attributes(name, "#[derive(Clone, Debug)]".to_string());
+        assert!(example.generate().unwrap_err().contains(name));
+    }
+}
+
+#[test]
+fn field_names() {
+    let mut example = config("example", "call");
+    example
+        .binding
+        .set_field_name("Account", "subaccount", "sub".to_string())
+        .set_field_name("Kind", "b_c", "Bc".to_string());
+    let bindings = example.generate().unwrap();
+    assert!(bindings.contains("#[serde(rename=\"subaccount\")]"));
+    assert!(bindings.contains("#[serde(rename=\"b_c\")]"));
+
+    // `x` is a field of the inline record of `Kind`, not of `Kind`.
+    for (name, label) in [
+        ("Account", "missing"),
+        ("Kind", "x"),
+        ("Tokens", "a"),
+        ("Missing", "a"),
+    ] {
+        let mut example = config("example", "call");
+        example
+            .binding
+            .set_field_name(name, label, "renamed".to_string());
+        let error = example.generate().unwrap_err();
+        assert!(error.contains(name), "{error}");
+    }
+}
+
+#[test]
+fn network_canister_id() {
+    let mut counter = Config::from_candid_path("counter", asset("counter.did"));
+    counter
+        .add_network_canister_id(
+            "ic",
+            Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap(),
+        )
+        .set_network("ic");
+    let bindings = counter.generate().unwrap();
+    assert!(bindings.contains("pub const CANISTER_ID"));
+    assert!(bindings.contains("pub fn canister_id_for_network"));
+
+    counter.set_network("local");
+    assert!(counter.generate().unwrap_err().contains("local"));
+}
+
+#[test]
+fn stub_requires_impl() {
+    let mut config = Config::from_candid_path("counter", asset("counter.did"));
+    config.binding.set_target(Target::CanisterStub);
+    assert!(config.generate().unwrap_err().contains("set_stub_impl"));
+}
@@ -0,0 +1,86 @@
+// This is an experimental feature to generate Rust binding from Candid.
+// You may want to manually adjust some of the types.
+#![allow(dead_code, unused_imports)]
+use candid::{self, CandidType, Deserialize, Principal, Encode, Decode};
+type Result<T> = std::result::Result<T, ic_agent::AgentError>;
+
+pub struct Counter<'a>(pub Principal, pub &'a ic_agent::Agent);
+impl<'a> Counter<'a> {
+  /// Query method `get`.
+  pub async fn get(&self) -> Result<candid::Nat> {
+    let args = Encode!()?;
+    let bytes = self.1.query(&self.0, "get").with_arg(args).call().await?;
+    Ok(Decode!(&bytes, candid::Nat)?)
+  }
+  /// Update method `inc`.
+  pub async fn inc(&self) -> Result<()> {
+    let args = Encode!()?;
+    let bytes = self.1.update(&self.0, "inc").with_arg(args).call_and_wait().await?;
+    Ok(Decode!(&bytes)?)
+  }
+  /// Update method `set`.
+  pub async fn set(&self, arg0: candid::Nat) -> Result<()> {
+    let args = Encode!(&arg0)?;
+    let bytes = self.1.update(&self.0, "set").with_arg(args).call_and_wait().await?;
+    Ok(Decode!(&bytes)?)
+  }
+}
+#[allow(async_fn_in_trait)]
+pub trait CounterApi {
+  /// Query method `get`.
+  async fn get(&self) -> Result<candid::Nat>;
+  /// Update method `inc`.
+  async fn inc(&self) -> Result<()>;
+  /// Update method `set`.
+  async fn set(&self, arg0: candid::Nat) -> Result<()>;
+}
+impl<'a> CounterApi for Counter<'a> {
+  async fn get(&self) -> Result<candid::Nat> { Counter::get(self).await }
+  async fn inc(&self) -> Result<()> { Counter::inc(self).await }
+  async fn set(&self, arg0: candid::Nat) -> Result<()> {
+    Counter::set(self, arg0).await
+  }
+}
+/// An in-memory [`CounterApi`] for tests, answering with the responses set by its `on_*` methods.
+#[derive(Default)]
+pub struct MockCounter {
+  get: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<candid::Nat>>>>,
+  inc: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<()>>>>,
+  set: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(candid::Nat) -> Result<
+    ()
+  >>>>,
+}
+impl MockCounter {
+  pub fn new() -> Self { Self::default() }
+  /// Sets the response of `get`.
+  pub fn on_get(&self, f: impl Fn() -> Result<candid::Nat> + 'static) -> &Self {
+    *self.get.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `inc`.
+  pub fn on_inc(&self, f: impl Fn() -> Result<()> + 'static) -> &Self {
+    *self.inc.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `set`.
+  pub fn on_set(&self, f: impl Fn(candid::Nat) -> Result<
+    ()
+  > + 'static) -> &Self {
+    *self.set.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+}
+impl CounterApi for MockCounter {
+  async fn get(&self) -> Result<candid::Nat> {
+    let f = self.get.borrow().clone().expect("MockCounter: no response set for `get`");
+    f()
+  }
+  async fn inc(&self) -> Result<()> {
+    let f = self.inc.borrow().clone().expect("MockCounter: no response set for `inc`");
+    f()
+  }
+  async fn set(&self, arg0: candid::Nat) -> Result<()> {
+    let f = self.set.borrow().clone().expect("MockCounter: no response set for `set`");
+    f(arg0)
+  }
+}
@@ -0,0 +1,138 @@
+// This is an experimental feature to generate Rust binding from Candid.
+// You may want to manually adjust some of the types.
+#![allow(dead_code, unused_imports)]
+use candid::{self, CandidType, Deserialize, Principal, Encode, Decode};
+use ic_cdk::api::call::CallResult as Result;
+
+pub struct Counter(pub Principal);
+impl Counter {
+  /// Query method `get`.
+  pub async fn get(&self) -> Result<(candid::Nat,)> {
+    ic_cdk::call(self.0, "get", ()).await
+  }
+  /// Query method `get`, attaching `cycles` to the call.
+  pub async fn get_with_payment(&self, cycles: u128) -> Result<(candid::Nat,)> {
+    ic_cdk::api::call::call_with_payment128(self.0, "get", (), cycles).await
+  }
+  /// Update method `inc`.
+  pub async fn inc(&self) -> Result<()> {
+    ic_cdk::call(self.0, "inc", ()).await
+  }
+  /// Update method `inc`, attaching `cycles` to the call.
+  pub async fn inc_with_payment(&self, cycles: u128) -> Result<()> {
+    ic_cdk::api::call::call_with_payment128(self.0, "inc", (), cycles).await
+  }
+  /// Update method `set`.
+  pub async fn set(&self, arg0: candid::Nat) -> Result<()> {
+    ic_cdk::call(self.0, "set", (arg0,)).await
+  }
+  /// Update method `set`, attaching `cycles` to the call.
+  pub async fn set_with_payment(
+    &self,
+    arg0: candid::Nat,
+    cycles: u128,
+  ) -> Result<()> {
+    ic_cdk::api::call::call_with_payment128(self.0, "set", (
+      arg0,
+    ), cycles).await
+  }
+}
+#[allow(async_fn_in_trait)]
+pub trait CounterApi {
+  /// Query method `get`.
+  async fn get(&self) -> Result<(candid::Nat,)>;
+  /// Query method `get`, attaching `cycles` to the call.
+  async fn get_with_payment(&self, cycles: u128) -> Result<(candid::Nat,)>;
+  /// Update method `inc`.
+  async fn inc(&self) -> Result<()>;
+  /// Update method `inc`, attaching `cycles` to the call.
+  async fn inc_with_payment(&self, cycles: u128) -> Result<()>;
+  /// Update method `set`.
+  async fn set(&self, arg0: candid::Nat) -> Result<()>;
+  /// Update method `set`, attaching `cycles` to the call.
+  async fn set_with_payment(&self, arg0: candid::Nat, cycles: u128) -> Result<
+    ()
+  >;
+}
+impl CounterApi for Counter {
+  async fn get(&self) -> Result<(candid::Nat,)> { Counter::get(self).await }
+  async fn get_with_payment(&self, cycles: u128) -> Result<(candid::Nat,)> {
+    Counter::get_with_payment(self, cycles).await
+  }
+  async fn inc(&self) -> Result<()> { Counter::inc(self).await }
+  async fn inc_with_payment(&self, cycles: u128) -> Result<()> {
+    Counter::inc_with_payment(self, cycles).await
+  }
+  async fn set(&self, arg0: candid::Nat) -> Result<()> {
+    Counter::set(self, arg0).await
+  }
+  async fn set_with_payment(&self, arg0: candid::Nat, cycles: u128) -> Result<
+    ()
+  > { Counter::set_with_payment(self, arg0, cycles).await }
+}
+/// An in-memory [`CounterApi`] for tests, answering with the responses set by its `on_*` methods.
+#[derive(Default)]
+pub struct MockCounter {
+  get: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<
+    (candid::Nat,)
+  >>>>,
+  inc: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<()>>>>,
+  set: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(candid::Nat) -> Result<
+    ()
+  >>>>,
+}
+impl MockCounter {
+  pub fn new() -> Self { Self::default() }
+  /// Sets the response of `get`.
+  pub fn on_get(&self, f: impl Fn() -> Result<
+    (candid::Nat,)
+  > + 'static) -> &Self {
+    *self.get.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `inc`.
+  pub fn on_inc(&self, f: impl Fn() -> Result<()> + 'static) -> &Self {
+    *self.inc.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `set`.
+  pub fn on_set(&self, f: impl Fn(candid::Nat) -> Result<
+    ()
+  > + 'static) -> &Self {
+    *self.set.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+}
+impl CounterApi for MockCounter {
+  async fn get(&self) -> Result<(candid::Nat,)> {
+    let f = self.get.borrow().clone().expect("MockCounter: no response set for `get`");
+    f()
+  }
+  async fn get_with_payment(&self, cycles: u128) -> Result<(candid::Nat,)> {
+    let _ = cycles;
+    let f = self.get.borrow().clone().expect("MockCounter: no response set for `get`");
+    f()
+  }
+  async fn inc(&self) -> Result<()> {
+    let f = self.inc.borrow().clone().expect("MockCounter: no response set for `inc`");
+    f()
+  }
+  async fn inc_with_payment(&self, cycles: u128) -> Result<()> {
+    let _ = cycles;
+    let f = self.inc.borrow().clone().expect("MockCounter: no response set for `inc`");
+    f()
+  }
+  async fn set(&self, arg0: candid::Nat) -> Result<()> {
+    let f = self.set.borrow().clone().expect("MockCounter: no response set for `set`");
+    f(arg0)
+  }
+  async fn set_with_payment(&self, arg0: candid::Nat, cycles: u128) -> Result<
+    ()
+  > {
+    let _ = cycles;
+    let f = self.set.borrow().clone().expect("MockCounter: no response set for `set`");
+    f(arg0)
+  }
+}
+pub const CANISTER_ID : Principal = Principal::from_slice(&[0, 0, 0, 0, 0, 0, 0, 2, 1, 1]); // ryjl3-tyaaa-aaaaa-aaaba-cai
+pub const counter : Counter = Counter(CANISTER_ID);
\ No newline at end of file
@@ -0,0 +1,17 @@
+// This is an experimental feature to generate Rust binding from Candid.
+// You may want to manually adjust some of the types.
+#![allow(dead_code, unused_imports)]
+use candid::{self, CandidType, Deserialize, Principal, Encode, Decode};
+
+#[allow(async_fn_in_trait)]
+pub trait Counter {
+  fn get() -> candid::Nat;
+  async fn inc();
+  async fn set(arg0: candid::Nat);
+}
+#[ic_cdk::query(name = "get")]
+fn get() -> candid::Nat { <Canister as Counter>::get() }
+#[ic_cdk::update(name = "inc")]
+async fn inc() { <Canister as Counter>::inc().await }
+#[ic_cdk::update(name = "set")]
+async fn set(arg0: candid::Nat) { <Canister as Counter>::set(arg0).await }
@@ -0,0 +1,241 @@
+// This is an experimental feature to generate Rust binding from Candid.
+// You may want to manually adjust some of the types.
+#![allow(dead_code, unused_imports)]
+use candid::{self, CandidType, Deserialize, Principal, Encode, Decode};
+type Result<T> = std::result::Result<T, ic_agent::AgentError>;
+
+#[derive(CandidType, Deserialize)]
+pub struct Init { pub owner: Principal }
+
+#[derive(CandidType, Deserialize)]
+pub struct Account {
+  pub owner: Principal,
+  pub subaccount: Option<serde_bytes::ByteBuf>,
+}
+
+pub type Tokens = candid::Nat;
+#[derive(CandidType, Deserialize)]
+pub enum Tree {
+  #[serde(rename="leaf")]
+  Leaf(candid::Nat),
+  #[serde(rename="node")]
+  Node{ left: Box<Tree>, right: Box<Tree> },
+}
+
+candid::define_function!(pub Callback : (candid::Nat) -> ());
+#[derive(CandidType, Deserialize)]
+pub enum TransferRet { Ok(candid::Nat), Err(String) }
+
+#[derive(CandidType, Deserialize)]
+pub enum Kind {
+  #[serde(rename="a")]
+  A,
+  #[serde(rename="d")]
+  D{ x: i8 },
+  #[serde(rename="b_c")]
+  BC(candid::Nat),
+}
+
+pub struct Example<'a>(pub Principal, pub &'a ic_agent::Agent);
+impl<'a> Example<'a> {
+  /// Query method `balance`.
+  pub async fn balance(&self, arg0: Account) -> Result<Tokens> {
+    let args = Encode!(&arg0)?;
+    let bytes = self.1.query(&self.0, "balance").with_arg(args).call().await?;
+    Ok(Decode!(&bytes, Tokens)?)
+  }
+  /// Composite query method `count`.
+  pub async fn count(&self) -> Result<u64> {
+    let args = Encode!()?;
+    let bytes = self.1.query(&self.0, "count").with_arg(args).call().await?;
+    Ok(Decode!(&bytes, u64)?)
+  }
+  /// Query method `depth`.
+  pub async fn depth(&self, arg0: Tree) -> Result<u32> {
+    let args = Encode!(&arg0)?;
+    let bytes = self.1.query(&self.0, "depth").with_arg(args).call().await?;
+    Ok(Decode!(&bytes, u32)?)
+  }
+  /// One-way method `log`: the call is sent without waiting for a reply.
+  pub async fn log(&self, arg0: String) -> Result<()> {
+    let args = Encode!(&arg0)?;
+    self.1.update(&self.0, "log").with_arg(args).call().await?;
+    Ok(())
+  }
+  /// Update method `subscribe`.
+  pub async fn subscribe(&self, arg0: Callback) -> Result<()> {
+    let args = Encode!(&arg0)?;
+    let bytes = self.1.update(&self.0, "subscribe").with_arg(args).call_and_wait().await?;
+    Ok(Decode!(&bytes)?)
+  }
+  /// Update method `transfer`.
+  pub async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    TransferRet
+  > {
+    let args = Encode!(&arg0, &arg1)?;
+    let bytes = self.1.update(&self.0, "transfer").with_arg(args).call_and_wait().await?;
+    Ok(Decode!(&bytes, TransferRet)?)
+  }
+  /// Update method `transfer_with_payment`.
+  pub async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()> {
+    let args = Encode!(&arg0)?;
+    let bytes = self.1.update(&self.0, "transfer_with_payment").with_arg(args).call_and_wait().await?;
+    Ok(Decode!(&bytes)?)
+  }
+  /// Query method `type`.
+  pub async fn r#type(&self) -> Result<Kind> {
+    let args = Encode!()?;
+    let bytes = self.1.query(&self.0, "type").with_arg(args).call().await?;
+    Ok(Decode!(&bytes, Kind)?)
+  }
+}
+#[allow(async_fn_in_trait)]
+pub trait ExampleApi {
+  /// Query method `balance`.
+  async fn balance(&self, arg0: Account) -> Result<Tokens>;
+  /// Composite query method `count`.
+  async fn count(&self) -> Result<u64>;
+  /// Query method `depth`.
+  async fn depth(&self, arg0: Tree) -> Result<u32>;
+  /// One-way method `log`: the call is sent without waiting for a reply.
+  async fn log(&self, arg0: String) -> Result<()>;
+  /// Update method `subscribe`.
+  async fn subscribe(&self, arg0: Callback) -> Result<()>;
+  /// Update method `transfer`.
+  async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    TransferRet
+  >;
+  /// Update method `transfer_with_payment`.
+  async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()>;
+  /// Query method `type`.
+  async fn r#type(&self) -> Result<Kind>;
+}
+impl<'a> ExampleApi for Example<'a> {
+  async fn balance(&self, arg0: Account) -> Result<Tokens> {
+    Example::balance(self, arg0).await
+  }
+  async fn count(&self) -> Result<u64> { Example::count(self).await }
+  async fn depth(&self, arg0: Tree) -> Result<u32> {
+    Example::depth(self, arg0).await
+  }
+  async fn log(&self, arg0: String) -> Result<()> {
+    Example::log(self, arg0).await
+  }
+  async fn subscribe(&self, arg0: Callback) -> Result<()> {
+    Example::subscribe(self, arg0).await
+  }
+  async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    TransferRet
+  > { Example::transfer(self, arg0, arg1).await }
+  async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()> {
+    Example::transfer_with_payment(self, arg0).await
+  }
+  async fn r#type(&self) -> Result<Kind> { Example::r#type(self).await }
+}
+/// An in-memory [`ExampleApi`] for tests, answering with the responses set by its `on_*` methods.
+#[derive(Default)]
+pub struct MockExample {
+  balance: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(Account) -> Result<
+    Tokens
+  >>>>,
+  count: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<u64>>>>,
+  depth: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(Tree) -> Result<u32>>>>,
+  log: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(String) -> Result<()>>>>,
+  subscribe: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(Callback) -> Result<
+    ()
+  >>>>,
+  transfer: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(
+    Account, candid::Nat
+  ) -> Result<TransferRet>>>>,
+  transfer_with_payment: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(
+    candid::Nat
+  ) -> Result<()>>>>,
+  r#type: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<Kind>>>>,
+}
+impl MockExample {
+  pub fn new() -> Self { Self::default() }
+  /// Sets the response of `balance`.
+  pub fn on_balance(&self, f: impl Fn(Account) -> Result<
+    Tokens
+  > + 'static) -> &Self {
+    *self.balance.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `count`.
+  pub fn on_count(&self, f: impl Fn() -> Result<u64> + 'static) -> &Self {
+    *self.count.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `depth`.
+  pub fn on_depth(&self, f: impl Fn(Tree) -> Result<u32> + 'static) -> &Self {
+    *self.depth.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `log`.
+  pub fn on_log(&self, f: impl Fn(String) -> Result<()> + 'static) -> &Self {
+    *self.log.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `subscribe`.
+  pub fn on_subscribe(&self, f: impl Fn(Callback) -> Result<
+    ()
+  > + 'static) -> &Self {
+    *self.subscribe.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `transfer`.
+  pub fn on_transfer(&self, f: impl Fn(Account, candid::Nat) -> Result<
+    TransferRet
+  > + 'static) -> &Self {
+    *self.transfer.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `transfer_with_payment`.
+  pub fn on_transfer_with_payment(&self, f: impl Fn(candid::Nat) -> Result<
+    ()
+  > + 'static) -> &Self {
+    *self.transfer_with_payment.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `type`.
+  pub fn on_type(&self, f: impl Fn() -> Result<Kind> + 'static) -> &Self {
+    *self.r#type.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+}
+impl ExampleApi for MockExample {
+  async fn balance(&self, arg0: Account) -> Result<Tokens> {
+    let f = self.balance.borrow().clone().expect("MockExample: no response set for `balance`");
+    f(arg0)
+  }
+  async fn count(&self) -> Result<u64> {
+    let f = self.count.borrow().clone().expect("MockExample: no response set for `count`");
+    f()
+  }
+  async fn depth(&self, arg0: Tree) -> Result<u32> {
+    let f = self.depth.borrow().clone().expect("MockExample: no response set for `depth`");
+    f(arg0)
+  }
+  async fn log(&self, arg0: String) -> Result<()> {
+    let f = self.log.borrow().clone().expect("MockExample: no response set for `log`");
+    f(arg0)
+  }
+  async fn subscribe(&self, arg0: Callback) -> Result<()> {
+    let f = self.subscribe.borrow().clone().expect("MockExample: no response set for `subscribe`");
+    f(arg0)
+  }
+  async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    TransferRet
+  > {
+    let f = self.transfer.borrow().clone().expect("MockExample: no response set for `transfer`");
+    f(arg0, arg1)
+  }
+  async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()> {
+    let f = self.transfer_with_payment.borrow().clone().expect("MockExample: no response set for `transfer_with_payment`");
+    f(arg0)
+  }
+  async fn r#type(&self) -> Result<Kind> {
+    let f = self.r#type.borrow().clone().expect("MockExample: no response set for `type`");
+    f()
+  }
+}
@@ -0,0 +1,447 @@
+// This is an experimental feature to generate Rust binding from Candid.
+// You may want to manually adjust some of the types.
+#![allow(dead_code, unused_imports)]
+use candid::{self, CandidType, Deserialize, Principal, Encode, Decode};
+use ic_cdk::api::call::CallResult as Result;
+
+#[derive(CandidType, Deserialize)]
+pub struct Init { pub owner: Principal }
+
+#[derive(CandidType, Deserialize)]
+pub struct Account {
+  pub owner: Principal,
+  pub subaccount: Option<serde_bytes::ByteBuf>,
+}
+
+pub type Tokens = candid::Nat;
+#[derive(CandidType, Deserialize)]
+pub enum Tree {
+  #[serde(rename="leaf")]
+  Leaf(candid::Nat),
+  #[serde(rename="node")]
+  Node{ left: Box<Tree>, right: Box<Tree> },
+}
+
+candid::define_function!(pub Callback : (candid::Nat) -> ());
+#[derive(CandidType, Deserialize)]
+pub enum TransferRet { Ok(candid::Nat), Err(String) }
+
+#[derive(CandidType, Deserialize)]
+pub enum Kind {
+  #[serde(rename="a")]
+  A,
+  #[serde(rename="d")]
+  D{ x: i8 },
+  #[serde(rename="b_c")]
+  BC(candid::Nat),
+}
+
+pub struct Example(pub Principal);
+impl Example {
+  /// Query method `balance`.
+  pub async fn balance(&self, arg0: Account) -> Result<(Tokens,)> {
+    ic_cdk::call(self.0, "balance", (arg0,)).await
+  }
+  /// Query method `balance`, attaching `cycles` to the call.
+  pub async fn balance_with_payment(
+    &self,
+    arg0: Account,
+    cycles: u128,
+  ) -> Result<(Tokens,)> {
+    ic_cdk::api::call::call_with_payment128(self.0, "balance", (
+      arg0,
+    ), cycles).await
+  }
+  /// Composite query method `count`.
+  pub async fn count(&self) -> Result<(u64,)> {
+    ic_cdk::call(self.0, "count", ()).await
+  }
+  /// Composite query method `count`, attaching `cycles` to the call.
+  pub async fn count_with_payment(&self, cycles: u128) -> Result<(u64,)> {
+    ic_cdk::api::call::call_with_payment128(self.0, "count", (), cycles).await
+  }
+  /// Query method `depth`.
+  pub async fn depth(&self, arg0: Tree) -> Result<(u32,)> {
+    ic_cdk::call(self.0, "depth", (arg0,)).await
+  }
+  /// Query method `depth`, attaching `cycles` to the call.
+  pub async fn depth_with_payment(&self, arg0: Tree, cycles: u128) -> Result<
+    (u32,)
+  > {
+    ic_cdk::api::call::call_with_payment128(self.0, "depth", (
+      arg0,
+    ), cycles).await
+  }
+  /// One-way method `log`: the call is sent without waiting for a reply.
+  pub fn log(
+    &self,
+    arg0: String,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode> {
+    ic_cdk::notify(self.0, "log", (arg0,))
+  }
+  /// One-way method `log`, attaching `cycles` to the call.
+  pub fn log_with_payment(
+    &self,
+    arg0: String,
+    cycles: u128,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode> {
+    ic_cdk::api::call::notify_with_payment128(self.0, "log", (arg0,), cycles)
+  }
+  /// Update method `subscribe`.
+  pub async fn subscribe(&self, arg0: Callback) -> Result<()> {
+    ic_cdk::call(self.0, "subscribe", (arg0,)).await
+  }
+  /// Update method `subscribe`, attaching `cycles` to the call.
+  pub async fn subscribe_with_payment(
+    &self,
+    arg0: Callback,
+    cycles: u128,
+  ) -> Result<()> {
+    ic_cdk::api::call::call_with_payment128(self.0, "subscribe", (
+      arg0,
+    ), cycles).await
+  }
+  /// Update method `transfer`.
+  pub async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    (TransferRet,)
+  > { ic_cdk::call(self.0, "transfer", (arg0,arg1,)).await }
+  /// Update method `transfer`, attaching `cycles` to the call.
+  pub async fn transfer_with_payment_(
+    &self,
+    arg0: Account,
+    arg1: candid::Nat,
+    cycles: u128,
+  ) -> Result<(TransferRet,)> {
+    ic_cdk::api::call::call_with_payment128(self.0, "transfer", (
+      arg0,arg1,
+    ), cycles).await
+  }
+  /// Update method `transfer_with_payment`.
+  pub async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()> {
+    ic_cdk::call(self.0, "transfer_with_payment", (arg0,)).await
+  }
+  /// Update method `transfer_with_payment`, attaching `cycles` to the call.
+  pub async fn transfer_with_payment_with_payment(
+    &self,
+    arg0: candid::Nat,
+    cycles: u128,
+  ) -> Result<()> {
+    ic_cdk::api::call::call_with_payment128(self.0, "transfer_with_payment", (
+      arg0,
+    ), cycles).await
+  }
+  /// Query method `type`.
+  pub async fn r#type(&self) -> Result<(Kind,)> {
+    ic_cdk::call(self.0, "type", ()).await
+  }
+  /// Query method `type`, attaching `cycles` to the call.
+  pub async fn type_with_payment(&self, cycles: u128) -> Result<(Kind,)> {
+    ic_cdk::api::call::call_with_payment128(self.0, "type", (), cycles).await
+  }
+}
+#[allow(async_fn_in_trait)]
+pub trait ExampleApi {
+  /// Query method `balance`.
+  async fn balance(&self, arg0: Account) -> Result<(Tokens,)>;
+  /// Query method `balance`, attaching `cycles` to the call.
+  async fn balance_with_payment(&self, arg0: Account, cycles: u128) -> Result<
+    (Tokens,)
+  >;
+  /// Composite query method `count`.
+  async fn count(&self) -> Result<(u64,)>;
+  /// Composite query method `count`, attaching `cycles` to the call.
+  async fn count_with_payment(&self, cycles: u128) -> Result<(u64,)>;
+  /// Query method `depth`.
+  async fn depth(&self, arg0: Tree) -> Result<(u32,)>;
+  /// Query method `depth`, attaching `cycles` to the call.
+  async fn depth_with_payment(&self, arg0: Tree, cycles: u128) -> Result<
+    (u32,)
+  >;
+  /// One-way method `log`: the call is sent without waiting for a reply.
+  fn log(
+    &self,
+    arg0: String,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode>;
+  /// One-way method `log`, attaching `cycles` to the call.
+  fn log_with_payment(
+    &self,
+    arg0: String,
+    cycles: u128,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode>;
+  /// Update method `subscribe`.
+  async fn subscribe(&self, arg0: Callback) -> Result<()>;
+  /// Update method `subscribe`, attaching `cycles` to the call.
+  async fn subscribe_with_payment(
+    &self,
+    arg0: Callback,
+    cycles: u128,
+  ) -> Result<()>;
+  /// Update method `transfer`.
+  async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    (TransferRet,)
+  >;
+  /// Update method `transfer`, attaching `cycles` to the call.
+  async fn transfer_with_payment_(
+    &self,
+    arg0: Account,
+    arg1: candid::Nat,
+    cycles: u128,
+  ) -> Result<(TransferRet,)>;
+  /// Update method `transfer_with_payment`.
+  async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()>;
+  /// Update method `transfer_with_payment`, attaching `cycles` to the call.
+  async fn transfer_with_payment_with_payment(
+    &self,
+    arg0: candid::Nat,
+    cycles: u128,
+  ) -> Result<()>;
+  /// Query method `type`.
+  async fn r#type(&self) -> Result<(Kind,)>;
+  /// Query method `type`, attaching `cycles` to the call.
+  async fn type_with_payment(&self, cycles: u128) -> Result<(Kind,)>;
+}
+impl ExampleApi for Example {
+  async fn balance(&self, arg0: Account) -> Result<(Tokens,)> {
+    Example::balance(self, arg0).await
+  }
+  async fn balance_with_payment(&self, arg0: Account, cycles: u128) -> Result<
+    (Tokens,)
+  > { Example::balance_with_payment(self, arg0, cycles).await }
+  async fn count(&self) -> Result<(u64,)> { Example::count(self).await }
+  async fn count_with_payment(&self, cycles: u128) -> Result<(u64,)> {
+    Example::count_with_payment(self, cycles).await
+  }
+  async fn depth(&self, arg0: Tree) -> Result<(u32,)> {
+    Example::depth(self, arg0).await
+  }
+  async fn depth_with_payment(&self, arg0: Tree, cycles: u128) -> Result<
+    (u32,)
+  > { Example::depth_with_payment(self, arg0, cycles).await }
+  fn log(
+    &self,
+    arg0: String,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode> {
+    Example::log(self, arg0)
+  }
+  fn log_with_payment(
+    &self,
+    arg0: String,
+    cycles: u128,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode> {
+    Example::log_with_payment(self, arg0, cycles)
+  }
+  async fn subscribe(&self, arg0: Callback) -> Result<()> {
+    Example::subscribe(self, arg0).await
+  }
+  async fn subscribe_with_payment(
+    &self,
+    arg0: Callback,
+    cycles: u128,
+  ) -> Result<()> { Example::subscribe_with_payment(self, arg0, cycles).await }
+  async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    (TransferRet,)
+  > { Example::transfer(self, arg0, arg1).await }
+  async fn transfer_with_payment_(
+    &self,
+    arg0: Account,
+    arg1: candid::Nat,
+    cycles: u128,
+  ) -> Result<(TransferRet,)> {
+    Example::transfer_with_payment_(self, arg0, arg1, cycles).await
+  }
+  async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()> {
+    Example::transfer_with_payment(self, arg0).await
+  }
+  async fn transfer_with_payment_with_payment(
+    &self,
+    arg0: candid::Nat,
+    cycles: u128,
+  ) -> Result<()> {
+    Example::transfer_with_payment_with_payment(self, arg0, cycles).await
+  }
+  async fn r#type(&self) -> Result<(Kind,)> { Example::r#type(self).await }
+  async fn type_with_payment(&self, cycles: u128) -> Result<(Kind,)> {
+    Example::type_with_payment(self, cycles).await
+  }
+}
+/// An in-memory [`ExampleApi`] for tests, answering with the responses set by its `on_*` methods.
+#[derive(Default)]
+pub struct MockExample {
+  balance: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(Account) -> Result<
+    (Tokens,)
+  >>>>,
+  count: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<(u64,)>>>>,
+  depth: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(Tree) -> Result<
+    (u32,)
+  >>>>,
+  log: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(
+    String
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode>>>>,
+  subscribe: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(Callback) -> Result<
+    ()
+  >>>>,
+  transfer: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(
+    Account, candid::Nat
+  ) -> Result<(TransferRet,)>>>>,
+  transfer_with_payment: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(
+    candid::Nat
+  ) -> Result<()>>>>,
+  r#type: std::cell::RefCell<Option<std::rc::Rc<dyn Fn() -> Result<(Kind,)>>>>,
+}
+impl MockExample {
+  pub fn new() -> Self { Self::default() }
+  /// Sets the response of `balance`.
+  pub fn on_balance(&self, f: impl Fn(Account) -> Result<
+    (Tokens,)
+  > + 'static) -> &Self {
+    *self.balance.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `count`.
+  pub fn on_count(&self, f: impl Fn() -> Result<(u64,)> + 'static) -> &Self {
+    *self.count.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `depth`.
+  pub fn on_depth(&self, f: impl Fn(Tree) -> Result<
+    (u32,)
+  > + 'static) -> &Self {
+    *self.depth.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `log`.
+  pub fn on_log(&self, f: impl Fn(
+    String
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode> + 'static) -> &Self {
+    *self.log.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `subscribe`.
+  pub fn on_subscribe(&self, f: impl Fn(Callback) -> Result<
+    ()
+  > + 'static) -> &Self {
+    *self.subscribe.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `transfer`.
+  pub fn on_transfer(&self, f: impl Fn(Account, candid::Nat) -> Result<
+    (TransferRet,)
+  > + 'static) -> &Self {
+    *self.transfer.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `transfer_with_payment`.
+  pub fn on_transfer_with_payment(&self, f: impl Fn(candid::Nat) -> Result<
+    ()
+  > + 'static) -> &Self {
+    *self.transfer_with_payment.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+  /// Sets the response of `type`.
+  pub fn on_type(&self, f: impl Fn() -> Result<(Kind,)> + 'static) -> &Self {
+    *self.r#type.borrow_mut() = Some(std::rc::Rc::new(f));
+    self
+  }
+}
+impl ExampleApi for MockExample {
+  async fn balance(&self, arg0: Account) -> Result<(Tokens,)> {
+    let f = self.balance.borrow().clone().expect("MockExample: no response set for `balance`");
+    f(arg0)
+  }
+  async fn balance_with_payment(&self, arg0: Account, cycles: u128) -> Result<
+    (Tokens,)
+  > {
+    let _ = cycles;
+    let f = self.balance.borrow().clone().expect("MockExample: no response set for `balance`");
+    f(arg0)
+  }
+  async fn count(&self) -> Result<(u64,)> {
+    let f = self.count.borrow().clone().expect("MockExample: no response set for `count`");
+    f()
+  }
+  async fn count_with_payment(&self, cycles: u128) -> Result<(u64,)> {
+    let _ = cycles;
+    let f = self.count.borrow().clone().expect("MockExample: no response set for `count`");
+    f()
+  }
+  async fn depth(&self, arg0: Tree) -> Result<(u32,)> {
+    let f = self.depth.borrow().clone().expect("MockExample: no response set for `depth`");
+    f(arg0)
+  }
+  async fn depth_with_payment(&self, arg0: Tree, cycles: u128) -> Result<
+    (u32,)
+  > {
+    let _ = cycles;
+    let f = self.depth.borrow().clone().expect("MockExample: no response set for `depth`");
+    f(arg0)
+  }
+  fn log(
+    &self,
+    arg0: String,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode> {
+    let f = self.log.borrow().clone().expect("MockExample: no response set for `log`");
+    f(arg0)
+  }
+  fn log_with_payment(
+    &self,
+    arg0: String,
+    cycles: u128,
+  ) -> std::result::Result<(), ic_cdk::api::call::RejectionCode> {
+    let _ = cycles;
+    let f = self.log.borrow().clone().expect("MockExample: no response set for `log`");
+    f(arg0)
+  }
+  async fn subscribe(&self, arg0: Callback) -> Result<()> {
+    let f = self.subscribe.borrow().clone().expect("MockExample: no response set for `subscribe`");
+    f(arg0)
+  }
+  async fn subscribe_with_payment(
+    &self,
+    arg0: Callback,
+    cycles: u128,
+  ) -> Result<()> {
+    let _ = cycles;
+    let f = self.subscribe.borrow().clone().expect("MockExample: no response set for `subscribe`");
+    f(arg0)
+  }
+  async fn transfer(&self, arg0: Account, arg1: candid::Nat) -> Result<
+    (TransferRet,)
+  > {
+    let f = self.transfer.borrow().clone().expect("MockExample: no response set for `transfer`");
+    f(arg0, arg1)
+  }
+  async fn transfer_with_payment_(
+    &self,
+    arg0: Account,
+    arg1: candid::Nat,
+    cycles: u128,
+  ) -> Result<(TransferRet,)> {
+    let _ = cycles;
+    let f = self.transfer.borrow().clone().expect("MockExample: no response set for `transfer`");
+    f(arg0, arg1)
+  }
+  async fn transfer_with_payment(&self, arg0: candid::Nat) -> Result<()> {
+    let f = self.transfer_with_payment.borrow().clone().expect("MockExample: no response set for `transfer_with_payment`");
+    f(arg0)
+  }
+  async fn transfer_with_payment_with_payment(
+    &self,
+    arg0: candid::Nat,
+    cycles: u128,
+  ) -> Result<()> {
+    let _ = cycles;
+    let f = self.transfer_with_payment.borrow().clone().expect("MockExample: no response set for `transfer_with_payment`");
+    f(arg0)
+  }
+  async fn r#type(&self) -> Result<(Kind,)> {
+    let f = self.r#type.borrow().clone().expect("MockExample: no response set for `type`");
+    f()
+  }
+  async fn type_with_payment(&self, cycles: u128) -> Result<(Kind,)> {
+    let _ = cycles;
+    let f = self.r#type.borrow().clone().expect("MockExample: no response set for `type`");
+    f()
+  }
+}
+pub const CANISTER_ID : Principal = Principal::from_slice(&[0, 0, 0, 0, 0, 0, 0, 2, 1, 1]); // ryjl3-tyaaa-aaaaa-aaaba-cai
+pub const example : Example = Example(CANISTER_ID);
\ No newline at end of file
@@ -0,0 +1,70 @@
+// This is an experimental feature to generate Rust binding from Candid.
+// You may want to manually adjust some of the types.
+#![allow(dead_code, unused_imports)]
+use candid::{self, CandidType, Deserialize, Principal, Encode, Decode};
+
+#[derive(CandidType, Deserialize)]
+pub struct Init { pub owner: Principal }
+
+#[derive(CandidType, Deserialize)]
+pub struct Account {
+  pub owner: Principal,
+  pub subaccount: Option<serde_bytes::ByteBuf>,
+}
+
+pub type Tokens = candid::Nat;
+#[derive(CandidType, Deserialize)]
+pub enum Tree {
+  #[serde(rename="leaf")]
+  Leaf(candid::Nat),
+  #[serde(rename="node")]
+  Node{ left: Box<Tree>, right: Box<Tree> },
+}
+
+candid::define_function!(pub Callback : (candid::Nat) -> ());
+#[derive(CandidType, Deserialize)]
+pub enum TransferRet { Ok(candid::Nat), Err(String) }
+
+#[derive(CandidType, Deserialize)]
+pub enum Kind {
+  #[serde(rename="a")]
+  A,
+  #[serde(rename="d")]
+  D{ x: i8 },
+  #[serde(rename="b_c")]
+  BC(candid::Nat),
+}
+
+#[allow(async_fn_in_trait)]
+pub trait Example {
+  fn balance(arg0: Account) -> Tokens;
+  async fn count() -> u64;
+  fn depth(arg0: Tree) -> u32;
+  async fn log(arg0: String);
+  async fn subscribe(arg0: Callback);
+  async fn transfer(arg0: Account, arg1: candid::Nat) -> TransferRet;
+  async fn transfer_with_payment(arg0: candid::Nat);
+  fn r#type() -> Kind;
+}
+#[ic_cdk::query(name = "balance")]
+fn balance(arg0: Account) -> Tokens { <Canister as Example>::balance(arg0) }
+#[ic_cdk::query(composite = true, name = "count")]
+async fn count() -> u64 { <Canister as Example>::count().await }
+#[ic_cdk::query(name = "depth")]
+fn depth(arg0: Tree) -> u32 { <Canister as Example>::depth(arg0) }
+#[ic_cdk::update(name = "log")]
+async fn log(arg0: String) { <Canister as Example>::log(arg0).await }
+#[ic_cdk::update(name = "subscribe")]
+async fn subscribe(arg0: Callback) {
+  <Canister as Example>::subscribe(arg0).await
+}
+#[ic_cdk::update(name = "transfer")]
+async fn transfer(arg0: Account, arg1: candid::Nat) -> TransferRet {
+  <Canister as Example>::transfer(arg0, arg1).await
+}
+#[ic_cdk::update(name = "transfer_with_payment")]
+async fn transfer_with_payment(arg0: candid::Nat) {
+  <Canister as Example>::transfer_with_payment(arg0).await
+}
+#[ic_cdk::query(name = "type")]
+fn r#type() -> Kind { <Canister as Example>::r#type() }
@@ -1,12 +1,17 @@
-use proc_macro2::{Ident, Span, TokenStream};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
 use quote::{format_ident, quote};
 use serde::Deserialize;
 use serde_tokenstream::from_tokenstream;
 use std::fmt::Formatter;
-use syn::Error;
-use syn::{spanned::Spanned, FnArg, ItemFn, Pat, PatIdent, PatType, ReturnType, Signature, Type};
+use syn::fold::Fold;
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    ext::IdentExt, spanned::Spanned, FnArg, ItemFn, Pat, PatIdent, PatType, ReturnType, Signature,
+    Type,
+};
+use syn::{Error, GenericParam, LitStr, Token, TypePath};
 
-#[derive(Default, Deserialize)]
+#[derive(Clone, Default, Deserialize)]
 struct ExportAttributes {
     pub name: Option<String>,
     pub guard: Option<String>,
@@ -24,6 +29,24 @@ struct ExportAttributes {
     pub debug: bool,
 }
 
+/// The features of this crate that change the generated code.
+///
+/// They are passed along explicitly, so that the tests can check the code generated with and
+/// without each feature, whichever features the crate is built with.
+#[derive(Copy, Clone, Debug, Default)]
+struct Features {
+    metrics: bool,
+    method_context: bool,
+}
+
+impl Features {
+    /// The features this crate is built with.
+    const ENABLED: Self = Self {
+        metrics: cfg!(feature = "metrics"),
+        method_context: cfg!(feature = "method-context"),
+    };
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum MethodType {
     Init,
@@ -101,11 +124,122 @@ fn get_args(method: MethodType, signature: &Signature) -> Result<Vec<(Ident, Box
     Ok(args)
 }
 
+/// A concrete instantiation of a generic method, e.g. `instantiate(name = "get_u64", T = u64)`.
+struct Instantiation {
+    name: LitStr,
+    types: Vec<(Ident, Type)>,
+}
+
+impl Parse for Instantiation {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut name = None;
+        let mut types = vec![];
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            if key == "name" {
+                if name.is_some() {
+                    return Err(Error::new(key.span(), "duplicate `name` in `instantiate`."));
+                }
+                name = Some(input.parse::<LitStr>()?);
+            } else {
+                if types.iter().any(|(param, _): &(Ident, Type)| *param == key) {
+                    return Err(Error::new(
+                        key.span(),
+                        format!("duplicate type parameter `{key}` in `instantiate`."),
+                    ));
+                }
+                types.push((key, input.parse::<Type>()?));
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        let name = name.ok_or_else(|| {
+            Error::new(
+                input.span(),
+                "`instantiate` requires a `name` for the exported method.",
+            )
+        })?;
+        Ok(Self { name, types })
+    }
+}
+
+/// Splits the `instantiate(...)` entries out of the attribute arguments.
+///
+/// The remaining arguments are returned as-is so that they can be deserialized into [`ExportAttributes`].
+fn split_instantiations(attr: TokenStream) -> Result<(TokenStream, Vec<Instantiation>), Error> {
+    let mut entries: Vec<Vec<TokenTree>> = vec![vec![]];
+    for tt in attr {
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => entries.push(vec![]),
+            _ => entries.last_mut().unwrap().push(tt),
+        }
+    }
+
+    let mut rest = TokenStream::new();
+    let mut instantiations = vec![];
+    for entry in entries {
+        match entry.as_slice() {
+            [TokenTree::Ident(ident), TokenTree::Group(group)]
+                if ident == "instantiate" && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                instantiations.push(syn::parse2::<Instantiation>(group.stream())?);
+            }
+            [] => {}
+            _ => {
+                if !rest.is_empty() {
+                    rest.extend(quote! { , });
+                }
+                rest.extend(entry);
+            }
+        }
+    }
+    Ok((rest, instantiations))
+}
+
+/// Replaces the generic type parameters with the concrete types of an [`Instantiation`].
+///
+/// A path starting with a type parameter, e.g. `T::Output`, has no concrete equivalent without
+/// its trait: it is reported in `error`, and must be written `<T as Trait>::Output` instead.
+struct SubstituteTypes<'a> {
+    types: &'a [(Ident, Type)],
+    error: Option<Error>,
+}
+
+impl Fold for SubstituteTypes<'_> {
+    fn fold_type(&mut self, ty: Type) -> Type {
+        if let Type::Path(TypePath { qself: None, path }) = &ty {
+            let param = path
+                .segments
+                .first()
+                .and_then(|segment| self.types.iter().find(|(param, _)| *param == segment.ident));
+            if let (None, Some((param, concrete))) = (path.leading_colon, param) {
+                if path.segments.len() == 1 {
+                    return concrete.clone();
+                }
+                self.error.get_or_insert_with(|| {
+                    Error::new(
+                        path.span(),
+                        format!(
+                            "cannot instantiate a path starting with `{param}`, write it as `<{param} as Trait>::...`."
+                        ),
+                    )
+                });
+            }
+        }
+        syn::fold::fold_type(self, ty)
+    }
+}
+
 fn dfn_macro(
     method: MethodType,
     attr: TokenStream,
     item: TokenStream,
+    features: Features,
 ) -> Result<TokenStream, Error> {
+    let (attr, instantiations) = split_instantiations(attr)?;
     let attrs = from_tokenstream::<ExportAttributes>(&attr)
         .map_err(|e| Error::new(attr.span(), format!("Failed to deserialize {attr}. \n{e}")))?;
 
@@ -118,6 +252,10 @@ fn dfn_macro(
     let signature = &fun.sig;
     let generics = &signature.generics;
 
+    if !instantiations.is_empty() {
+        return instantiate_generic(method, attrs, &attr, &fun, instantiations, features);
+    }
+
     if !generics.params.is_empty() {
         return Err(Error::new(
             generics.span(),
@@ -128,6 +266,158 @@ fn dfn_macro(
         ));
     }
 
+    export_function(method, attrs, &attr, &fun, item, features)
+}
+
+/// Exports a generic function once per instantiation.
+///
+/// Every instantiation gets a non-generic wrapper, with the type parameters substituted, which is
+/// then exported like any other function under the name given in the instantiation. The wrapper is
+/// named after the generic function and the position of the instantiation, as exported names that
+/// only differ in characters invalid in identifiers (e.g. `get-u64` and `get_u64`) must not clash.
+fn instantiate_generic(
+    method: MethodType,
+    attrs: ExportAttributes,
+    attr: &TokenStream,
+    fun: &ItemFn,
+    instantiations: Vec<Instantiation>,
+    features: Features,
+) -> Result<TokenStream, Error> {
+    let signature = &fun.sig;
+    let generics = &signature.generics;
+
+    if method.is_lifecycle() {
+        return Err(Error::new(
+            attr.span(),
+            format!("#[{}] cannot be instantiated.", method),
+        ));
+    }
+    if generics.params.is_empty() {
+        return Err(Error::new(
+            attr.span(),
+            format!(
+                "#[{}] can only instantiate functions with generic parameters.",
+                method
+            ),
+        ));
+    }
+    if attrs.name.is_some() {
+        return Err(Error::new(
+            attr.span(),
+            format!(
+                "#[{}] cannot have a `name` when using `instantiate`, set it for each instantiation instead.",
+                method
+            ),
+        ));
+    }
+
+    let mut type_params = vec![];
+    for param in generics.params.iter() {
+        match param {
+            GenericParam::Type(ty) => type_params.push(&ty.ident),
+            _ => {
+                return Err(Error::new(
+                    param.span(),
+                    format!("#[{}] can only instantiate type parameters.", method),
+                ))
+            }
+        }
+    }
+
+    let args = get_args(method, signature)?;
+    let name = &signature.ident;
+    let mut names: Vec<String> = vec![];
+    let mut result = TokenStream::new();
+    for (index, instantiation) in instantiations.into_iter().enumerate() {
+        let function_name = instantiation.name.value();
+        if names.contains(&function_name) {
+            return Err(Error::new(
+                instantiation.name.span(),
+                format!("duplicate instantiation name `{}`.", function_name),
+            ));
+        }
+        if let Some((param, _)) = instantiation
+            .types
+            .iter()
+            .find(|(param, _)| !type_params.contains(&param))
+        {
+            return Err(Error::new(
+                param.span(),
+                format!("`{}` is not a type parameter of `{}`.", param, name),
+            ));
+        }
+
+        let mut substitute = SubstituteTypes {
+            types: &instantiation.types,
+            error: None,
+        };
+        let mut concrete_types = vec![];
+        for param in type_params.iter() {
+            match instantiation.types.iter().find(|(p, _)| p == *param) {
+                Some((_, ty)) => concrete_types.push(ty),
+                None => {
+                    return Err(Error::new(
+                        instantiation.name.span(),
+                        format!("missing type parameter `{}` in `instantiate`.", param),
+                    ))
+                }
+            }
+        }
+
+        let wrapper_ident = format_ident!("__canister_instance_{}_{index}", name.unraw());
+        let wrapper_args: Vec<_> = args
+            .iter()
+            .map(|(ident, ty)| {
+                let ty = substitute.fold_type(ty.as_ref().clone());
+                quote! { #ident: #ty }
+            })
+            .collect();
+        let arg_idents = args.iter().map(|(ident, _)| ident);
+        let output = substitute.fold_return_type(signature.output.clone());
+        if let Some(error) = substitute.error {
+            return Err(error);
+        }
+        let asyncness = &signature.asyncness;
+        let call = if asyncness.is_some() {
+            quote! { #name::<#(#concrete_types),*>(#(#arg_idents),*).await }
+        } else {
+            quote! { #name::<#(#concrete_types),*>(#(#arg_idents),*) }
+        };
+        let wrapper = quote! {
+            #asyncness fn #wrapper_ident(#(#wrapper_args),*) #output {
+                #call
+            }
+        };
+        let wrapper_fn = syn::parse2::<ItemFn>(wrapper.clone())?;
+
+        let attrs = ExportAttributes {
+            name: Some(function_name.clone()),
+            ..attrs.clone()
+        };
+        result.extend(export_function(
+            method,
+            attrs,
+            attr,
+            &wrapper_fn,
+            wrapper,
+            features,
+        )?);
+        names.push(function_name);
+    }
+
+    result.extend(quote! { #fun });
+    Ok(result)
+}
+
+fn export_function(
+    method: MethodType,
+    attrs: ExportAttributes,
+    attr: &TokenStream,
+    fun: &ItemFn,
+    item: TokenStream,
+    features: Features,
+) -> Result<TokenStream, Error> {
+    let signature = &fun.sig;
     let is_async = signature.asyncness.is_some();
 
     let return_length = match &signature.output {
@@ -149,9 +439,9 @@ fn dfn_macro(
         get_args(method, signature)?.iter().cloned().unzip();
     let name = &signature.ident;
 
-    let outer_function_ident = format_ident!("__canister_method_{name}");
+    let outer_function_ident = format_ident!("__canister_method_{}", name.unraw());
 
-    let function_name = attrs.name.unwrap_or_else(|| name.to_string());
+    let function_name = attrs.name.unwrap_or_else(|| name.unraw().to_string());
     let export_name = if method.is_lifecycle() {
         format!("canister_{}", method)
     } else if method == MethodType::Query && attrs.composite {
@@ -216,6 +506,44 @@ fn dfn_macro(
         quote! { let ( #( #arg_tuple, )* ) = ic_cdk::api::call::arg_data(#config); }
     };
 
+    // With the `metrics` feature, update and query methods record their calls, guard rejects,
+    // traps and instruction usage in the `ic_cdk::metrics` registry.
+    let record_metrics = features.metrics && !method.is_lifecycle();
+    let (metrics_call, metrics_reject, metrics_scope) = if record_metrics {
+        (
+            quote! { ic_cdk::metrics::record_call(#function_name); },
+            quote! { ic_cdk::metrics::record_reject(#function_name); },
+            quote! { let _metrics_scope = ic_cdk::metrics::CallScope::new(#function_name); },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    let body = quote! {
+        async {
+            #metrics_scope
+            #arg_decode
+            let result = #function_call;
+            #return_encode
+        }
+    };
+
+    // With the `method-context` feature, the executed method is tracked for
+    // `ic_cdk::api::call::current_method`, including across `await` points.
+    let (method_scope, body) = if features.method_context {
+        let method_name = if method.is_lifecycle() {
+            method.to_string()
+        } else {
+            function_name.clone()
+        };
+        (
+            quote! { let _method_scope = ic_cdk::api::call::enter_method(#method_name); },
+            quote! { ic_cdk::api::call::in_method(#method_name, #body) },
+        )
+    } else {
+        (quote! {}, body)
+    };
+
     let guard = if let Some(guard_name) = attrs.guard {
         // ic_cdk::api::call::reject calls ic0::msg_reject which is only allowed in update/query
         if method.is_lifecycle() {
@@ -229,6 +557,7 @@ fn dfn_macro(
         quote! {
             let r: Result<(), String> = #guard_ident ();
             if let Err(e) = r {
+                #metrics_reject
                 ic_cdk::api::call::reject(&e);
                 return;
             }
@@ -264,14 +593,12 @@ fn dfn_macro(
         #[cfg_attr(not(target_family = "wasm"), export_name = #host_compatible_name)]
         fn #outer_function_ident() {
             ic_cdk::setup();
+            #method_scope
+            #metrics_call
 
             #guard
 
-            ic_cdk::spawn(async {
-                #arg_decode
-                let result = #function_call;
-                #return_encode
-            });
+            ic_cdk::spawn(#body);
         }
 
         #item
@@ -279,50 +606,59 @@ fn dfn_macro(
 }
 
 pub(crate) fn ic_query(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::Query, attr, item)
+    dfn_macro(MethodType::Query, attr, item, Features::ENABLED)
 }
 
 pub(crate) fn ic_update(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::Update, attr, item)
+    dfn_macro(MethodType::Update, attr, item, Features::ENABLED)
 }
 
 #[derive(Default, Deserialize)]
 struct InitAttributes {}
 
 pub(crate) fn ic_init(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::Init, attr, item)
+    dfn_macro(MethodType::Init, attr, item, Features::ENABLED)
 }
 
 pub(crate) fn ic_pre_upgrade(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::PreUpgrade, attr, item)
+    dfn_macro(MethodType::PreUpgrade, attr, item, Features::ENABLED)
 }
 
 pub(crate) fn ic_post_upgrade(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::PostUpgrade, attr, item)
+    dfn_macro(MethodType::PostUpgrade, attr, item, Features::ENABLED)
 }
 
 pub(crate) fn ic_heartbeat(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::Heartbeat, attr, item)
+    dfn_macro(MethodType::Heartbeat, attr, item, Features::ENABLED)
 }
 
 pub(crate) fn ic_inspect_message(
     attr: TokenStream,
     item: TokenStream,
 ) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::InspectMessage, attr, item)
+    dfn_macro(MethodType::InspectMessage, attr, item, Features::ENABLED)
 }
 
 pub(crate) fn ic_on_low_wasm_memory(
     attr: TokenStream,
     item: TokenStream,
 ) -> Result<TokenStream, Error> {
-    dfn_macro(MethodType::OnLowWasmMemory, attr, item)
+    dfn_macro(MethodType::OnLowWasmMemory, attr, item, Features::ENABLED)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    // The code generated without the optional features, which shadows the exported macros.
+    fn ic_query(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
+        dfn_macro(MethodType::Query, attr, item, Features::default())
+    }
+
+    fn ic_update(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
+        dfn_macro(MethodType::Update, attr, item, Features::default())
+    }
+
     #[test]
     fn ic_query_empty() {
         let generated = ic_query(
@@ -367,6 +703,103 @@ mod test {
         };
     }
     #[test]
+    fn ic_update_metrics() {
+        let generated = dfn_macro(
+            MethodType::Update,
+            quote!(guard = "is_allowed"),
+            quote! {
+                fn update() {}
+            },
+            Features {
+                metrics: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let parsed = syn::parse2::<syn::File>(generated).unwrap();
+        let fn_name = match parsed.items[0] {
+            syn::Item::Fn(ref f) => &f.sig.ident,
+            _ => panic!("Incorrect parsed AST."),
+        };
+
+        let expected = quote! {
+            #[cfg_attr(target_family = "wasm", export_name = "canister_update update")]
+            #[cfg_attr(not(target_family = "wasm"), export_name = "canister_update.update")]
+            fn #fn_name() {
+                ic_cdk::setup();
+                ic_cdk::metrics::record_call("update");
+                let r: Result<(), String> = is_allowed();
+                if let Err(e) = r {
+                    ic_cdk::metrics::record_reject("update");
+                    ic_cdk::api::call::reject(&e);
+                    return;
+                }
+                ic_cdk::spawn(async {
+                    let _metrics_scope = ic_cdk::metrics::CallScope::new("update");
+                    let () = ic_cdk::api::call::arg_data(
+                        ic_cdk::api::call::ArgDecoderConfig {
+                            decoding_quota: None,
+                            skipping_quota: Some(10000usize),
+                            debug: false,
+                        }
+                    );
+                    let result = update();
+                    ic_cdk::api::call::reply(())
+                });
+            }
+        };
+        let expected = syn::parse2::<syn::ItemFn>(expected).unwrap();
+
+        assert!(parsed.items.len() == 2);
+        match &parsed.items[0] {
+            syn::Item::Fn(f) => {
+                assert_eq!(*f, expected);
+            }
+            _ => panic!("not a function"),
+        };
+    }
+    #[test]
+    fn ic_init_method_context() {
+        let generated = dfn_macro(
+            MethodType::Init,
+            quote!(),
+            quote! {
+                fn init() {}
+            },
+            Features {
+                method_context: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let parsed = syn::parse2::<syn::File>(generated).unwrap();
+        let fn_name = match parsed.items[0] {
+            syn::Item::Fn(ref f) => &f.sig.ident,
+            _ => panic!("Incorrect parsed AST."),
+        };
+
+        let expected = quote! {
+            #[cfg_attr(target_family = "wasm", export_name = "canister_init")]
+            #[cfg_attr(not(target_family = "wasm"), export_name = "canister_init")]
+            fn #fn_name() {
+                ic_cdk::setup();
+                let _method_scope = ic_cdk::api::call::enter_method("init");
+                ic_cdk::spawn(ic_cdk::api::call::in_method("init", async {
+                    let result = init();
+                }));
+            }
+        };
+        let expected = syn::parse2::<syn::ItemFn>(expected).unwrap();
+
+        assert!(parsed.items.len() == 2);
+        match &parsed.items[0] {
+            syn::Item::Fn(f) => {
+                assert_eq!(*f, expected);
+            }
+            _ => panic!("not a function"),
+        };
+    }
+    #[test]
     fn ic_query_return_one_value() {
         let generated = ic_query(
             quote!(),
@@ -629,4 +1062,209 @@ mod test {
             _ => panic!("not a function"),
         };
     }
+
+    #[test]
+    fn ic_query_raw_identifier() {
+        let generated = ic_query(
+            quote!(),
+            quote! {
+                fn r#type() {}
+            },
+        )
+        .unwrap();
+        let parsed = syn::parse2::<syn::File>(generated).unwrap();
+        let exported = match &parsed.items[0] {
+            syn::Item::Fn(f) => f,
+            _ => panic!("Incorrect parsed AST."),
+        };
+        assert_eq!(exported.sig.ident, "__canister_method_type");
+        assert!(quote!(#exported)
+            .to_string()
+            .contains("\"canister_query type\""));
+    }
+
+    #[test]
+    fn ic_query_instantiate() {
+        let generated = ic_query(
+            quote!(
+                instantiate(name = "get_u64", T = u64),
+                instantiate(name = "get_text", T = String)
+            ),
+            quote! {
+                fn get<T: Default>(key: u32) -> Option<T> {}
+            },
+        )
+        .unwrap();
+        let parsed = syn::parse2::<syn::File>(generated).unwrap();
+        assert_eq!(parsed.items.len(), 5);
+
+        let wrapper_name = match parsed.items[0] {
+            syn::Item::Fn(ref f) => &f.sig.ident,
+            _ => panic!("Incorrect parsed AST."),
+        };
+        let expected = quote! {
+            #[cfg_attr(target_family = "wasm", export_name = "canister_query get_u64")]
+            #[cfg_attr(not(target_family = "wasm"), export_name = "canister_query.get_u64")]
+            fn #wrapper_name() {
+                ic_cdk::setup();
+                ic_cdk::spawn(async {
+                    let (key, ) = ic_cdk::api::call::arg_data(
+                        ic_cdk::api::call::ArgDecoderConfig {
+                            decoding_quota: None,
+                            skipping_quota: Some(10000usize),
+                            debug: false,
+                        }
+                    );
+                    let result = __canister_instance_get_0(key);
+                    ic_cdk::api::call::reply((result,))
+                });
+            }
+        };
+        let expected = syn::parse2::<syn::ItemFn>(expected).unwrap();
+        match &parsed.items[0] {
+            syn::Item::Fn(f) => assert_eq!(*f, expected),
+            _ => panic!("not a function"),
+        };
+
+        let expected = quote! {
+            #[::candid::candid_method(query, rename = "get_u64")]
+            fn __canister_instance_get_0(key: u32) -> Option<u64> {
+                get::<u64>(key)
+            }
+        };
+        let expected = syn::parse2::<syn::ItemFn>(expected).unwrap();
+        match &parsed.items[1] {
+            syn::Item::Fn(f) => assert_eq!(*f, expected),
+            _ => panic!("not a function"),
+        };
+
+        let expected = quote! {
+            #[::candid::candid_method(query, rename = "get_text")]
+            fn __canister_instance_get_1(key: u32) -> Option<String> {
+                get::<String>(key)
+            }
+        };
+        let expected = syn::parse2::<syn::ItemFn>(expected).unwrap();
+        match &parsed.items[3] {
+            syn::Item::Fn(f) => assert_eq!(*f, expected),
+            _ => panic!("not a function"),
+        };
+
+        match &parsed.items[4] {
+            syn::Item::Fn(f) => assert_eq!(f.sig.ident, "get"),
+            _ => panic!("not a function"),
+        };
+    }
+
+    #[test]
+    fn ic_query_instantiate_similar_names() {
+        let generated = ic_query(
+            quote!(
+                instantiate(name = "get-u64", T = u64),
+                instantiate(name = "get_u64", T = u64)
+            ),
+            quote! {
+                fn get<T: Default>(key: u32) -> Option<T> {}
+            },
+        )
+        .unwrap();
+        let parsed = syn::parse2::<syn::File>(generated).unwrap();
+        let fns: Vec<_> = parsed
+            .items
+            .iter()
+            .map(|item| match item {
+                syn::Item::Fn(f) => f,
+                _ => panic!("not a function"),
+            })
+            .collect();
+        let idents: Vec<_> = fns.iter().map(|f| f.sig.ident.to_string()).collect();
+        assert_eq!(
+            idents,
+            [
+                "__canister_method___canister_instance_get_0",
+                "__canister_instance_get_0",
+                "__canister_method___canister_instance_get_1",
+                "__canister_instance_get_1",
+                "get",
+            ]
+        );
+
+        let export_name = |f: &syn::ItemFn| {
+            let attr = &f.attrs[0];
+            quote!(#attr).to_string()
+        };
+        assert!(export_name(fns[0]).contains("\"canister_query get-u64\""));
+        assert!(export_name(fns[2]).contains("\"canister_query get_u64\""));
+    }
+
+    #[test]
+    fn ic_update_instantiate_errors() {
+        let item = quote! {
+            fn put<K, V>(key: K, value: V) {}
+        };
+        assert!(ic_update(quote!(), item.clone()).is_err());
+        // Every type parameter must be instantiated.
+        assert!(ic_update(quote!(instantiate(name = "put", K = u64)), item.clone()).is_err());
+        // Only type parameters of the function can be instantiated.
+        assert!(ic_update(
+            quote!(instantiate(name = "put", K = u64, V = u64, W = u64)),
+            item.clone()
+        )
+        .is_err());
+        // Each instantiation needs its own name.
+        assert!(ic_update(quote!(instantiate(K = u64, V = u64)), item.clone()).is_err());
+        assert!(ic_update(
+            quote!(
+                instantiate(name = "put", K = u64, V = u64),
+                instantiate(name = "put", K = u32, V = u32)
+            ),
+            item.clone()
+        )
+        .is_err());
+        assert!(ic_update(
+            quote!(
+                name = "put",
+                instantiate(name = "put_u64", K = u64, V = u64)
+            ),
+            item
+        )
+        .is_err());
+        assert!(ic_update(
+            quote!(instantiate(name = "put_u64", K = u64, V = u64)),
+            quote! { fn put(key: u64, value: u64) {} }
+        )
+        .is_err());
+        // The trait of an associated type must be named.
+        let error = ic_update(
+            quote!(instantiate(name = "put_u64", K = u64)),
+            quote! { fn put<K: Storable>(key: K, value: K::Value) {} },
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("`<K as Trait>::...`"));
+    }
+
+    #[test]
+    fn ic_update_instantiate_qualified_paths() {
+        let generated = ic_update(
+            quote!(instantiate(name = "put_u64", K = u64)),
+            quote! {
+                fn put<K: Storable>(key: Vec<K>, value: <K as Storable>::Value) -> Option<K> {}
+            },
+        )
+        .unwrap();
+        let parsed = syn::parse2::<syn::File>(generated).unwrap();
+        let wrapper = parsed
+            .items
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Fn(f) if f.sig.ident == "__canister_instance_put_0" => Some(f),
+                _ => None,
+            })
+            .unwrap();
+        let expected = quote! {
+            fn __canister_instance_put_0(key: Vec<u64>, value: <u64 as Storable>::Value) -> Option<u64>
+        };
+        let signature = &wrapper.sig;
+        assert_eq!(quote!(#signature).to_string(), expected.to_string());
+    }
 }
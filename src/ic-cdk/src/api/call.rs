@@ -875,3 +875,91 @@ where
 pub fn is_recovering_from_trap() -> bool {
     crate::futures::CLEANUP.load(Ordering::Relaxed)
 }
+
+#[cfg(feature = "method-context")]
+thread_local! {
+    static CURRENT_METHOD: std::cell::Cell<Option<&'static str>> = const { std::cell::Cell::new(None) };
+}
+
+/// Returns the name of the exported method currently being executed, if known.
+///
+/// The name is tracked by the code generated by [`update`](crate::update), [`query`](crate::query)
+/// and the lifecycle macros, including across `await` points. Unlike [`method_name`], it can be
+/// used in any context.
+#[cfg(feature = "method-context")]
+#[cfg_attr(docsrs, doc(cfg(feature = "method-context")))]
+pub fn current_method() -> Option<&'static str> {
+    CURRENT_METHOD.with(|m| m.get())
+}
+
+/// Marks `method` as the method being executed until the returned scope is dropped.
+#[cfg(feature = "method-context")]
+#[doc(hidden)]
+pub fn enter_method(method: &'static str) -> MethodScope {
+    MethodScope {
+        previous: CURRENT_METHOD.with(|m| m.replace(Some(method))),
+    }
+}
+
+/// Restores the previously executed method on drop.
+#[cfg(feature = "method-context")]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct MethodScope {
+    previous: Option<&'static str>,
+}
+
+#[cfg(feature = "method-context")]
+impl Drop for MethodScope {
+    fn drop(&mut self) {
+        CURRENT_METHOD.with(|m| m.set(self.previous));
+    }
+}
+
+/// Wraps `future` so that `method` is marked as being executed whenever it is polled.
+#[cfg(feature = "method-context")]
+#[doc(hidden)]
+pub fn in_method<F: Future<Output = ()>>(method: &'static str, future: F) -> InMethod<F> {
+    InMethod {
+        method,
+        future: Box::pin(future),
+    }
+}
+
+/// Future returned by [`in_method`].
+#[cfg(feature = "method-context")]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InMethod<F> {
+    method: &'static str,
+    future: Pin<Box<F>>,
+}
+
+#[cfg(feature = "method-context")]
+impl<F: Future<Output = ()>> Future for InMethod<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let _scope = enter_method(self.method);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+#[cfg(all(test, feature = "method-context"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_scope() {
+        assert_eq!(current_method(), None);
+        {
+            let _outer = enter_method("outer");
+            {
+                let _inner = enter_method("inner");
+                assert_eq!(current_method(), Some("inner"));
+            }
+            assert_eq!(current_method(), Some("outer"));
+        }
+        assert_eq!(current_method(), None);
+    }
+}
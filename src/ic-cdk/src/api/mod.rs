@@ -9,6 +9,8 @@ pub mod stable;
 /// Prints the given message.
 pub fn print<S: std::convert::AsRef<str>>(s: S) {
     let s = s.as_ref();
+    #[cfg(feature = "log-buffer")]
+    crate::log_buffer::capture(s);
     // SAFETY: `s`, being &str, is a readable sequence of bytes and therefore can be passed to ic0.debug_print.
     unsafe {
         ic0::debug_print(s.as_ptr() as i32, s.len() as i32);
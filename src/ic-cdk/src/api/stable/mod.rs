@@ -4,7 +4,7 @@
 //! for a in-depth explanation of stable memory.
 mod canister;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 pub use canister::CanisterStableMemory;
 use std::{error, fmt, io};
@@ -14,7 +14,17 @@ compile_error!("This version of the CDK does not support multithreading.");
 
 pub mod api;
 mod futures;
+#[cfg(feature = "log")]
+#[cfg_attr(docsrs, doc(cfg(feature = "log")))]
+pub mod log;
+#[cfg(feature = "log-buffer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "log-buffer")))]
+pub mod log_buffer;
 mod macros;
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+pub mod metrics;
+pub mod panic_hook;
 mod printer;
 pub mod storage;
 
@@ -0,0 +1,275 @@
+//! Structured logging to the canister log.
+//!
+//! This module provides a [`log`](https://docs.rs/log) backend, and with the `tracing` feature a
+//! [`tracing`](https://docs.rs/tracing) subscriber, which write every record to the canister log
+//! through [`print`](crate::api::print).
+//!
+//! Each line carries the [`time`](crate::api::time), the level, the exported method being executed,
+//! the target, the message and the key/value fields of the record:
+//!
+//! ```text
+//! 2024-12-19T10:00:00.000000000Z INFO  transfer my_canister::ledger: sent tokens amount=100 to=aaaaa-aa
+//! ```
+//!
+//! The level filter can be changed at any time with [`set_level`], e.g. from a controller-only update.
+//! It is kept on the Wasm heap, so it has to be restored after an upgrade; [`level`] returns the value
+//! to persist.
+//!
+//! ```rust,ignore
+//! #[ic_cdk::init]
+//! fn init() {
+//!     ic_cdk::log::init(log::LevelFilter::Info);
+//! }
+//!
+//! #[ic_cdk::update]
+//! fn transfer(to: candid::Principal, amount: u64) {
+//!     log::info!(amount = amount, to:% = to; "sent tokens");
+//! }
+//! ```
+
+use crate::api::call::current_method;
+use std::cell::Cell;
+use std::fmt::{self, Write};
+
+pub use ::log::LevelFilter;
+
+thread_local! {
+    static LEVEL: Cell<LevelFilter> = const { Cell::new(LevelFilter::Info) };
+}
+
+static LOGGER: CanisterLogger = CanisterLogger;
+
+/// Installs the canister logger as the global [`log`](https://docs.rs/log) logger, and with the
+/// `tracing` feature as the global `tracing` subscriber, with the given level filter.
+///
+/// Calling it more than once only updates the level filter.
+pub fn init(level: LevelFilter) {
+    let _ = ::log::set_logger(&LOGGER);
+    #[cfg(feature = "tracing")]
+    {
+        let _ = tracing_core::dispatcher::set_global_default(tracing_core::Dispatch::new(
+            CanisterSubscriber::default(),
+        ));
+    }
+    set_level(level);
+}
+
+/// Sets the level filter of the canister logger.
+pub fn set_level(level: LevelFilter) {
+    LEVEL.with(|l| l.set(level));
+    ::log::set_max_level(level);
+    #[cfg(feature = "tracing")]
+    tracing_core::callsite::rebuild_interest_cache();
+}
+
+/// Returns the level filter of the canister logger.
+pub fn level() -> LevelFilter {
+    LEVEL.with(|l| l.get())
+}
+
+/// The [`log::Log`](https://docs.rs/log/latest/log/trait.Log.html) implementation installed by [`init`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CanisterLogger;
+
+impl ::log::Log for CanisterLogger {
+    fn enabled(&self, metadata: &::log::Metadata<'_>) -> bool {
+        metadata.level() <= level()
+    }
+
+    fn log(&self, record: &::log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut line = header(record.level().as_str(), record.target());
+        let _ = write!(line, "{}", record.args());
+        let _ = record.key_values().visit(&mut KeyValues(&mut line));
+        crate::api::print(line);
+    }
+
+    fn flush(&self) {}
+}
+
+struct KeyValues<'a>(&'a mut String);
+
+impl<'kvs> ::log::kv::VisitSource<'kvs> for KeyValues<'_> {
+    fn visit_pair(
+        &mut self,
+        key: ::log::kv::Key<'kvs>,
+        value: ::log::kv::Value<'kvs>,
+    ) -> Result<(), ::log::kv::Error> {
+        let _ = write!(self.0, " {key}={value}");
+        Ok(())
+    }
+}
+
+/// Formats the common prefix of a log line: time, level, method and target.
+fn header(level: &str, target: &str) -> String {
+    let mut line = String::new();
+    let _ = write!(
+        line,
+        "{} {level:<5} {} {target}: ",
+        Timestamp(crate::api::time()),
+        current_method().unwrap_or("-"),
+    );
+    line
+}
+
+/// Formats nanoseconds since the epoch as an RFC 3339 UTC timestamp.
+struct Timestamp(u64);
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0 / 1_000_000_000;
+        let nanos = self.0 % 1_000_000_000;
+        let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+        // Civil date from days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let doe = z % 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + u64::from(month <= 2);
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{nanos:09}Z",
+            secs_of_day / 3_600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+        )
+    }
+}
+
+#[cfg(feature = "tracing")]
+pub use self::tracing::CanisterSubscriber;
+
+#[cfg(feature = "tracing")]
+mod tracing {
+    use super::{header, level};
+    use std::fmt::{self, Write};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tracing_core::field::{Field, Visit};
+    use tracing_core::span::{Attributes, Id, Record};
+    use tracing_core::{Event, Level, LevelFilter, Metadata, Subscriber};
+
+    /// The `tracing` subscriber installed by [`init`](super::init).
+    ///
+    /// Events are written like [`log`](https://docs.rs/log) records; spans are not tracked.
+    #[derive(Debug, Default)]
+    pub struct CanisterSubscriber {
+        next_id: AtomicU64,
+    }
+
+    fn to_level_filter(level: ::log::LevelFilter) -> LevelFilter {
+        match level {
+            ::log::LevelFilter::Off => LevelFilter::OFF,
+            ::log::LevelFilter::Error => LevelFilter::ERROR,
+            ::log::LevelFilter::Warn => LevelFilter::WARN,
+            ::log::LevelFilter::Info => LevelFilter::INFO,
+            ::log::LevelFilter::Debug => LevelFilter::DEBUG,
+            ::log::LevelFilter::Trace => LevelFilter::TRACE,
+        }
+    }
+
+    impl Subscriber for CanisterSubscriber {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            *metadata.level() <= to_level_filter(level())
+        }
+
+        fn max_level_hint(&self) -> Option<LevelFilter> {
+            Some(to_level_filter(level()))
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let metadata = event.metadata();
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            let mut line = header(level_str(metadata.level()), metadata.target());
+            line.push_str(&fields.message);
+            line.push_str(&fields.pairs);
+            crate::api::print(line);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    fn level_str(level: &Level) -> &'static str {
+        match *level {
+            Level::ERROR => "ERROR",
+            Level::WARN => "WARN",
+            Level::INFO => "INFO",
+            Level::DEBUG => "DEBUG",
+            Level::TRACE => "TRACE",
+        }
+    }
+
+    #[derive(Default)]
+    struct Fields {
+        message: String,
+        pairs: String,
+    }
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message.push_str(value);
+            } else {
+                let _ = write!(self.pairs, " {}={value}", field.name());
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.message, "{value:?}");
+            } else {
+                let _ = write!(self.pairs, " {}={value:?}", field.name());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamp() {
+        assert_eq!(Timestamp(0).to_string(), "1970-01-01T00:00:00.000000000Z");
+        assert_eq!(
+            Timestamp(1_734_602_400_123_456_789).to_string(),
+            "2024-12-19T10:00:00.123456789Z"
+        );
+        assert_eq!(
+            Timestamp(951_782_400_000_000_000).to_string(),
+            "2000-02-29T00:00:00.000000000Z"
+        );
+    }
+
+    #[test]
+    fn level_filter() {
+        set_level(LevelFilter::Warn);
+        assert_eq!(level(), LevelFilter::Warn);
+        assert!(::log::Log::enabled(
+            &CanisterLogger,
+            &::log::Metadata::builder()
+                .level(::log::Level::Error)
+                .build()
+        ));
+        assert!(!::log::Log::enabled(
+            &CanisterLogger,
+            &::log::Metadata::builder().level(::log::Level::Info).build()
+        ));
+    }
+}
@@ -0,0 +1,461 @@
+//! Bounded in-canister buffer of log entries.
+//!
+//! Logs written with [`debug_print`](https://internetcomputer.org/docs/current/references/ic-interface-spec#debugging-aids)
+//! are only available to controllers through the management canister, and older entries are dropped
+//! under load. With the `log-buffer` feature, every message passed to [`print`](crate::api::print),
+//! and therefore to [`println!`](crate::println) and [`ic_cdk::log`](crate::log), is also captured into
+//! a ring buffer installed with [`set_log_buffer`]:
+//!
+//! * [`HeapLogBuffer`] keeps the last entries on the Wasm heap.
+//! * [`StableLogBuffer`] keeps them in a fixed region of stable memory, so they survive upgrades.
+//!
+//! The buffer is read with [`get_logs`], or through a query exported by
+//! [`export_get_logs`](crate::export_get_logs) that only controllers may call.
+//!
+//! ```rust,ignore
+//! #[ic_cdk::init]
+//! fn init() {
+//!     ic_cdk::log_buffer::set_log_buffer(ic_cdk::log_buffer::HeapLogBuffer::new(1000));
+//! }
+//!
+//! ic_cdk::export_get_logs!();
+//! ```
+
+use crate::api::stable::{
+    CanisterStableMemory, StableMemory, StableMemoryError, WASM_PAGE_SIZE_IN_BYTES,
+};
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// Default number of entries returned by [`get_logs`].
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Maximum number of entries returned by [`get_logs`].
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+/// A captured log entry.
+#[derive(
+    CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Default,
+)]
+pub struct LogEntry {
+    /// Sequence number of the entry, starting at 0 and never reused.
+    pub index: u64,
+    /// Time the entry was captured, in nanoseconds since the epoch.
+    pub timestamp: u64,
+    /// The logged message.
+    pub message: String,
+}
+
+/// Argument type of [`get_logs`].
+#[derive(
+    CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Default,
+)]
+pub struct GetLogsArgs {
+    /// Index of the first entry to return.
+    ///
+    /// If not specified, or older than the oldest retained entry, starts at the oldest retained entry.
+    pub start: Option<u64>,
+    /// Maximum number of entries to return.
+    ///
+    /// Defaults to [`DEFAULT_PAGE_SIZE`] and is capped at [`MAX_PAGE_SIZE`].
+    pub limit: Option<u32>,
+}
+
+/// Result type of [`get_logs`].
+#[derive(
+    CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Default,
+)]
+pub struct GetLogsResponse {
+    /// The entries, in increasing index order.
+    pub entries: Vec<LogEntry>,
+    /// Index of the oldest retained entry.
+    pub first_index: u64,
+    /// Index to pass as `start` to fetch the next page, if there are more entries.
+    pub next: Option<u64>,
+}
+
+/// Storage backend of the log buffer.
+pub trait LogStore {
+    /// Appends an entry, evicting the oldest one if the buffer is full.
+    fn push(&mut self, timestamp: u64, message: &str);
+
+    /// Index of the oldest retained entry.
+    fn first_index(&self) -> u64;
+
+    /// Index that will be assigned to the next entry.
+    fn next_index(&self) -> u64;
+
+    /// Returns the entry with the given index, if retained.
+    fn get(&self, index: u64) -> Option<LogEntry>;
+}
+
+/// A [`LogStore`] keeping the last `capacity` entries on the Wasm heap.
+#[derive(Debug, Clone, Default)]
+pub struct HeapLogBuffer {
+    capacity: usize,
+    next: u64,
+    entries: VecDeque<LogEntry>,
+}
+
+impl HeapLogBuffer {
+    /// Creates a buffer holding up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next: 0,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+}
+
+impl LogStore for HeapLogBuffer {
+    fn push(&mut self, timestamp: u64, message: &str) {
+        if self.capacity == 0 {
+            self.next += 1;
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            index: self.next,
+            timestamp,
+            message: message.to_string(),
+        });
+        self.next += 1;
+    }
+
+    fn first_index(&self) -> u64 {
+        self.next - self.entries.len() as u64
+    }
+
+    fn next_index(&self) -> u64 {
+        self.next
+    }
+
+    fn get(&self, index: u64) -> Option<LogEntry> {
+        let offset = index.checked_sub(self.first_index())?;
+        self.entries.get(usize::try_from(offset).ok()?).cloned()
+    }
+}
+
+const MAGIC: &[u8; 4] = b"ICLB";
+// magic, slot size (u32), slot count (u32), reserved (u32), next index (u64)
+const HEADER_SIZE: u64 = 24;
+// message length (u32), timestamp (u64)
+const SLOT_HEADER_SIZE: u32 = 12;
+
+/// A [`LogStore`] keeping the last entries in a region of stable memory.
+///
+/// The region starts at `offset` and is made of a small header followed by `slot_count` slots of
+/// `slot_size` bytes each. Messages longer than `slot_size - 12` bytes are truncated.
+///
+/// The region is reused if it already holds a buffer with the same layout, e.g. after an upgrade,
+/// and initialized otherwise. It must not overlap with other data kept in stable memory.
+#[derive(Debug)]
+pub struct StableLogBuffer<M: StableMemory = CanisterStableMemory> {
+    memory: M,
+    offset: u64,
+    slot_size: u32,
+    slot_count: u32,
+    next: u64,
+}
+
+impl<M: StableMemory> StableLogBuffer<M> {
+    /// Opens or initializes a buffer of `slot_count` slots of `slot_size` bytes at `offset`,
+    /// growing the stable memory if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slot_size` is not larger than 12 bytes.
+    pub fn new(
+        memory: M,
+        offset: u64,
+        slot_size: u32,
+        slot_count: u32,
+    ) -> Result<Self, StableMemoryError> {
+        assert!(
+            slot_size > SLOT_HEADER_SIZE,
+            "slot_size must be larger than {SLOT_HEADER_SIZE} bytes"
+        );
+        let end = offset + HEADER_SIZE + u64::from(slot_size) * u64::from(slot_count);
+        let size = memory.stable_size() * WASM_PAGE_SIZE_IN_BYTES;
+        if end > size {
+            memory.stable_grow((end - size).div_ceil(WASM_PAGE_SIZE_IN_BYTES))?;
+        }
+
+        let mut header = [0; HEADER_SIZE as usize];
+        memory.stable_read(offset, &mut header);
+        let mut buffer = Self {
+            memory,
+            offset,
+            slot_size,
+            slot_count,
+            next: 0,
+        };
+        if &header[0..4] == MAGIC
+            && header[4..8] == slot_size.to_le_bytes()
+            && header[8..12] == slot_count.to_le_bytes()
+        {
+            buffer.next = u64::from_le_bytes(header[16..24].try_into().unwrap());
+        } else {
+            header[0..4].copy_from_slice(MAGIC);
+            header[4..8].copy_from_slice(&slot_size.to_le_bytes());
+            header[8..12].copy_from_slice(&slot_count.to_le_bytes());
+            header[12..24].fill(0);
+            buffer.memory.stable_write(offset, &header);
+        }
+        Ok(buffer)
+    }
+
+    fn slot_offset(&self, index: u64) -> u64 {
+        self.offset + HEADER_SIZE + (index % u64::from(self.slot_count)) * u64::from(self.slot_size)
+    }
+}
+
+impl<M: StableMemory> LogStore for StableLogBuffer<M> {
+    fn push(&mut self, timestamp: u64, message: &str) {
+        if self.slot_count > 0 {
+            let max_len = (self.slot_size - SLOT_HEADER_SIZE) as usize;
+            let mut len = message.len().min(max_len);
+            while !message.is_char_boundary(len) {
+                len -= 1;
+            }
+            let mut slot = Vec::with_capacity(SLOT_HEADER_SIZE as usize + len);
+            slot.extend_from_slice(&(len as u32).to_le_bytes());
+            slot.extend_from_slice(&timestamp.to_le_bytes());
+            slot.extend_from_slice(&message.as_bytes()[..len]);
+            self.memory.stable_write(self.slot_offset(self.next), &slot);
+        }
+        self.next += 1;
+        self.memory
+            .stable_write(self.offset + 16, &self.next.to_le_bytes());
+    }
+
+    fn first_index(&self) -> u64 {
+        self.next.saturating_sub(u64::from(self.slot_count))
+    }
+
+    fn next_index(&self) -> u64 {
+        self.next
+    }
+
+    fn get(&self, index: u64) -> Option<LogEntry> {
+        if index < self.first_index() || index >= self.next {
+            return None;
+        }
+        let offset = self.slot_offset(index);
+        let mut header = [0; SLOT_HEADER_SIZE as usize];
+        self.memory.stable_read(offset, &mut header);
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let timestamp = u64::from_le_bytes(header[4..12].try_into().unwrap());
+        let mut message = vec![0; len.min(self.slot_size - SLOT_HEADER_SIZE) as usize];
+        self.memory
+            .stable_read(offset + u64::from(SLOT_HEADER_SIZE), &mut message);
+        Some(LogEntry {
+            index,
+            timestamp,
+            message: String::from_utf8_lossy(&message).into_owned(),
+        })
+    }
+}
+
+thread_local! {
+    static LOG_BUFFER: RefCell<Option<Box<dyn LogStore>>> = RefCell::new(None);
+}
+
+/// Installs the buffer capturing the messages passed to [`print`](crate::api::print).
+///
+/// Replaces any previously installed buffer.
+pub fn set_log_buffer<S: LogStore + 'static>(store: S) {
+    LOG_BUFFER.with(|b| *b.borrow_mut() = Some(Box::new(store)));
+}
+
+/// Captures `message` into the installed buffer, if any.
+pub(crate) fn capture(message: &str) {
+    LOG_BUFFER.with(|b| {
+        // The buffer is already borrowed if printing while accessing it, e.g. in a panic.
+        if let Ok(mut b) = b.try_borrow_mut() {
+            if let Some(store) = b.as_mut() {
+                store.push(crate::api::time(), message);
+            }
+        }
+    });
+}
+
+/// Returns a page of captured entries.
+///
+/// Returns an empty page if no buffer is installed.
+pub fn get_logs(args: GetLogsArgs) -> GetLogsResponse {
+    LOG_BUFFER.with(|b| match b.borrow().as_ref() {
+        Some(store) => page(store.as_ref(), args),
+        None => GetLogsResponse::default(),
+    })
+}
+
+fn page(store: &dyn LogStore, args: GetLogsArgs) -> GetLogsResponse {
+    let first_index = store.first_index();
+    let start = args.start.unwrap_or(first_index).max(first_index);
+    let limit = args.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    let end = store
+        .next_index()
+        .min(start.saturating_add(u64::from(limit)));
+    let entries = (start..end).filter_map(|i| store.get(i)).collect();
+    GetLogsResponse {
+        entries,
+        first_index,
+        next: (end < store.next_index()).then_some(end),
+    }
+}
+
+/// Guard function only allowing controllers of the canister.
+pub fn is_controller() -> Result<(), String> {
+    if crate::api::is_controller(&crate::api::caller()) {
+        Ok(())
+    } else {
+        Err("Only controllers can read the logs.".to_string())
+    }
+}
+
+/// Exports a query returning a page of the captured log entries, see [`get_logs`](crate::log_buffer::get_logs).
+///
+/// The query is named `get_logs` unless another name is given, and is guarded by
+/// [`is_controller`](crate::log_buffer::is_controller). The generated functions are scoped to the
+/// invocation, so the macro can be used more than once in a module with different names. Its Candid
+/// signature is:
+///
+/// ```text
+/// (record { start : opt nat64; limit : opt nat32 }) -> (record {
+///     entries : vec record { index : nat64; timestamp : nat64; message : text };
+///     first_index : nat64;
+///     next : opt nat64;
+/// }) query
+/// ```
+#[macro_export]
+macro_rules! export_get_logs {
+    () => {
+        $crate::export_get_logs!("get_logs");
+    };
+    ($name:tt) => {
+        const _: () = {
+            fn __ic_cdk_get_logs_guard() -> ::std::result::Result<(), ::std::string::String> {
+                $crate::log_buffer::is_controller()
+            }
+
+            #[$crate::query(name = $name, guard = "__ic_cdk_get_logs_guard")]
+            fn __ic_cdk_get_logs(
+                args: $crate::log_buffer::GetLogsArgs,
+            ) -> $crate::log_buffer::GetLogsResponse {
+                $crate::log_buffer::get_logs(args)
+            }
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::stable::tests::TestStableMemory;
+    use std::rc::Rc;
+    use std::sync::Mutex;
+
+    fn messages(response: &GetLogsResponse) -> Vec<&str> {
+        response
+            .entries
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn heap_buffer_evicts_oldest() {
+        let mut buffer = HeapLogBuffer::new(3);
+        for (i, m) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            buffer.push(i as u64, m);
+        }
+        assert_eq!(buffer.first_index(), 2);
+        assert_eq!(buffer.next_index(), 5);
+        assert_eq!(buffer.get(1), None);
+        assert_eq!(
+            buffer.get(3),
+            Some(LogEntry {
+                index: 3,
+                timestamp: 3,
+                message: "d".to_string()
+            })
+        );
+
+        let response = page(&buffer, GetLogsArgs::default());
+        assert_eq!(messages(&response), ["c", "d", "e"]);
+        assert_eq!(response.first_index, 2);
+        assert_eq!(response.next, None);
+    }
+
+    #[test]
+    fn pagination() {
+        let mut buffer = HeapLogBuffer::new(10);
+        for i in 0..5 {
+            buffer.push(i, &i.to_string());
+        }
+        let first = page(
+            &buffer,
+            GetLogsArgs {
+                start: None,
+                limit: Some(2),
+            },
+        );
+        assert_eq!(messages(&first), ["0", "1"]);
+        assert_eq!(first.next, Some(2));
+        let second = page(
+            &buffer,
+            GetLogsArgs {
+                start: first.next,
+                limit: Some(10),
+            },
+        );
+        assert_eq!(messages(&second), ["2", "3", "4"]);
+        assert_eq!(second.next, None);
+    }
+
+    #[test]
+    fn stable_buffer_wraps_and_reopens() {
+        let memory = Rc::new(Mutex::new(Vec::new()));
+        let mut buffer =
+            StableLogBuffer::new(TestStableMemory::new(memory.clone()), 100, 20, 2).unwrap();
+        buffer.push(1, "first");
+        buffer.push(2, "second");
+        buffer.push(3, "third message, truncated");
+        assert_eq!(buffer.first_index(), 1);
+        assert_eq!(buffer.get(0), None);
+        assert_eq!(buffer.get(1).unwrap().message, "second");
+        assert_eq!(
+            buffer.get(2),
+            Some(LogEntry {
+                index: 2,
+                timestamp: 3,
+                message: "third me".to_string()
+            })
+        );
+
+        let reopened =
+            StableLogBuffer::new(TestStableMemory::new(memory.clone()), 100, 20, 2).unwrap();
+        assert_eq!(reopened.next_index(), 3);
+        assert_eq!(
+            messages(&page(&reopened, GetLogsArgs::default())),
+            ["second", "third me"]
+        );
+
+        let resized = StableLogBuffer::new(TestStableMemory::new(memory), 100, 20, 4).unwrap();
+        assert_eq!(resized.next_index(), 0);
+    }
+
+    #[test]
+    fn stable_buffer_truncates_at_char_boundary() {
+        let memory = Rc::new(Mutex::new(Vec::new()));
+        let mut buffer = StableLogBuffer::new(TestStableMemory::new(memory), 0, 14, 1).unwrap();
+        buffer.push(0, "aé");
+        assert_eq!(buffer.get(0).unwrap().message, "a");
+    }
+}
@@ -70,6 +70,23 @@ pub use ic_cdk_macros::export_candid;
 /// }
 /// ```
 ///
+/// A generic function can be exported multiple times, once per `instantiate`.
+/// Each instantiation needs a distinct method `name` and a concrete type for every type parameter.
+/// Associated types of a type parameter must name their trait, e.g. `<T as Storable>::Value`
+/// rather than `T::Value`.
+///
+/// ```rust
+/// # use ic_cdk::query;
+/// #[query(
+///     instantiate(name = "get_nat64", T = u64),
+///     instantiate(name = "get_text", T = String)
+/// )]
+/// fn query_function<T>(key: u32) -> T {
+///     // ...
+/// # unimplemented!()
+/// }
+/// ```
+///
 /// If you would rather call the [`reply()`](crate::api::call::reply) function than return a value,
 /// you will need to set `manual_reply` to `true` so that the canister does not trap.
 ///
@@ -141,6 +158,23 @@ pub use ic_cdk_macros::query;
 /// }
 /// ```
 ///
+/// A generic function can be exported multiple times, once per `instantiate`.
+/// Each instantiation needs a distinct method `name` and a concrete type for every type parameter.
+/// Associated types of a type parameter must name their trait, e.g. `<T as Storable>::Value`
+/// rather than `T::Value`.
+///
+/// ```rust
+/// # use ic_cdk::update;
+/// #[update(
+///     instantiate(name = "put_nat64", T = u64),
+///     instantiate(name = "put_text", T = String)
+/// )]
+/// fn update_function<T>(key: u32, value: T) {
+///     // ...
+/// # unimplemented!()
+/// }
+/// ```
+///
 /// If you would rather call the [`reply()`](crate::api::call::reply) function than return a value,
 /// you will need to set `manual_reply` to `true` so that the canister does not trap.
 ///
@@ -0,0 +1,276 @@
+//! Per-method call metrics.
+//!
+//! With the `metrics` feature enabled, every function exported with [`update`](crate::update)
+//! or [`query`](crate::query) records into a thread-local registry:
+//!
+//! * the number of calls,
+//! * the number of calls rejected by a `guard` function,
+//! * the number of calls that trapped after an `await` (detected during cleanup),
+//! * the instructions spent per completed call, as reported by
+//!   [`call_context_instruction_counter`](crate::api::call_context_instruction_counter).
+//!
+//! The registry lives on the Wasm heap, so it is reset on upgrade. A trap before the
+//! first `await` rolls back the whole message execution, including the recorded call.
+//! Likewise, query calls that are not executed in replicated mode do not persist any
+//! state changes and are therefore not reflected in the registry.
+//!
+//! The metrics can be read with [`method_metrics`] or rendered in the Prometheus text
+//! exposition format with [`encode_prometheus`]. The [`export_metrics`](crate::export_metrics)
+//! macro exports a query returning them as Candid.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Metrics recorded for a single exported method.
+#[derive(
+    CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Default,
+)]
+pub struct MethodMetrics {
+    /// The exported name of the method.
+    pub method: String,
+    /// Number of times the method was called.
+    pub calls: u64,
+    /// Number of calls rejected by the guard function.
+    pub rejects: u64,
+    /// Number of calls that trapped after an `await`.
+    pub traps: u64,
+    /// Total instructions spent by completed calls.
+    pub instructions: u64,
+    /// Largest number of instructions spent by a single completed call.
+    pub max_instructions: u64,
+}
+
+thread_local! {
+    static METHOD_METRICS: RefCell<BTreeMap<&'static str, MethodMetrics>> = RefCell::default();
+}
+
+fn with_method<R>(method: &'static str, f: impl FnOnce(&mut MethodMetrics) -> R) -> R {
+    METHOD_METRICS.with(|m| {
+        let mut m = m.borrow_mut();
+        let entry = m.entry(method).or_insert_with(|| MethodMetrics {
+            method: method.to_string(),
+            ..Default::default()
+        });
+        f(entry)
+    })
+}
+
+/// Records a call to `method`.
+#[doc(hidden)]
+pub fn record_call(method: &'static str) {
+    with_method(method, |m| m.calls += 1);
+}
+
+/// Records a call to `method` rejected by its guard function.
+#[doc(hidden)]
+pub fn record_reject(method: &'static str) {
+    with_method(method, |m| m.rejects += 1);
+}
+
+fn record_trap(method: &'static str) {
+    with_method(method, |m| m.traps += 1);
+}
+
+fn record_instructions(method: &'static str, instructions: u64) {
+    with_method(method, |m| {
+        m.instructions = m.instructions.saturating_add(instructions);
+        m.max_instructions = m.max_instructions.max(instructions);
+    });
+}
+
+/// Guard held by the generated method body for the duration of a call.
+///
+/// On drop it records either a trap, if the call is being cleaned up after a trap,
+/// or the instructions spent by the call context.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct CallScope {
+    method: &'static str,
+}
+
+impl CallScope {
+    /// Starts tracking a call to `method`.
+    pub fn new(method: &'static str) -> Self {
+        Self { method }
+    }
+}
+
+impl Drop for CallScope {
+    fn drop(&mut self) {
+        if crate::api::call::is_recovering_from_trap() {
+            record_trap(self.method);
+        } else {
+            record_instructions(self.method, crate::api::call_context_instruction_counter());
+        }
+    }
+}
+
+/// Returns the metrics of all methods called so far, ordered by method name.
+pub fn method_metrics() -> Vec<MethodMetrics> {
+    METHOD_METRICS.with(|m| m.borrow().values().cloned().collect())
+}
+
+/// Clears all recorded metrics.
+pub fn reset_method_metrics() {
+    METHOD_METRICS.with(|m| m.borrow_mut().clear());
+}
+
+/// A Prometheus metric family: name, type, help text and the value per method.
+type MetricFamily = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&MethodMetrics) -> u64,
+);
+
+/// Renders the metrics of all methods in the Prometheus text exposition format.
+///
+/// The result can be served from an `http_request` query, for example.
+pub fn encode_prometheus() -> String {
+    let metrics = method_metrics();
+    let families: [MetricFamily; 5] = [
+        (
+            "canister_method_calls_total",
+            "counter",
+            "Number of calls per method.",
+            |m| m.calls,
+        ),
+        (
+            "canister_method_rejects_total",
+            "counter",
+            "Number of calls rejected by the guard function per method.",
+            |m| m.rejects,
+        ),
+        (
+            "canister_method_traps_total",
+            "counter",
+            "Number of calls that trapped after an await per method.",
+            |m| m.traps,
+        ),
+        (
+            "canister_method_instructions_total",
+            "counter",
+            "Instructions spent by completed calls per method.",
+            |m| m.instructions,
+        ),
+        (
+            "canister_method_instructions_max",
+            "gauge",
+            "Largest number of instructions spent by a single call per method.",
+            |m| m.max_instructions,
+        ),
+    ];
+    let mut out = String::new();
+    for (name, kind, help, value) in families {
+        // Writing into a `String` never fails.
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        for m in &metrics {
+            let _ = writeln!(
+                out,
+                "{name}{{method=\"{}\"}} {}",
+                escape_label(&m.method),
+                value(m)
+            );
+        }
+    }
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Exports a query returning the recorded [`MethodMetrics`](crate::metrics::MethodMetrics).
+///
+/// The query is named `metrics` unless another name is given. Any `query` attribute
+/// arguments, such as `guard` or `hidden`, can follow the name.
+///
+/// ```rust,ignore
+/// fn is_controller() -> Result<(), String> {
+///     if ic_cdk::api::is_controller(&ic_cdk::caller()) {
+///         Ok(())
+///     } else {
+///         Err("caller is not a controller".to_string())
+///     }
+/// }
+///
+/// ic_cdk::export_metrics!("get_metrics", guard = "is_controller");
+/// ```
+#[macro_export]
+macro_rules! export_metrics {
+    () => {
+        $crate::export_metrics!("metrics");
+    };
+    ($name:tt $(, $($args:tt)*)?) => {
+        #[$crate::query(name = $name $(, $($args)*)?)]
+        fn __ic_cdk_export_metrics() -> ::std::vec::Vec<$crate::metrics::MethodMetrics> {
+            $crate::metrics::method_metrics()
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_per_method() {
+        reset_method_metrics();
+        record_call("b");
+        record_call("a");
+        record_call("a");
+        record_reject("a");
+        record_instructions("a", 100);
+        record_instructions("a", 50);
+        record_trap("b");
+
+        assert_eq!(
+            method_metrics(),
+            vec![
+                MethodMetrics {
+                    method: "a".to_string(),
+                    calls: 2,
+                    rejects: 1,
+                    traps: 0,
+                    instructions: 150,
+                    max_instructions: 100,
+                },
+                MethodMetrics {
+                    method: "b".to_string(),
+                    calls: 1,
+                    traps: 1,
+                    ..Default::default()
+                },
+            ]
+        );
+
+        reset_method_metrics();
+        assert!(method_metrics().is_empty());
+    }
+
+    #[test]
+    fn prometheus_text() {
+        reset_method_metrics();
+        record_call("say \"hi\"");
+        record_instructions("say \"hi\"", 42);
+
+        let text = encode_prometheus();
+        assert!(text.contains("# TYPE canister_method_calls_total counter\n"));
+        assert!(text.contains("canister_method_calls_total{method=\"say \\\"hi\\\"\"} 1\n"));
+        assert!(text.contains("canister_method_instructions_max{method=\"say \\\"hi\\\"\"} 42\n"));
+        assert!(text.contains("canister_method_traps_total{method=\"say \\\"hi\\\"\"} 0\n"));
+    }
+}
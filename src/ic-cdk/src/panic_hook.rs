@@ -0,0 +1,150 @@
+//! Configuration of the panic hook installed by [`setup`](crate::setup).
+//!
+//! When a canister method panics, the hook builds a [`PanicReport`], runs the callbacks registered
+//! with [`add_panic_callback`], prints the report to the canister log and traps with it:
+//!
+//! ```text
+//! Panicked at 'balance too low', src/lib.rs:42:9 in method `transfer` called by 2vxsx-fae
+//! ```
+//!
+//! The method is known when the `method-context` feature is enabled, see
+//! `api::call::current_method`. The caller can be left out with
+//! [`set_panic_context`], e.g. if panics may happen where [`caller`](crate::api::caller) is not
+//! available.
+//!
+//! A trap rolls back every change made by the message execution, including writes to stable memory.
+//! The printed report, which controllers can fetch from the canister logs, is therefore the only
+//! record of the panic that outlives it. Callbacks can add context to the log or clean up
+//! resources, but cannot persist state.
+
+use crate::api;
+use candid::Principal;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+/// Which context is added to panic reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PanicContext {
+    /// Include the method being executed, if known.
+    pub method: bool,
+    /// Include the caller of the method.
+    pub caller: bool,
+}
+
+impl Default for PanicContext {
+    fn default() -> Self {
+        Self {
+            method: true,
+            caller: true,
+        }
+    }
+}
+
+/// A panic, as passed to the callbacks and printed before trapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicReport {
+    /// The panic message.
+    pub message: String,
+    /// The source location of the panic, as `file:line:column`.
+    pub location: Option<String>,
+    /// The method being executed.
+    pub method: Option<&'static str>,
+    /// The caller of the method.
+    pub caller: Option<Principal>,
+}
+
+impl fmt::Display for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Panicked at '{}'", self.message)?;
+        if let Some(location) = &self.location {
+            write!(f, ", {location}")?;
+        }
+        if let Some(method) = self.method {
+            write!(f, " in method `{method}`")?;
+        }
+        if let Some(caller) = &self.caller {
+            write!(f, " called by {caller}")?;
+        }
+        Ok(())
+    }
+}
+
+type PanicCallback = Rc<dyn Fn(&PanicReport)>;
+
+thread_local! {
+    static CONTEXT: Cell<PanicContext> = Cell::new(PanicContext::default());
+    static CALLBACKS: RefCell<Vec<PanicCallback>> = const { RefCell::new(Vec::new()) };
+    static PANICKING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets which context is added to panic reports.
+pub fn set_panic_context(context: PanicContext) {
+    CONTEXT.with(|c| c.set(context));
+}
+
+/// Registers a callback run on every panic, before the canister traps.
+///
+/// Callbacks run in registration order. A panic inside a callback skips the remaining callbacks.
+pub fn add_panic_callback<F: Fn(&PanicReport) + 'static>(callback: F) {
+    CALLBACKS.with(|c| c.borrow_mut().push(Rc::new(callback)));
+}
+
+/// Removes all registered callbacks.
+pub fn clear_panic_callbacks() {
+    CALLBACKS.with(|c| c.borrow_mut().clear());
+}
+
+pub(crate) fn handle(message: String, location: Option<String>) -> ! {
+    // A panic while handling a panic, e.g. in a callback, is reported without context or callbacks.
+    let nested = PANICKING.with(|p| p.replace(true));
+    let context = if nested {
+        PanicContext {
+            method: false,
+            caller: false,
+        }
+    } else {
+        CONTEXT.with(|c| c.get())
+    };
+    #[cfg(feature = "method-context")]
+    let method = api::call::current_method().filter(|_| context.method);
+    #[cfg(not(feature = "method-context"))]
+    let method = None;
+    let report = PanicReport {
+        message,
+        location,
+        method,
+        caller: context.caller.then(api::caller),
+    };
+    if !nested {
+        let callbacks = CALLBACKS.with(|c| c.borrow().clone());
+        for callback in callbacks {
+            callback(&report);
+        }
+    }
+    let err_info = report.to_string();
+    api::print(&err_info);
+    api::trap(&err_info);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let mut report = PanicReport {
+            message: "oops".to_string(),
+            location: Some("src/lib.rs:1:2".to_string()),
+            method: None,
+            caller: None,
+        };
+        assert_eq!(report.to_string(), "Panicked at 'oops', src/lib.rs:1:2");
+        report.method = Some("transfer");
+        report.caller = Some(Principal::anonymous());
+        assert_eq!(
+            report.to_string(),
+            "Panicked at 'oops', src/lib.rs:1:2 in method `transfer` called by 2vxsx-fae"
+        );
+    }
+}
@@ -1,12 +1,12 @@
-use crate::api;
+use crate::panic_hook;
 use std::panic;
 
 /// Sets a custom panic hook, uses debug.trace
 pub fn set_panic_hook() {
     panic::set_hook(Box::new(|info| {
-        let file = info.location().unwrap().file();
-        let line = info.location().unwrap().line();
-        let col = info.location().unwrap().column();
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
 
         let msg = match info.payload().downcast_ref::<&'static str>() {
             Some(s) => *s,
@@ -16,9 +16,7 @@ pub fn set_panic_hook() {
             },
         };
 
-        let err_info = format!("Panicked at '{}', {}:{}:{}", msg, file, line, col);
-        api::print(&err_info);
-        api::trap(&err_info);
+        panic_hook::handle(msg.to_string(), location);
     }));
 }
 
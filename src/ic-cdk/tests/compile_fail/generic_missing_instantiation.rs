@@ -0,0 +1,6 @@
+use ic_cdk::update;
+
+#[update(instantiate(name = "put_nat64", K = u32))]
+fn put<K, V>(_key: K, _value: V) {}
+
+fn main() {}
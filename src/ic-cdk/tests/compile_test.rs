@@ -2,5 +2,7 @@
 fn ui() {
     let t = trybuild::TestCases::new();
     t.pass("tests/pass/*.rs");
+    #[cfg(feature = "log-buffer")]
+    t.pass("tests/pass_log_buffer/*.rs");
     t.compile_fail("tests/compile_fail/*.rs");
 }
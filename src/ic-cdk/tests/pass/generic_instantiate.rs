@@ -0,0 +1,23 @@
+use ic_cdk::{query, update};
+
+#[query(
+    instantiate(name = "get_nat64", T = u64),
+    instantiate(name = "get_text", T = String)
+)]
+fn get<T: Default>(_key: u32) -> Option<T> {
+    Some(T::default())
+}
+
+#[update(instantiate(name = "put_nat64", K = u32, V = u64))]
+async fn put<K, V>(_key: K, _value: V) {}
+
+#[query(
+    composite = true,
+    hidden = true,
+    instantiate(name = "list_nat64", T = u64)
+)]
+fn list<T>() -> Vec<T> {
+    vec![]
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+ic_cdk::export_get_logs!();
+ic_cdk::export_get_logs!("get_logs_v2");
+
+fn main() {}